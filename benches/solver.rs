@@ -0,0 +1,137 @@
+use std::hint::black_box;
+use std::sync::Once;
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use rand::SeedableRng;
+use rand::prelude::SmallRng;
+use route_finder::datastructures::linked_vectors::LinkedVector;
+use route_finder::simulated_annealing::neighbor_move::add_multiple_at_once::AddMultipleNewOrders;
+use route_finder::simulated_annealing::neighbor_move::neighbor_move_trait::NeighborMove;
+use route_finder::simulated_annealing::neighbor_move::remove_multiple_at_once::RemoveMultipleOrders;
+use route_finder::simulated_annealing::neighbor_move::shift_between_days::ShiftBetweenDays;
+use route_finder::simulated_annealing::neighbor_move::shift_in_day::ShiftInDay;
+use route_finder::simulated_annealing::neighbor_move::shift_in_route::ShiftInRoute;
+use route_finder::simulated_annealing::route::Route;
+use route_finder::simulated_annealing::score_calculator::calculate_score;
+use route_finder::simulated_annealing::simulated_annealing::{
+    SimulatedAnnealing, SimulatedAnnealingConfig, TruckEnum,
+};
+use route_finder::simulated_annealing::solution::Solution;
+use route_finder::{get_orders, initialize};
+
+const SEED: u64 = 0;
+const MAX_TRIES: usize = 1_000_000;
+const NO_MOVE: &str = "could not construct this move on the warmed up solution";
+
+static INIT: Once = Once::new();
+
+/// Criterion runs every group in the same process, so we only parse the data once.
+fn init() {
+    INIT.call_once(|| initialize().unwrap());
+}
+
+/// A solution that has been annealed for a bit, so the routes actually contain orders.
+/// We stop early enough that there are still unfilled orders left to add.
+/// Always the same one, because everything is seeded.
+fn warmed_up_solution() -> Solution {
+    let mut rng = SmallRng::seed_from_u64(SEED);
+    let mut annealing =
+        SimulatedAnnealing::new(&mut rng, SimulatedAnnealingConfig::headless(50_000.0, 10.0, 10_000, 0.99));
+    let mut solution = Solution::new();
+    annealing.run_steps(&mut rng, &mut solution, 20_000);
+    solution
+}
+
+fn route_operations(c: &mut Criterion) {
+    init();
+    let order_count = get_orders().len() - 1;
+    let mut route = Route::for_truck(TruckEnum::Truck1);
+    for order_index in 0..order_count.min(20) {
+        let tail = route.linked_vector.get_tail_index().unwrap();
+        let before_tail = route.linked_vector.get_prev_index(tail).unwrap();
+        route.apply_add_order(before_tail, order_index);
+    }
+    let head = route.linked_vector.get_head_index().unwrap();
+    let new_order = order_count.min(20) % order_count;
+
+    c.bench_function("route/calculate_add_order", |b| {
+        b.iter(|| black_box(&route).calculate_add_order(black_box(head), black_box(new_order)))
+    });
+    c.bench_function("route/apply_add_order", |b| {
+        b.iter_batched(
+            || route.clone(),
+            |mut route| route.apply_add_order(black_box(head), black_box(new_order)),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn neighbor_moves(c: &mut Criterion) {
+    init();
+    let solution = warmed_up_solution();
+    let mut rng = SmallRng::seed_from_u64(SEED);
+
+    // the constructors are random and can fail, so we just keep trying until we get one
+    let moves: Vec<(&str, Box<dyn NeighborMove>)> = vec![
+        ("add", Box::new(std::iter::repeat_with(|| {
+            let (_, order_index) = solution.unfilled_orders.get_random(&mut rng)?;
+            AddMultipleNewOrders::new(&solution, &mut rng, *order_index, false)
+        }).take(MAX_TRIES).flatten().next().expect(NO_MOVE))),
+        ("remove", Box::new(std::iter::repeat_with(|| RemoveMultipleOrders::new(&solution, &mut rng))
+            .take(MAX_TRIES).flatten().next().expect(NO_MOVE).0)),
+        ("shift_in_route", Box::new(std::iter::repeat_with(|| ShiftInRoute::new(&solution, &mut rng, false))
+            .take(MAX_TRIES).flatten().next().expect(NO_MOVE))),
+        ("shift_in_day", Box::new(std::iter::repeat_with(|| ShiftInDay::new(&solution, &mut rng, false))
+            .take(MAX_TRIES).flatten().next().expect(NO_MOVE))),
+        ("shift_between_days", Box::new(std::iter::repeat_with(|| ShiftBetweenDays::new(&solution, &mut rng, false))
+            .take(MAX_TRIES).flatten().next().expect(NO_MOVE))),
+    ];
+
+    for (name, neighbor_move) in &moves {
+        c.bench_function(&format!("{name}/evaluate"), |b| {
+            b.iter(|| neighbor_move.evaluate(black_box(&solution)))
+        });
+        c.bench_function(&format!("{name}/apply"), |b| {
+            b.iter_batched(
+                || solution.clone(),
+                |mut solution| neighbor_move.apply(&mut solution),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+fn solution_operations(c: &mut Criterion) {
+    init();
+    let solution = warmed_up_solution();
+    c.bench_function("calculate_score", |b| {
+        b.iter(|| calculate_score(black_box(&solution), &solution.order_flags))
+    });
+    c.bench_function("solution/clone", |b| b.iter(|| black_box(&solution).clone()));
+}
+
+fn annealing_run(c: &mut Criterion) {
+    init();
+    let mut group = c.benchmark_group("annealing");
+    group.sample_size(10);
+    group.bench_function("1_000_000_steps", |b| {
+        b.iter(|| {
+            let mut rng = SmallRng::seed_from_u64(SEED);
+            let mut annealing =
+                SimulatedAnnealing::new(&mut rng, SimulatedAnnealingConfig::headless(50_000.0, 10.0, 50_000, 0.99));
+            let mut solution = Solution::new();
+            annealing.run_steps(&mut rng, &mut solution, 1_000_000);
+            solution.score
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    route_operations,
+    neighbor_moves,
+    solution_operations,
+    annealing_run
+);
+criterion_main!(benches);
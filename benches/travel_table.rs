@@ -0,0 +1,27 @@
+//! The travel table against the distance matrix it replaced, for the lookups every evaluation does.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use route_finder::{get_distance_matrix, get_orders, get_travel_table, initialize};
+
+fn travel_time_lookup(c: &mut Criterion) {
+    initialize().unwrap();
+    let matrix_ids: Vec<_> = get_orders().iter().map(|order| order.matrix_id).collect();
+    let distance_matrix = get_distance_matrix();
+    let travel_table = get_travel_table();
+
+    // This is the lookup we did on every evaluation before the travel table existed.
+    c.bench_function("travel_time/matrix", |b| {
+        b.iter(|| {
+            matrix_ids
+                .windows(2)
+                .map(|w| if w[0] == w[1] { 0 } else { distance_matrix.get_edge_weight(w[0], w[1]).unwrap().travel_time })
+                .sum::<i32>()
+        })
+    });
+    c.bench_function("travel_time/table", |b| {
+        b.iter(|| matrix_ids.windows(2).map(|w| travel_table.travel_time(w[0], w[1])).sum::<i32>())
+    });
+}
+
+criterion_group!(benches, travel_time_lookup);
+criterion_main!(benches);
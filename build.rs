@@ -0,0 +1,31 @@
+//! The depots, the trucks, the objective and the drivers each have their own data file, but these are optional.
+//! This writes a constant for every one of them: the file if it's in data/, or None, so the parser uses its defaults.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const OPTIONAL_FILES: [(&str, &str); 4] = [
+    ("SITE_FILE", "Locaties.txt"),
+    ("TRUCK_FILE", "Wagens.txt"),
+    ("OBJECTIVE_FILE", "Doelfunctie.txt"),
+    ("DRIVER_FILE", "Chauffeurs.txt"),
+];
+
+fn main() {
+    let data_dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("data");
+    // also when a file gets added or removed
+    println!("cargo::rerun-if-changed={}", data_dir.display());
+
+    let mut constants = String::new();
+    for (name, file_name) in OPTIONAL_FILES {
+        let path = data_dir.join(file_name);
+        let value = if path.is_file() {
+            format!("Some(include_str!({:?}))", path.display().to_string())
+        } else {
+            "None".to_string()
+        };
+        constants += &format!("const {name}: Option<&str> = {value};\n");
+    }
+    fs::write(Path::new(&env::var("OUT_DIR").unwrap()).join("optional_data_files.rs"), constants).unwrap();
+}
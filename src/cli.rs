@@ -0,0 +1,220 @@
+//! The command line. Without arguments the GUI opens, the other commands work without it.
+
+use crate::printer::checkpoint::load_checkpoint;
+use crate::printer::diff::{SolutionDiff, write_diff};
+use crate::printer::json::load_solution;
+use crate::printer::score::write_score_breakdown;
+use crate::simulated_annealing::bounds::LowerBound;
+use crate::simulated_annealing::constraints::{Constraint, ConstraintMode, ConstraintModes, is_feasible};
+use crate::simulated_annealing::score_calculator::ScoreBreakdown;
+use crate::simulated_annealing::simulated_annealing::{
+    DEFAULT_ALPHA, DEFAULT_END_TEMP, DEFAULT_Q, DEFAULT_TEMP, SimulatedAnnealing, SimulatedAnnealingConfig,
+};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+use std::error::Error;
+use std::fs::create_dir_all;
+
+pub const USAGE: &str = "\
+Usage:
+    route_finder                          Opens the GUI
+    route_finder search [--seed <seed>] [--start <solution>] [--hard <constraint>]...
+                                          Searches without the GUI, with the default parameters.
+                                          A hard constraint is never broken more, the others cost a penalty.
+                                          Either way, orders get taken out of the plans until they're feasible,
+                                          unless the locks keep them broken.
+                                          The constraints are day, capacity, driving and week
+    route_finder resume <checkpoint>      Carries on with the search in a checkpoint.json
+    route_finder diff <before> <after>    Shows what changed between two solutions
+    route_finder score <solution>         Shows what the score of a solution is made of, and its gap to the lower bound
+    route_finder help                     Shows this
+
+A search writes to a new folder in output, and checkpoints itself there every now and then.
+Killing it loses at most the work since the last checkpoint.";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Gui,
+    Search { seed: Option<u64>, start_solution: Option<String>, constraint_modes: ConstraintModes },
+    Resume { checkpoint: String },
+    Diff { before: String, after: String },
+    Score { solution: String },
+    Help,
+}
+
+/// Parses the arguments, without the program name.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let Some(command) = args.next() else {
+        return Ok(Command::Gui);
+    };
+
+    match command.as_str() {
+        "search" => {
+            let mut seed = None;
+            let mut start_solution = None;
+            let mut constraint_modes = ConstraintModes::default();
+            while let Some(flag) = args.next() {
+                let mut value = || args.next().ok_or_else(|| format!("{flag} needs a value"));
+                match flag.as_str() {
+                    "--seed" => {
+                        let value = value()?;
+                        seed = Some(value.parse().map_err(|_| format!("\"{value}\" is not a seed"))?);
+                    }
+                    "--start" => start_solution = Some(value()?),
+                    "--hard" => {
+                        let value = value()?;
+                        let constraint = Constraint::from_name(&value).ok_or_else(|| format!("\"{value}\" is not a constraint"))?;
+                        constraint_modes.set(constraint, ConstraintMode::Hard);
+                    }
+                    _ => return Err(format!("Unknown option \"{flag}\"")),
+                }
+            }
+            Ok(Command::Search { seed, start_solution, constraint_modes })
+        }
+        "resume" => {
+            let checkpoint = args.next().ok_or("resume needs the path to a checkpoint")?;
+            if let Some(extra) = args.next() {
+                return Err(format!("Unexpected argument \"{extra}\""));
+            }
+            Ok(Command::Resume { checkpoint })
+        }
+        "diff" => {
+            let before = args.next().ok_or("diff needs two solutions")?;
+            let after = args.next().ok_or("diff needs two solutions")?;
+            if let Some(extra) = args.next() {
+                return Err(format!("Unexpected argument \"{extra}\""));
+            }
+            Ok(Command::Diff { before, after })
+        }
+        "score" => {
+            let solution = args.next().ok_or("score needs a solution")?;
+            if let Some(extra) = args.next() {
+                return Err(format!("Unexpected argument \"{extra}\""));
+            }
+            Ok(Command::Score { solution })
+        }
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        _ => Err(format!("Unknown command \"{command}\"")),
+    }
+}
+
+/// Runs any command but the GUI on this thread, searches run until they're done.
+/// The orders and distance matrix have to be initialized already.
+pub fn run_headless(command: Command) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if let Command::Diff { before, after } = command {
+        let diff = SolutionDiff::new(&load_solution(&before)?, &load_solution(&after)?);
+        write_diff(&mut std::io::stdout().lock(), &diff)?;
+        return Ok(());
+    }
+    if let Command::Score { solution } = command {
+        let solution = load_solution(&solution)?;
+        let breakdown = ScoreBreakdown::new(&solution);
+        write_score_breakdown(&mut std::io::stdout().lock(), &breakdown)?;
+        println!();
+        println!("{}", LowerBound::new().describe_gap(breakdown.total, is_feasible(&solution)));
+        return Ok(());
+    }
+
+    let mut config = SimulatedAnnealingConfig::headless(DEFAULT_TEMP, DEFAULT_END_TEMP, DEFAULT_Q, DEFAULT_ALPHA);
+    let mut checkpoint = None;
+    match command {
+        Command::Search { seed, start_solution, constraint_modes } => {
+            config.seed = seed;
+            config.constraint_modes = constraint_modes;
+            config.start_solution = start_solution.as_deref().map(load_solution).transpose()?;
+        }
+        Command::Resume { checkpoint: path } => checkpoint = Some(load_checkpoint(&path)?),
+        Command::Gui | Command::Help | Command::Diff { .. } | Command::Score { .. } => return Err("That command doesn't search".into()),
+    }
+
+    create_dir_all("output")?;
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut search = SimulatedAnnealing::new(&mut rng, config);
+    if let Some(checkpoint) = &checkpoint {
+        search.resume_from(checkpoint)?;
+        println!("Resuming in \"{}\" at step {}", checkpoint.output_dir, checkpoint.step_count);
+    }
+    search.insanely_large_stuffloop();
+    println!("best score: {}", search.best_solution.score as f32 / 6000f32);
+    if let Some(summary) = search.best_repair.summary() {
+        println!("{summary}");
+    }
+    println!("{}", LowerBound::new().describe_gap(search.best_solution.score, is_feasible(&search.best_solution)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, parse_args};
+    use crate::simulated_annealing::constraints::{Constraint, ConstraintMode, ConstraintModes};
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_opens_the_gui() {
+        assert_eq!(parse(&[]), Ok(Command::Gui));
+    }
+
+    #[test]
+    fn search_options() {
+        assert_eq!(
+            parse(&["search"]),
+            Ok(Command::Search { seed: None, start_solution: None, constraint_modes: ConstraintModes::default() })
+        );
+        assert_eq!(
+            parse(&["search", "--start", "output/0 5421.txt", "--seed", "42"]),
+            Ok(Command::Search {
+                seed: Some(42),
+                start_solution: Some("output/0 5421.txt".to_string()),
+                constraint_modes: ConstraintModes::default(),
+            })
+        );
+        assert!(parse(&["search", "--seed"]).is_err());
+        assert!(parse(&["search", "--seed", "lots"]).is_err());
+        assert!(parse(&["search", "--fast"]).is_err());
+    }
+
+    #[test]
+    fn hard_constraints_can_be_repeated() {
+        let mut constraint_modes = ConstraintModes::default();
+        constraint_modes.set(Constraint::Capacity, ConstraintMode::Hard);
+        constraint_modes.set(Constraint::WeeklyTime, ConstraintMode::Hard);
+        assert_eq!(
+            parse(&["search", "--hard", "capacity", "--hard", "week"]),
+            Ok(Command::Search { seed: None, start_solution: None, constraint_modes })
+        );
+        assert!(parse(&["search", "--hard"]).is_err());
+        assert!(parse(&["search", "--hard", "weekend"]).is_err());
+    }
+
+    #[test]
+    fn resume_needs_exactly_one_checkpoint() {
+        assert_eq!(
+            parse(&["resume", "output/run/checkpoint.json"]),
+            Ok(Command::Resume { checkpoint: "output/run/checkpoint.json".to_string() })
+        );
+        assert!(parse(&["resume"]).is_err());
+        assert!(parse(&["resume", "a", "b"]).is_err());
+        assert!(parse(&["serch"]).is_err());
+    }
+
+    #[test]
+    fn diff_needs_two_solutions() {
+        assert_eq!(
+            parse(&["diff", "a.json", "b.txt"]),
+            Ok(Command::Diff { before: "a.json".to_string(), after: "b.txt".to_string() })
+        );
+        assert!(parse(&["diff", "a.json"]).is_err());
+        assert!(parse(&["diff", "a", "b", "c"]).is_err());
+    }
+
+    #[test]
+    fn score_needs_one_solution() {
+        assert_eq!(parse(&["score", "a.json"]), Ok(Command::Score { solution: "a.json".to_string() }));
+        assert!(parse(&["score"]).is_err());
+        assert!(parse(&["score", "a", "b"]).is_err());
+    }
+}
@@ -5,7 +5,7 @@ use rand::prelude::IndexedRandom;
 /// This is a linkedlist inside a Vector. It works with Node<t>
 /// Each Node contains its own index in the vector, and the index of the next and previous Node
 /// If you want to make use of the get_random function, make sure to use it in this order.
-/// ```rust
+/// ```rust,ignore
 /// let mut lv = CompactLinkedVector::new();
 /// let mut rng = rand::rng();
 /// loop {
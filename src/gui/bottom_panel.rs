@@ -7,7 +7,9 @@ pub fn show_bottom_panel(ui: &mut egui::Ui, app: &mut GuiApp) {
             .num_columns(5)
             .striped(true)
             .show(ui, |ui| {
-                for i in 0..app.num_threads {
+                // a resumed checkpoint runs fewer threads than the setting
+                let thread_count = if app.search_handle.is_empty() { app.num_threads } else { app.search_handle.len() };
+                for i in 0..thread_count {
                     ui.radio_value(&mut app.drawn_thread, i, format!("Thread {}", i));
                     // TODO: Replace with real data
                     let status = {
@@ -21,7 +23,19 @@ pub fn show_bottom_panel(ui: &mut egui::Ui, app: &mut GuiApp) {
                             "Idle"
                         }
                     };
-                    ui.label(status);
+                    ui.horizontal(|ui| {
+                        ui.label(status);
+                        // what the repair at the end of the last iteration did
+                        if let Some(repair) = app.repairs.get(i)
+                            && let Some(summary) = repair.summary()
+                        {
+                            if repair.failure.is_some() {
+                                ui.colored_label(egui::Color32::RED, "Infeasible").on_hover_text(summary);
+                            } else {
+                                ui.label("Repaired").on_hover_text(summary);
+                            }
+                        }
+                    });
                     ui.label(format!("Score: {}", app.cur_score.get(i).unwrap_or(&0.0)));
                     ui.label(format!("Temp.: {}", app.cur_temp.get(i).unwrap_or(&0.0)));
                     ui.label(format!("Q: {}", app.cur_q.get(i).unwrap_or(&0)));
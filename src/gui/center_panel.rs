@@ -1,11 +1,41 @@
 use super::GuiApp;
-use crate::get_orders;
+use crate::{get_orders, get_sites};
+use crate::simulated_annealing::route::OrderIndex;
+use egui::emath::TSTransform;
 use egui::{Color32, Pos2, Sense, Stroke, Ui};
 
+/// How close to a customer, in pixels, a click or drop has to be to hit it
+const PICK_RADIUS: f32 = 8.0;
+
+/// The map. Clicking a customer selects it. While editing a plan, dragging a customer onto another one
+/// moves its stop in the selected routes to right after that one, anywhere else dragging pans the map.
 pub fn show_center_panel(ctx: &egui::Context, ui: &mut Ui, app: &mut GuiApp) {
-    let (response, painter) = ui.allocate_painter(ui.available_size_before_wrap(), Sense::drag());
+    let (response, painter) = ui.allocate_painter(ui.available_size_before_wrap(), Sense::click_and_drag());
+
+    let orders = get_orders();
+    let pointer = response.interact_pointer_pos().or(response.hover_pos());
+    let sites = get_sites();
 
-    app.camera.translation += response.drag_delta();
+    if response.clicked() {
+        app.selected_order = order_at(app.camera, pointer).filter(|order_index| !sites.is_site(*order_index));
+    }
+    if response.drag_started() && app.plan_editor.is_some() {
+        app.dragged_order = order_at(app.camera, pointer).filter(|order_index| !sites.is_site(*order_index));
+    }
+    if app.dragged_order.is_none() {
+        app.camera.translation += response.drag_delta();
+    }
+    if response.drag_stopped()
+        && let Some(dragged) = app.dragged_order.take()
+        && let Some(target) = order_at(app.camera, pointer).filter(|target| *target != dragged)
+        && let Some(editor) = &app.plan_editor
+    {
+        match editor.edit_for_drop(app.route_selection.iter().copied(), dragged, target) {
+            Ok(edit) => app.edit_plan(edit),
+            Err(error) => app.plan_editor.as_mut().unwrap().message = error,
+        }
+        app.selected_order = Some(dragged);
+    }
 
     // Zoom handling: pinch (touch) and scroll wheel.
     {
@@ -52,7 +82,6 @@ pub fn show_center_panel(ctx: &egui::Context, ui: &mut Ui, app: &mut GuiApp) {
         }
     }
 
-    let orders = get_orders();
     let route_lines = routes.iter().map(|route| {
         egui::Shape::line(
             route
@@ -68,14 +97,53 @@ pub fn show_center_panel(ctx: &egui::Context, ui: &mut Ui, app: &mut GuiApp) {
     });
 
     painter.extend(route_lines);
-    let shapes = get_orders().iter().map(|o| {
+    let unplanned = |order_index: OrderIndex| {
+        app.plan_editor
+            .as_ref()
+            .is_some_and(|editor| editor.solution.order_flags.get_flag(order_index) == 0)
+    };
+    let shapes = orders.iter().enumerate().map(|(order_index, o)| {
         let screen_pos = app.camera * Pos2::new(o.x_coordinate as f32, o.y_coordinate as f32);
-        let (colour, radius) = match o.matrix_id.index() {
-            287 => (Color32::GREEN, 3.5), // Maarheeze, the dump site
-            _ => (Color32::BLUE, 2.0),
+        let (colour, radius) = match &o.site {
+            Some(site) if site.is_dump => (Color32::GREEN, 3.5),
+            Some(_) => (Color32::DARK_GREEN, 3.5),
+            // Only known while editing, the search threads don't send their unfilled orders
+            None if unplanned(order_index) => (Color32::RED, 2.0),
+            None => (Color32::BLUE, 2.0),
         };
         egui::Shape::circle_filled(screen_pos, radius, colour)
     });
 
     painter.extend(shapes);
+
+    let screen_pos = |order_index: OrderIndex| {
+        let order = &orders[order_index];
+        app.camera * Pos2::new(order.x_coordinate as f32, order.y_coordinate as f32)
+    };
+    if let Some(selected) = app.selected_order {
+        painter.circle_stroke(screen_pos(selected), 5.0, Stroke::new(2.0_f32, Color32::YELLOW));
+    }
+    if let Some(dragged) = app.dragged_order
+        && let Some(pointer) = pointer
+    {
+        painter.line_segment([screen_pos(dragged), pointer], Stroke::new(1.5_f32, Color32::YELLOW));
+        if let Some(target) = order_at(app.camera, Some(pointer)) {
+            painter.circle_stroke(screen_pos(target), 5.0, Stroke::new(2.0_f32, Color32::YELLOW));
+        }
+    }
+}
+
+/// The customer or dump site closest to the position on the screen, if it's close enough to hit.
+fn order_at(camera: TSTransform, pos: Option<Pos2>) -> Option<OrderIndex> {
+    let pos = pos?;
+    get_orders()
+        .iter()
+        .enumerate()
+        .map(|(order_index, o)| {
+            let screen_pos = camera * Pos2::new(o.x_coordinate as f32, o.y_coordinate as f32);
+            (order_index, screen_pos.distance(pos))
+        })
+        .filter(|(_, distance)| *distance <= PICK_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(order_index, _)| order_index)
 }
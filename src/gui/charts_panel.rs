@@ -0,0 +1,141 @@
+use super::GuiApp;
+use crate::resource::MINUTE;
+use crate::simulated_annealing::simulated_annealing::ProgressPoint;
+use egui::{Align2, Color32, FontId, Pos2, Rect, Sense, Shape, Stroke, StrokeKind, Ui, Vec2};
+
+/// One colour per thread, they repeat when there are more threads
+const THREAD_COLOURS: [Color32; 8] = [
+    Color32::from_rgb(31, 119, 180),
+    Color32::from_rgb(255, 127, 14),
+    Color32::from_rgb(44, 160, 44),
+    Color32::from_rgb(214, 39, 40),
+    Color32::from_rgb(148, 103, 189),
+    Color32::from_rgb(140, 86, 75),
+    Color32::from_rgb(227, 119, 194),
+    Color32::from_rgb(23, 190, 207),
+];
+
+const MARGIN: f32 = 4.0;
+const TEXT_HEIGHT: f32 = 14.0;
+
+struct Line {
+    name: String,
+    colour: Color32,
+    /// (seconds, value), sorted by seconds
+    points: Vec<(f32, f32)>,
+}
+
+/// Line charts of everything the search threads send every TRACE_INTERVAL steps, with one line per thread.
+pub fn show_charts_panel(ui: &mut Ui, app: &GuiApp) {
+    if app.progress.iter().all(Vec::is_empty) {
+        ui.label("The charts fill up once a search is running");
+        return;
+    }
+
+    let minutes = |time: i32| time as f32 / MINUTE as f32;
+    let mut score_lines = lines(app, "", |point| minutes(point.score));
+    // the best score in a lighter colour of the same thread
+    score_lines.extend(lines(app, " best", |point| minutes(point.best_score)).into_iter().map(|line| Line {
+        colour: line.colour.gamma_multiply(0.5),
+        ..line
+    }));
+
+    ui.columns(4, |columns| {
+        line_chart(&mut columns[0], "Score (min)", score_lines, false);
+        line_chart(&mut columns[1], "Temperature", lines(app, "", |point| point.temp), true);
+        line_chart(&mut columns[2], "Acceptance rate", lines(app, "", |point| point.acceptance_rate), false);
+        line_chart(&mut columns[3], "Unfilled orders", lines(app, "", |point| point.unfilled_orders as f32), false);
+    });
+}
+
+fn lines(app: &GuiApp, suffix: &str, value: impl Fn(&ProgressPoint) -> f32) -> Vec<Line> {
+    app.progress
+        .iter()
+        .enumerate()
+        .map(|(thread, progress)| Line {
+            name: format!("Thread {thread}{suffix}"),
+            colour: THREAD_COLOURS[thread % THREAD_COLOURS.len()],
+            points: progress.iter().map(|point| (point.seconds, value(point))).collect(),
+        })
+        .collect()
+}
+
+/// Draws the lines with the time in seconds on the x axis. A log scale is for things that decay exponentially, like the temperature.
+fn line_chart(ui: &mut Ui, title: &str, lines: Vec<Line>, log_scale: bool) {
+    let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::hover());
+    let rect = response.rect;
+    let text_colour = ui.visuals().text_color();
+    let font = FontId::proportional(11.0);
+
+    painter.rect_stroke(rect, 2.0, ui.visuals().widgets.noninteractive.bg_stroke, StrokeKind::Inside);
+    painter.text(rect.left_top() + Vec2::splat(MARGIN), Align2::LEFT_TOP, title, FontId::proportional(12.0), text_colour);
+
+    let scale = |value: f32| if log_scale { value.max(f32::MIN_POSITIVE).log10() } else { value };
+    let unscale = |value: f32| if log_scale { 10f32.powf(value) } else { value };
+
+    let all_points = || lines.iter().flat_map(|line| line.points.iter());
+    let Some(max_seconds) = all_points().map(|(seconds, _)| *seconds).reduce(f32::max) else {
+        return;
+    };
+    let mut min_value = all_points().map(|(_, value)| scale(*value)).fold(f32::INFINITY, f32::min);
+    let mut max_value = all_points().map(|(_, value)| scale(*value)).fold(f32::NEG_INFINITY, f32::max);
+    if (max_value - min_value).abs() < f32::EPSILON {
+        min_value -= 1.0;
+        max_value += 1.0;
+    }
+    let max_seconds = max_seconds.max(1.0);
+
+    let plot = Rect::from_min_max(
+        rect.left_top() + Vec2::new(MARGIN, MARGIN + TEXT_HEIGHT),
+        rect.right_bottom() - Vec2::new(MARGIN, MARGIN + TEXT_HEIGHT),
+    );
+    let to_screen = |(seconds, value): (f32, f32)| {
+        Pos2::new(
+            plot.left() + seconds / max_seconds * plot.width(),
+            plot.bottom() - (scale(value) - min_value) / (max_value - min_value) * plot.height(),
+        )
+    };
+
+    painter.text(plot.right_top(), Align2::RIGHT_BOTTOM, format_value(unscale(max_value)), font.clone(), text_colour);
+    painter.text(plot.left_bottom(), Align2::LEFT_TOP, format_value(unscale(min_value)), font.clone(), text_colour);
+    painter.text(plot.right_bottom(), Align2::RIGHT_TOP, format!("{max_seconds:.0} s"), font, text_colour);
+
+    for line in &lines {
+        // There's no use in drawing more than a couple of points per pixel
+        let stride = (line.points.len() / (2 * plot.width().max(1.0) as usize)).max(1);
+        let mut points: Vec<Pos2> = line.points.iter().step_by(stride).map(|point| to_screen(*point)).collect();
+        if let Some(last) = line.points.last()
+            && (line.points.len() - 1) % stride != 0
+        {
+            points.push(to_screen(*last));
+        }
+        painter.add(Shape::line(points, Stroke::new(1.5_f32, line.colour)));
+    }
+
+    // The value of every line at the time under the pointer
+    if let Some(pointer) = response.hover_pos()
+        && plot.contains(pointer)
+    {
+        let seconds = (pointer.x - plot.left()) / plot.width() * max_seconds;
+        painter.line_segment(
+            [Pos2::new(pointer.x, plot.top()), Pos2::new(pointer.x, plot.bottom())],
+            Stroke::new(1.0_f32, Color32::GRAY),
+        );
+        let mut text = format!("{seconds:.0} s");
+        for line in &lines {
+            let index = line.points.partition_point(|(point_seconds, _)| *point_seconds < seconds);
+            if let Some((_, value)) = line.points.get(index.min(line.points.len().saturating_sub(1))) {
+                text.push_str(&format!("\n{}: {}", line.name, format_value(*value)));
+            }
+        }
+        response.on_hover_text_at_pointer(text);
+    }
+}
+
+fn format_value(value: f32) -> String {
+    if value.abs() >= 100.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.3}")
+    }
+}
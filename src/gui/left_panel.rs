@@ -1,19 +1,30 @@
+use std::error::Error;
+use std::fs::create_dir_all;
 use std::sync::Arc;
 
 use super::GuiApp;
+use crate::printer::checkpoint::{Checkpoint, load_checkpoint};
+use crate::printer::geojson::print_geojson;
+use crate::printer::json::{RunInfo, load_solution, print_solution_json};
+use crate::printer::manifest::print_manifest;
+use crate::resource::MINUTE;
+use crate::simulated_annealing::constraints::{Constraint, ConstraintMode, Repair};
 use crate::simulated_annealing::simulated_annealing::{
-    SimulatedAnnealing, SimulatedAnnealingConfig,
+    SimulatedAnnealing, SimulatedAnnealingConfig, TruckEnum,
 };
+use crate::simulated_annealing::solution::Solution;
+use crate::simulated_annealing::week::Week;
 use egui::Ui;
 use flume::bounded;
 use rand::SeedableRng;
 use rand::rngs::SmallRng;
+use time::OffsetDateTime;
 
 pub fn show_left_panel(ui: &mut Ui, app: &mut GuiApp, ctx: &egui::Context) {
     ui.vertical_centered(|ui| ui.heading("Controls"));
     ui.separator();
     ui.horizontal(|ui| {
-        if !app.search_handle.is_empty() && app.search_handle.iter().all(|t| !t.is_finished()) {
+        if app.is_searching() {
             {
                 // println!(
                 //     "Search thread result: {:?}",
@@ -36,38 +47,77 @@ pub fn show_left_panel(ui: &mut Ui, app: &mut GuiApp, ctx: &egui::Context) {
                 );
             }
         } else if ui.button("Start search").clicked() {
+            let resume = match load_resume_checkpoint(&app.resume_path) {
+                Ok(resume) => resume,
+                Err(error) => {
+                    println!("Could not load the checkpoint: {error}");
+                    return;
+                }
+            };
+            // A plan that was edited by hand takes the place of the start solution
+            let edited_plan = app.plan_editor.take().map(|editor| editor.solution);
+            let start_solution = match (&resume, edited_plan) {
+                (Some(_), _) => None,
+                (None, Some(plan)) => Some(plan),
+                (None, None) => match load_start_solution(&app.start_solution_path) {
+                    Ok(start_solution) => start_solution,
+                    Err(error) => {
+                        println!("Could not load the start solution: {error}");
+                        return;
+                    }
+                },
+            };
+            app.search_locks = match (&resume, &start_solution) {
+                (Some(checkpoint), _) => checkpoint.current.to_solution().map(|solution| solution.locks).unwrap_or_default(),
+                (None, Some(solution)) => solution.locks.clone(),
+                (None, None) => Default::default(),
+            };
+            // A checkpoint is the state of a single search thread
+            let thread_count = if resume.is_some() { 1 } else { app.num_threads };
             app.score_rec.clear();
             app.q_rec.clear();
             app.temp_rec.clear();
             app.route_rec.clear();
+            app.progress_rec.clear();
+            app.progress.clear();
+            app.repair_rec.clear();
+            app.repairs.clear();
             app.pause_channel.clear();
             app.stop_channel.clear();
             app.search_handle.clear();
-            for idx in 0..app.num_threads {
+            for idx in 0..thread_count {
                 let (pause_snd, pause_rec) = bounded(1);
                 let (stop_snd, stop_rec) = bounded(1);
-                let (score_sender, score_rec) = bounded(app.num_threads);
-                let (q_sender, q_rec) = bounded(app.num_threads);
-                let (temp_sender, temp_rec) = bounded(app.num_threads);
-                let (route_sender, route_rec) = bounded(app.num_threads);
+                let (score_sender, score_rec) = bounded(thread_count);
+                let (q_sender, q_rec) = bounded(thread_count);
+                let (temp_sender, temp_rec) = bounded(thread_count);
+                let (route_sender, route_rec) = bounded(thread_count);
+                // Big enough that no point gets lost when the GUI is slow for a moment
+                let (progress_sender, progress_rec) = bounded(256);
+                let (repair_sender, repair_rec) = bounded(1);
                 app.score_rec.push(score_rec);
                 app.q_rec.push(q_rec);
                 app.temp_rec.push(temp_rec);
                 app.route_rec.push(route_rec);
+                app.progress_rec.push(progress_rec);
+                app.progress.push(vec![]);
+                app.repair_rec.push(repair_rec);
+                app.repairs.push(Repair::default());
                 app.pause_channel.push((pause_snd, pause_rec));
                 app.stop_channel.push((stop_snd, stop_rec));
-                app.cur_score = vec![0.0; app.num_threads];
-                app.cur_q = vec![0; app.num_threads];
-                app.cur_temp = vec![0.0; app.num_threads];
+                app.cur_score = vec![0.0; thread_count];
+                app.cur_q = vec![0; thread_count];
+                app.cur_temp = vec![0.0; thread_count];
                 app.cur_route = vec![
-                    (Arc::new(Default::default()), Arc::new(Default::default()));
-                    app.num_threads
+                    (Arc::new(Week::new(TruckEnum::Truck1)), Arc::new(Week::new(TruckEnum::Truck2)));
+                    thread_count
                 ];
                 let mut rng = SmallRng::seed_from_u64(0);
                 let mut the_thing = SimulatedAnnealing::new(
                     &mut rng,
                     SimulatedAnnealingConfig {
                         idx,
+                        thread_count,
                         temp: app.temp,
                         end_temp: app.end_temp,
                         q: app.q,
@@ -79,8 +129,20 @@ pub fn show_left_panel(ui: &mut Ui, app: &mut GuiApp, ctx: &egui::Context) {
                         q_sender: q_sender.clone(),
                         temp_sender: temp_sender.clone(),
                         route_sender: route_sender.clone(),
+                        progress_sender,
+                        repair_sender,
+                        start_solution: start_solution.clone(),
+                        paranoid_interval: app.paranoid.then_some(app.paranoid_interval),
+                        seed: None,
+                        constraint_modes: app.constraint_modes,
                     },
                 );
+                if let Some(checkpoint) = &resume
+                    && let Err(error) = the_thing.resume_from(checkpoint)
+                {
+                    println!("Could not resume the checkpoint: {error}");
+                    return;
+                }
                 app.search_handle.push(std::thread::spawn(move || {
                     the_thing.insanely_large_stuffloop();
                 }));
@@ -92,6 +154,44 @@ pub fn show_left_panel(ui: &mut Ui, app: &mut GuiApp, ctx: &egui::Context) {
             });
         }
     });
+    ui.horizontal(|ui| {
+        if app.plan_editor.is_none() {
+            let edit_button = ui.add_enabled(!app.is_searching(), egui::Button::new("Edit plan")).on_hover_text(
+                "Edit the drawn routes by hand, or the start solution if there are none. Starting a search carries on from the edited plan",
+            );
+            if edit_button.clicked() {
+                let plan = match app.cur_route.get(app.drawn_thread) {
+                    Some((truck1, truck2)) => {
+                        let mut plan = Solution::from_trucks((**truck1).clone(), (**truck2).clone());
+                        plan.locks = app.search_locks.clone();
+                        Ok(Some(plan))
+                    }
+                    None => load_start_solution(&app.start_solution_path),
+                };
+                match plan {
+                    Ok(plan) => app.start_editing(plan.unwrap_or_default()),
+                    Err(error) => println!("Could not load the start solution: {error}"),
+                }
+            }
+        } else {
+            if ui.add_enabled(app.plan_editor.as_ref().is_some_and(|editor| editor.can_undo()), egui::Button::new("Undo")).clicked() {
+                app.undo_edit();
+            }
+            if ui.button("Save plan").on_hover_text("Writes the edited plan to a new folder in the output folder").clicked()
+                && let Some(editor) = &app.plan_editor
+            {
+                let now = OffsetDateTime::now_local().unwrap();
+                let dir = format!("output/{now} edited").replace(":", "_");
+                match create_dir_all(&dir).and_then(|_| print_solution_json(&editor.solution, &dir, 0, RunInfo::default())) {
+                    Ok(()) => println!("Wrote the edited plan to {dir}"),
+                    Err(error) => println!("Could not write the edited plan: {error}"),
+                }
+            }
+            if ui.button("Stop editing").clicked() {
+                app.plan_editor = None;
+            }
+        }
+    });
     ui.label("Searching overview");
     for (idx, score_rec) in app.score_rec.iter().enumerate() {
         if let Ok(cur_score) = score_rec.try_recv() {
@@ -104,6 +204,14 @@ pub fn show_left_panel(ui: &mut Ui, app: &mut GuiApp, ctx: &egui::Context) {
             app.cur_temp[idx] = cur_temp;
         }
     }
+    for (idx, progress_rec) in app.progress_rec.iter().enumerate() {
+        app.progress[idx].extend(progress_rec.try_iter());
+    }
+    for (idx, repair_rec) in app.repair_rec.iter().enumerate() {
+        if let Ok(repair) = repair_rec.try_recv() {
+            app.repairs[idx] = repair;
+        }
+    }
     for (idx, q_rec) in app.q_rec.iter().enumerate() {
         if let Ok(cur_q) = q_rec.try_recv() {
             app.cur_q[idx] = cur_q;
@@ -121,6 +229,20 @@ pub fn show_left_panel(ui: &mut Ui, app: &mut GuiApp, ctx: &egui::Context) {
                     .to_string(),
             );
             ui.end_row();
+            if let Some(point) = app.progress.get(app.drawn_thread).and_then(|progress| progress.last()) {
+                ui.label("Best score:");
+                ui.label((point.best_score as f32 / MINUTE as f32).to_string());
+                ui.end_row();
+                ui.label("Gap:");
+                let bound = format!("No plan can score below the lower bound of {:.1}", app.lower_bound.total as f32 / MINUTE as f32);
+                match app.lower_bound.gap(point.best_score, point.best_feasible) {
+                    Some(gap) => ui.label(format!("{:.2}%", gap * 100.0)).on_hover_text(bound),
+                    None => ui
+                        .label("-")
+                        .on_hover_text(format!("{bound}, but not for this one: {}", app.lower_bound.caveat(point.best_feasible).unwrap_or_default())),
+                };
+                ui.end_row();
+            }
             ui.label("Temperature:");
             ui.label(
                 app.cur_temp
@@ -155,6 +277,95 @@ pub fn show_left_panel(ui: &mut Ui, app: &mut GuiApp, ctx: &egui::Context) {
                         .speed(0.01),
                 );
                 ui.end_row();
+                ui.label("Start solution:");
+                ui.text_edit_singleline(&mut app.start_solution_path)
+                    .on_hover_text("A .txt or .json file from the output folder. Leave empty to start from scratch");
+                ui.end_row();
+                ui.label("Resume from:");
+                ui.text_edit_singleline(&mut app.resume_path)
+                    .on_hover_text("A checkpoint.json from a run in the output folder, resumed in a single thread. Leave empty to start a new search");
+                ui.end_row();
+            });
+    });
+    ui.collapsing("Constraints", |ui| {
+        for constraint in Constraint::ALL {
+            let mut hard = app.constraint_modes.get(constraint) == ConstraintMode::Hard;
+            let label = format!("Hard {}", constraint.name());
+            if ui
+                .checkbox(&mut hard, label)
+                .on_hover_text(
+                    "Never accept a move that breaks this more, but a start solution that breaks it stays broken. \
+                    Otherwise it costs a penalty that adapts during the search. \
+                    Either way, the plan of every iteration gets orders taken out until it's feasible, unless the locks keep it broken",
+                )
+                .changed()
+            {
+                let mode = if hard { ConstraintMode::Hard } else { ConstraintMode::Soft };
+                app.constraint_modes.set(constraint, mode);
+            }
+        }
+    });
+    ui.collapsing("View", |ui| {
+        ui.checkbox(&mut app.show_timeline, "Timeline")
+            .on_hover_text("Shows the stops of every day of the drawn thread over time");
+        ui.checkbox(&mut app.show_charts, "Charts")
+            .on_hover_text("Shows how the score, temperature, acceptance rate and unfilled orders of every thread develop");
+    });
+    ui.collapsing("Export", |ui| {
+        egui::Grid::new("export_params")
+            .num_columns(2)
+            .show(ui, |ui| {
+                let transform = &mut app.geojson_transform;
+                ui.label("x scale:");
+                ui.add(egui::DragValue::new(&mut transform.x_scale).speed(1e-8));
+                ui.end_row();
+                ui.label("x offset:");
+                ui.add(egui::DragValue::new(&mut transform.x_offset));
+                ui.end_row();
+                ui.label("y scale:");
+                ui.add(egui::DragValue::new(&mut transform.y_scale).speed(1e-8));
+                ui.end_row();
+                ui.label("y offset:");
+                ui.add(egui::DragValue::new(&mut transform.y_offset));
+                ui.end_row();
+            });
+        if ui.button("Export GeoJSON").on_hover_text("Writes the routes of the drawn thread to the output folder").clicked()
+            && let Some((truck1, truck2)) = app.cur_route.get(app.drawn_thread)
+        {
+            let now = OffsetDateTime::now_local().unwrap();
+            let path = format!("output/{now}.geojson").replace(":", "_");
+            match create_dir_all("output").and_then(|_| print_geojson(truck1, truck2, &path, &app.geojson_transform)) {
+                Ok(()) => println!("Wrote the routes to {path}"),
+                Err(error) => println!("Could not write the GeoJSON: {error}"),
+            }
+        }
+        if ui.button("Export manifests").on_hover_text("Writes a CSV and a printable HTML page with the stops of every route").clicked()
+            && let Some((truck1, truck2)) = app.cur_route.get(app.drawn_thread)
+        {
+            let now = OffsetDateTime::now_local().unwrap();
+            let dir = format!("output/{now} manifest").replace(":", "_");
+            match create_dir_all(&dir).and_then(|_| print_manifest(truck1, truck2, &dir)) {
+                Ok(()) => println!("Wrote the manifests to {dir}"),
+                Err(error) => println!("Could not write the manifests: {error}"),
+            }
+        }
+    });
+    ui.collapsing("Debugging", |ui| {
+        egui::Grid::new("debugging_params")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Paranoid mode:");
+                ui.checkbox(&mut app.paranoid, "")
+                    .on_hover_text("Checks the whole solution every few steps, and writes it to the output folder if something is wrong");
+                ui.end_row();
+                ui.label("Check every:");
+                ui.add_enabled(
+                    app.paranoid,
+                    egui::DragValue::new(&mut app.paranoid_interval)
+                        .range(1..=u32::MAX)
+                        .suffix(" steps"),
+                );
+                ui.end_row();
             });
     });
     ui.collapsing("Multithreading", |ui| {
@@ -162,14 +373,35 @@ pub fn show_left_panel(ui: &mut Ui, app: &mut GuiApp, ctx: &egui::Context) {
             .num_columns(2)
             .show(ui, |ui| {
                 ui.label("Number of threads:");
-                ui.add(
+                let resuming = !app.resume_path.trim().is_empty();
+                ui.add_enabled(
+                    !resuming,
                     egui::DragValue::new(&mut app.num_threads).range(
                         1..=std::thread::available_parallelism()
                             .map(|n| n.get())
                             .unwrap_or(32),
                     ),
-                );
+                )
+                .on_disabled_hover_text("A checkpoint is resumed in a single thread");
                 ui.end_row();
             });
     });
 }
+
+fn load_start_solution(path: &str) -> Result<Option<Solution>, Box<dyn Error + Send + Sync>> {
+    let path = path.trim();
+    if path.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(load_solution(path)?))
+    }
+}
+
+fn load_resume_checkpoint(path: &str) -> Result<Option<Checkpoint>, Box<dyn Error + Send + Sync>> {
+    let path = path.trim();
+    if path.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(load_checkpoint(path)?))
+    }
+}
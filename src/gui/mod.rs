@@ -3,7 +3,16 @@ use std::sync::Arc;
 use std::thread::JoinHandle;
 
 use crate::get_orders;
-use crate::simulated_annealing::route::Route;
+use crate::printer::geojson::CoordinateTransform;
+use crate::simulated_annealing::bounds::LowerBound;
+use crate::simulated_annealing::constraints::{ConstraintModes, Repair};
+use crate::simulated_annealing::locks::Locks;
+use crate::simulated_annealing::manual_edit::ManualEdit;
+use crate::simulated_annealing::route::{OrderIndex, Route};
+use crate::simulated_annealing::simulated_annealing::{
+    DEFAULT_ALPHA, DEFAULT_END_TEMP, DEFAULT_Q, DEFAULT_TEMP, ProgressPoint,
+};
+use crate::simulated_annealing::solution::Solution;
 use crate::simulated_annealing::week::Week;
 use crate::simulated_annealing::{day::TimeOfDay, simulated_annealing::TruckEnum, week::DayEnum};
 use egui::Vec2;
@@ -12,13 +21,19 @@ use flume::{Receiver, Sender};
 
 mod bottom_panel;
 mod center_panel;
+mod charts_panel;
 mod left_panel;
+mod plan_editor;
 mod right_panel;
+mod timeline_panel;
 
 pub use bottom_panel::show_bottom_panel;
 pub use center_panel::show_center_panel;
+pub use charts_panel::show_charts_panel;
 pub use left_panel::show_left_panel;
+pub use plan_editor::PlanEditor;
 pub use right_panel::show_right_panel;
+pub use timeline_panel::show_timeline_panel;
 
 #[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
 struct RouteSelection {
@@ -37,6 +52,25 @@ pub struct GuiApp {
     pub end_temp: f32,
     pub q: u32,
     pub alpha: f32,
+    /// A .txt or .json solution to start from. Empty means starting from an empty schedule.
+    pub start_solution_path: String,
+    /// A checkpoint.json to resume. When this is filled in, the start solution and parameters are ignored.
+    pub resume_path: String,
+    /// Which constraints the search may never break
+    pub constraint_modes: ConstraintModes,
+    /// What no plan can beat, to show how good the best score is
+    pub lower_bound: LowerBound,
+
+    // Debugging parameters
+    pub paranoid: bool,
+    pub paranoid_interval: u32,
+
+    // View parameters
+    pub show_timeline: bool,
+    pub show_charts: bool,
+
+    // Export parameters
+    pub geojson_transform: CoordinateTransform,
 
     // Multithreading parameters
     pub num_threads: usize,
@@ -55,6 +89,21 @@ pub struct GuiApp {
     pub route_rec: Vec<Receiver<(Arc<Week>, Arc<Week>)>>,
     // The currently displayed route
     pub cur_route: Vec<(Arc<Week>, Arc<Week>)>,
+    pub progress_rec: Vec<Receiver<ProgressPoint>>,
+    // Everything every thread has sent since the search started, for the charts
+    pub progress: Vec<Vec<ProgressPoint>>,
+    pub repair_rec: Vec<Receiver<Repair>>,
+    // What the repair did to the last plan of every thread
+    pub repairs: Vec<Repair>,
+
+    // Editing by hand, only while no search is running
+    pub plan_editor: Option<PlanEditor>,
+    // The locks of the solution the search started from. The threads only send their routes,
+    // so editing their result again needs these.
+    pub search_locks: Locks,
+    // The customer that was clicked on the map
+    pub selected_order: Option<OrderIndex>,
+    dragged_order: Option<OrderIndex>,
 }
 
 impl GuiApp {
@@ -72,10 +121,19 @@ impl GuiApp {
                 translation: -Vec2::new(min_x as f32, min_y as f32) * 0.0001,
             },
             route_selection: BTreeSet::new(),
-            temp: 50_000.0,
-            end_temp: 10.0,
-            q: 500_000,
-            alpha: 0.99,
+            temp: DEFAULT_TEMP,
+            end_temp: DEFAULT_END_TEMP,
+            q: DEFAULT_Q,
+            alpha: DEFAULT_ALPHA,
+            start_solution_path: "output/0 5421.txt".to_string(),
+            resume_path: String::new(),
+            constraint_modes: ConstraintModes::default(),
+            lower_bound: LowerBound::new(),
+            paranoid: false,
+            paranoid_interval: 100_000,
+            show_timeline: false,
+            show_charts: false,
+            geojson_transform: CoordinateTransform::default(),
             num_threads: std::thread::available_parallelism()
                 .map(|n| n.get())
                 .unwrap_or(4),
@@ -91,8 +149,60 @@ impl GuiApp {
             cur_temp: vec![],
             route_rec: vec![],
             cur_route: vec![],
+            progress_rec: vec![],
+            progress: vec![],
+            repair_rec: vec![],
+            repairs: vec![],
+            plan_editor: None,
+            search_locks: Locks::default(),
+            selected_order: None,
+            dragged_order: None,
+        }
+    }
+
+    pub fn is_searching(&self) -> bool {
+        !self.search_handle.is_empty() && self.search_handle.iter().all(|t| !t.is_finished())
+    }
+
+    /// Starts editing the solution by hand, and shows it instead of the search threads.
+    pub fn start_editing(&mut self, solution: Solution) {
+        // Whatever the threads still had on its way would overwrite the plan
+        self.score_rec.clear();
+        self.q_rec.clear();
+        self.temp_rec.clear();
+        self.route_rec.clear();
+        self.progress_rec.clear();
+        self.repair_rec.clear();
+        self.plan_editor = Some(PlanEditor::new(solution));
+        self.show_plan();
+    }
+
+    /// Makes the edit on the plan, if there is one, and shows the result.
+    pub fn edit_plan(&mut self, edit: ManualEdit) {
+        if let Some(editor) = &mut self.plan_editor
+            && editor.edit(edit)
+        {
+            self.show_plan();
+        }
+    }
+
+    pub fn undo_edit(&mut self) {
+        if let Some(editor) = &mut self.plan_editor {
+            editor.undo();
+            self.show_plan();
         }
     }
+
+    fn show_plan(&mut self) {
+        let Some(editor) = &self.plan_editor else {
+            return;
+        };
+        self.drawn_thread = 0;
+        self.cur_route = vec![editor.trucks()];
+        self.cur_score = vec![editor.solution.score as f32 / 6000.0];
+        self.cur_temp = vec![0.0];
+        self.cur_q = vec![0];
+    }
 }
 
 impl eframe::App for GuiApp {
@@ -101,10 +211,6 @@ impl eframe::App for GuiApp {
             show_left_panel(ui, self, ctx);
         });
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            show_center_panel(ctx, ui, self);
-        });
-
         egui::SidePanel::right("inspector").show(ctx, |ui| {
             show_right_panel(ui, self);
         });
@@ -112,6 +218,29 @@ impl eframe::App for GuiApp {
         egui::TopBottomPanel::bottom("multithreading_info").show(ctx, |ui| {
             show_bottom_panel(ui, self);
         });
+
+        if self.show_charts {
+            egui::TopBottomPanel::bottom("charts")
+                .resizable(true)
+                .default_height(200.0)
+                .show(ctx, |ui| {
+                    show_charts_panel(ui, self);
+                });
+        }
+
+        if self.show_timeline {
+            egui::TopBottomPanel::bottom("timeline")
+                .resizable(true)
+                .default_height(260.0)
+                .show(ctx, |ui| {
+                    show_timeline_panel(ui, self);
+                });
+        }
+
+        // The central panel has to come last, it takes whatever space the other panels leave
+        egui::CentralPanel::default().show(ctx, |ui| {
+            show_center_panel(ctx, ui, self);
+        });
     }
 }
 
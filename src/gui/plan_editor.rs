@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use super::RouteSelection;
+use crate::datastructures::linked_vectors::LinkedVector;
+use crate::{get_orders, get_sites};
+use crate::resource::MINUTE;
+use crate::simulated_annealing::manual_edit::ManualEdit;
+use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
+use crate::simulated_annealing::neighbor_move::route_position::RoutePosition;
+use crate::simulated_annealing::route::OrderIndex;
+use crate::simulated_annealing::solution::Solution;
+use crate::simulated_annealing::week::Week;
+
+/// A plan that's being edited by hand while no search is running.
+/// The map, timeline and inspector show it in place of a search thread.
+pub struct PlanEditor {
+    pub solution: Solution,
+    /// The plan before every edit, newest last
+    history: Vec<Solution>,
+    /// What the last edit did, or why it couldn't be done
+    pub message: String,
+}
+
+impl PlanEditor {
+    pub fn new(solution: Solution) -> Self {
+        Self {
+            solution,
+            history: vec![],
+            message: "Drag a stop onto another one to move it there".to_string(),
+        }
+    }
+
+    /// Applies the edit if it can be done. The plan gets checked completely afterwards,
+    /// an edit that breaks something is undone right away.
+    pub fn edit(&mut self, edit: ManualEdit) -> bool {
+        let before = self.solution.clone();
+        let result = edit
+            .apply(&mut self.solution)
+            .and_then(|evaluation| self.solution.check_consistency().map(|_| evaluation));
+        match result {
+            Ok(evaluation) => {
+                self.message = describe(&edit, &before, &evaluation);
+                self.history.push(before);
+                true
+            }
+            Err(error) => {
+                self.solution = before;
+                self.message = error;
+                false
+            }
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(solution) = self.history.pop() {
+            // Locks aren't edits, they stay like they are now
+            let locks = std::mem::take(&mut self.solution.locks);
+            self.solution = solution;
+            self.solution.locks = locks;
+            self.message = "Undid the last edit".to_string();
+        }
+    }
+
+    pub fn edit_count(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn trucks(&self) -> (Arc<Week>, Arc<Week>) {
+        (Arc::new(self.solution.truck1.clone()), Arc::new(self.solution.truck2.clone()))
+    }
+
+    /// The edit for dragging one order onto another one on the map. Only the selected routes can be edited:
+    /// the stop and the place it goes to are looked up in those.
+    /// Dropping onto a depot or dump site puts the stop at the start of the first selected route.
+    pub(super) fn edit_for_drop(
+        &self,
+        selection: impl Iterator<Item = RouteSelection> + Clone,
+        dragged: OrderIndex,
+        target: OrderIndex,
+    ) -> Result<ManualEdit, String> {
+        let orders = get_orders();
+        let sites = get_sites();
+        let positions_of = |order_index: OrderIndex| {
+            selection.clone().filter_map(move |selected| {
+                let route = self.solution.get_truck(selected.truck).get(selected.day).get(selected.shift);
+                let lv = &route.linked_vector;
+                let node_index = if sites.is_site(order_index) {
+                    lv.get_head_index()
+                } else {
+                    lv.iter().find(|(_, order_i)| **order_i == order_index).map(|(node_i, _)| node_i)
+                }?;
+                Some(RoutePosition {
+                    truck: selected.truck,
+                    day: selected.day,
+                    time_of_day: selected.shift,
+                    node_index,
+                })
+            })
+        };
+
+        let after = positions_of(target)
+            .next()
+            .ok_or_else(|| format!("Order {} is not in any of the selected routes", orders[target].order))?;
+        if self.solution.order_flags.get_flag(dragged) == 0 {
+            return Ok(ManualEdit::Insert { order: dragged, after: Some(after) });
+        }
+        let stops: Vec<RoutePosition> = positions_of(dragged).collect();
+        let stop = stops
+            .iter()
+            .find(|stop| stop.day == after.day)
+            .or(stops.first())
+            .ok_or_else(|| format!("Select a route that visits order {} to move it", orders[dragged].order))?;
+        Ok(ManualEdit::Move { stop: *stop, after })
+    }
+}
+
+fn describe(edit: &ManualEdit, before: &Solution, evaluation: &Evaluation) -> String {
+    let orders = get_orders();
+    let minutes = |time: i32| format!("{:+.1} min", time as f32 / MINUTE as f32);
+    let what = match edit {
+        ManualEdit::Move { stop, .. } => {
+            let order = stop.order(before).map(|order_index| orders[order_index].order);
+            format!("Moved order {}", order.unwrap_or_default())
+        }
+        ManualEdit::Remove { order } => format!("Removed order {}", orders[*order].order),
+        ManualEdit::Insert { order, .. } => format!("Inserted order {}", orders[*order].order),
+    };
+    format!(
+        "{what}: score {}, time overflow {}, capacity overflow {:+}L",
+        minutes(evaluation.cost),
+        minutes(evaluation.time_overflow_delta),
+        evaluation.capacity_overflow_delta
+    )
+}
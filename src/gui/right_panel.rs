@@ -1,6 +1,11 @@
 use super::GuiApp;
 use crate::datastructures::linked_vectors::LinkedVector;
 use crate::get_orders;
+use crate::resource::MINUTE;
+use crate::simulated_annealing::locks::OrderLock;
+use crate::simulated_annealing::manual_edit::ManualEdit;
+use crate::simulated_annealing::order_day_flags::OrderFlags;
+use crate::simulated_annealing::score_calculator::ScoreBreakdown;
 use crate::simulated_annealing::{day::TimeOfDay, simulated_annealing::TruckEnum, week::DayEnum};
 use egui::{Color32, Ui};
 use time::Time;
@@ -9,6 +14,10 @@ pub fn show_right_panel(ui: &mut Ui, app: &mut GuiApp) {
     ui.vertical_centered(|ui| ui.heading("Inspector"));
     ui.separator();
     egui::ScrollArea::vertical().show(ui, |ui| {
+        if app.plan_editor.is_some() {
+            plan_editor(ui, app);
+            ui.separator();
+        }
         ui.collapsing("Routes", |ui| {
             if ui.button("Deselect all").clicked() {
                 app.route_selection.clear();
@@ -88,7 +97,7 @@ pub fn show_right_panel(ui: &mut Ui, app: &mut GuiApp) {
                             .show(ui, |ui| {
                                 let route = super::route_selection_to_route(routes, selection);
                                 ui.label("Trash collected:");
-                                if route.capacity > 100_000 {
+                                if route.capacity_overflow() > 0 {
                                     ui.colored_label(
                                         Color32::RED,
                                         format!("{}L, (OVERFLOW)", route.capacity),
@@ -97,6 +106,11 @@ pub fn show_right_panel(ui: &mut Ui, app: &mut GuiApp) {
                                     ui.label(format!("{}L", route.capacity));
                                 };
                                 ui.end_row();
+                                if route.unload_stops > 0 {
+                                    ui.label("Unload stops:");
+                                    ui.label(route.unload_stops.to_string());
+                                    ui.end_row();
+                                }
                                 ui.label("Time (h:m:s):");
                                 let total_seconds = route.time as u32 / 100;
                                 let hours = total_seconds / 3600;
@@ -107,13 +121,37 @@ pub fn show_right_panel(ui: &mut Ui, app: &mut GuiApp) {
                                 ui.label("Orders fulfilled:");
                                 ui.label(route.linked_vector.len().to_string());
                                 ui.end_row();
+                                ui.label("Frozen:");
+                                let mut locks = app.plan_editor.as_mut().map(|editor| &mut editor.solution.locks);
+                                let mut frozen = locks.as_deref().unwrap_or(&app.search_locks).is_frozen(
+                                    selection.truck,
+                                    selection.day,
+                                    selection.shift,
+                                );
+                                let checkbox = ui
+                                    .add_enabled(locks.is_some(), egui::Checkbox::without_text(&mut frozen))
+                                    .on_hover_text("Nothing gets added to, removed from or moved in a frozen route. Edit the plan to change this");
+                                if checkbox.changed()
+                                    && let Some(locks) = &mut locks
+                                {
+                                    locks.set_frozen(selection.truck, selection.day, selection.shift, frozen);
+                                }
+                                ui.end_row();
                             });
                         },
                     );
                 }
             }
         });
+        ui.collapsing("Selected order", |ui| {
+            selected_order(ui, app);
+        });
         ui.separator();
+        ui.collapsing("Score", |ui| {
+            if let Some((truck1, truck2)) = app.cur_route.get(app.drawn_thread) {
+                score_breakdown(ui, &ScoreBreakdown::from_trucks(truck1, truck2));
+            }
+        });
         ui.collapsing("Week", |ui| {
             if let Some(routes) = &app.cur_route.get(app.drawn_thread) {
                 egui::Grid::new("week_overview")
@@ -197,8 +235,8 @@ pub fn show_right_panel(ui: &mut Ui, app: &mut GuiApp) {
                                     combined_route.time = morning_route.time + afternoon_route.time;
                                     (
                                         combined_route,
-                                        morning_route.capacity > 100_000
-                                            || afternoon_route.capacity > 100_000,
+                                        morning_route.capacity_overflow() > 0
+                                            || afternoon_route.capacity_overflow() > 0,
                                     )
                                 };
                                 egui::Grid::new(format!("day_overview_{:?}_{:?}", truck, day))
@@ -274,3 +312,191 @@ pub fn show_right_panel(ui: &mut Ui, app: &mut GuiApp) {
         });
     });
 }
+
+fn plan_editor(ui: &mut Ui, app: &mut GuiApp) {
+    let Some(editor) = &app.plan_editor else {
+        return;
+    };
+    ui.label(format!("Editing by hand, {} edits", editor.edit_count()));
+    ui.label(&editor.message);
+    ui.small("Only the selected routes can be edited. Dropping a stop on the dump site puts it at the start of the first selected route");
+}
+
+/// Everything about the customer that was clicked on the map, and when it's collected in the drawn routes.
+fn selected_order(ui: &mut Ui, app: &mut GuiApp) {
+    let Some(order_index) = app.selected_order else {
+        ui.label("Click a customer on the map");
+        return;
+    };
+    let order = &get_orders()[order_index];
+    egui::Grid::new("selected_order")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Order:");
+            ui.label(order.order.to_string());
+            ui.end_row();
+            ui.label("Place:");
+            ui.label(&order.place);
+            ui.end_row();
+            ui.label("Frequency:");
+            ui.label(format!("{} times a week", order.frequency as u8));
+            ui.end_row();
+            ui.label("Allowed days:");
+            let patterns: Vec<String> = order
+                .allowed_patterns
+                .iter()
+                .map(|pattern| {
+                    let days: Vec<String> = OrderFlags::flags_to_days(*pattern).iter().map(|day| format!("{day:?}")[..3].to_string()).collect();
+                    days.join("+")
+                })
+                .collect();
+            ui.label(patterns.join(" | "));
+            ui.end_row();
+            ui.label("Containers:");
+            ui.label(format!("{} x {}L = {}L", order.container_count, order.container_volume, order.total_container_volume));
+            ui.end_row();
+            ui.label("Emptying time:");
+            ui.label(format!("{:.1} min", order.emptying_time as f32 / MINUTE as f32));
+            ui.end_row();
+            ui.label("Lock:");
+            match &mut app.plan_editor {
+                Some(editor) => {
+                    let locks = &mut editor.solution.locks;
+                    let mut lock = locks.order(order_index);
+                    egui::ComboBox::from_id_salt("order_lock")
+                        .selected_text(format!("{lock:?}"))
+                        .show_ui(ui, |ui| {
+                            for option in OrderLock::ALL {
+                                ui.selectable_value(&mut lock, option, format!("{option:?}"));
+                            }
+                        })
+                        .response
+                        .on_hover_text("Day keeps the days, Truck also keeps the truck and Position keeps the order where it is");
+                    locks.set_order(order_index, lock);
+                }
+                None => {
+                    ui.label(format!("{:?}", app.search_locks.order(order_index)))
+                        .on_hover_text("Edit the plan to change this");
+                }
+            }
+            ui.end_row();
+        });
+
+    let mut planned = false;
+    if let Some(routes) = app.cur_route.get(app.drawn_thread) {
+        for truck in [TruckEnum::Truck1, TruckEnum::Truck2] {
+            for day in DayEnum::ALL {
+                for shift in [TimeOfDay::Morning, TimeOfDay::Afternoon] {
+                    let week = if truck == TruckEnum::Truck1 { &routes.0 } else { &routes.1 };
+                    let route = week.get(day).get(shift);
+                    if let Some(stop) = route.linked_vector.iter().position(|(_, o)| *o == order_index) {
+                        ui.label(format!("{truck:?}, {day:?} {shift}, stop {stop}"));
+                        planned = true;
+                    }
+                }
+            }
+        }
+    }
+    if !planned {
+        ui.label("Not planned");
+    }
+
+    if app.plan_editor.is_some() {
+        if planned {
+            if ui.button("Remove from the plan").clicked() {
+                app.edit_plan(ManualEdit::Remove { order: order_index });
+            }
+        } else if ui.button("Insert where it's cheapest").on_hover_text("Drag it onto a stop to insert it right after that one instead").clicked() {
+            app.edit_plan(ManualEdit::Insert { order: order_index, after: None });
+        }
+    }
+}
+
+fn score_breakdown(ui: &mut Ui, breakdown: &ScoreBreakdown) {
+    let minutes = |time: i32| format!("{:.1} min", time as f32 / MINUTE as f32);
+    egui::Grid::new("score_breakdown")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Total:");
+            ui.label(minutes(breakdown.total));
+            ui.end_row();
+            ui.label("Driving:");
+            ui.label(minutes(breakdown.driving_time));
+            ui.end_row();
+            ui.label("Emptying:");
+            ui.label(minutes(breakdown.emptying_time));
+            ui.end_row();
+            ui.label("Dropoff:");
+            ui.label(minutes(breakdown.dropoff_time));
+            ui.end_row();
+            ui.label("Breaks:").on_hover_text("The breaks the drivers have to take on long drives");
+            ui.label(minutes(breakdown.break_time));
+            ui.end_row();
+            ui.label("Objective:").on_hover_text("What the kilometres, fuel and CO2 are worth");
+            ui.label(minutes(breakdown.objective_cost));
+            ui.end_row();
+            ui.label("Balance:").on_hover_text("What it costs that some days or one truck have more work than the others");
+            ui.label(minutes(breakdown.balance_cost));
+            ui.end_row();
+            ui.label("Penalty:");
+            ui.label(format!("{} ({} orders)", minutes(breakdown.penalty), breakdown.unfinished_orders.len()));
+            ui.end_row();
+            ui.label("Time overflow:");
+            if breakdown.time_overflow > 0 {
+                ui.colored_label(Color32::RED, minutes(breakdown.time_overflow));
+            } else {
+                ui.label(minutes(0));
+            }
+            ui.end_row();
+            ui.label("Capacity overflow:");
+            if breakdown.capacity_overflow > 0 {
+                ui.colored_label(Color32::RED, format!("{}L", breakdown.capacity_overflow));
+            } else {
+                ui.label("0L");
+            }
+            ui.end_row();
+            ui.label("Driving overflow:").on_hover_text("How much longer than allowed the drivers drive without a rest");
+            if breakdown.continuous_driving_overflow > 0 {
+                ui.colored_label(Color32::RED, minutes(breakdown.continuous_driving_overflow));
+            } else {
+                ui.label(minutes(0));
+            }
+            ui.end_row();
+            ui.label("Weekly overflow:").on_hover_text("How much longer than allowed the drivers work in the week");
+            if breakdown.weekly_overflow > 0 {
+                ui.colored_label(Color32::RED, minutes(breakdown.weekly_overflow));
+            } else {
+                ui.label(minutes(0));
+            }
+            ui.end_row();
+            ui.label("Distance:");
+            ui.label(format!("{:.1} km", breakdown.emissions.km));
+            ui.end_row();
+            ui.label("Fuel:");
+            ui.label(format!("{:.1} L", breakdown.emissions.liters));
+            ui.end_row();
+            ui.label("CO2:");
+            ui.label(format!("{:.1} kg", breakdown.emissions.kg_co2));
+            ui.end_row();
+        });
+    for truck in [TruckEnum::Truck1, TruckEnum::Truck2] {
+        ui.collapsing(format!("{:?}: {}", truck, minutes(breakdown.truck_total(truck))), |ui| {
+            egui::Grid::new(format!("score_breakdown_{:?}", truck))
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for day in breakdown.days.iter().filter(|day| day.truck == truck) {
+                        ui.label(format!("{:?}:", day.day));
+                        ui.label(minutes(day.total())).on_hover_text(format!(
+                            "driving {}, emptying {}, dropoff {}, breaks {}, {:.1} km",
+                            minutes(day.driving_time),
+                            minutes(day.emptying_time),
+                            minutes(day.dropoff_time),
+                            minutes(day.break_time),
+                            day.emissions.km
+                        ));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
@@ -0,0 +1,119 @@
+use super::GuiApp;
+use crate::printer::manifest::{ManifestStop, START_OF_DAY, build_manifest, clock_time};
+use crate::resource::{FULL_DAY, MINUTE, TRUCK_CAPACITY, Time};
+use crate::simulated_annealing::{day::TimeOfDay, simulated_annealing::TruckEnum, week::DayEnum};
+use egui::{Align2, Color32, FontId, Pos2, Rect, Sense, Stroke, StrokeKind, Ui, Vec2};
+
+const LABEL_WIDTH: f32 = 120.0;
+const HEADER_HEIGHT: f32 = 16.0;
+const HOUR: Time = 60 * MINUTE;
+
+/// Every day of both trucks as a row of bars: thin ones for driving, thick ones for emptying containers
+/// and green ones for the dropoff. Whatever happens after the end of the working day is red.
+pub fn show_timeline_panel(ui: &mut Ui, app: &GuiApp) {
+    let Some((truck1, truck2)) = app.cur_route.get(app.drawn_thread) else {
+        ui.label("Start a search to see the timeline");
+        return;
+    };
+    let mut stops = build_manifest(truck1, TruckEnum::Truck1);
+    stops.extend(build_manifest(truck2, TruckEnum::Truck2));
+
+    let end_of_day = START_OF_DAY + FULL_DAY;
+    // Make room for days that are too long, so the overflow is visible
+    let last = stops.iter().map(|stop| stop.departure).max().unwrap_or(end_of_day).max(end_of_day) + HOUR / 2;
+
+    let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::hover());
+    let rect = response.rect;
+    let timeline = Rect::from_min_max(rect.min + Vec2::new(LABEL_WIDTH, HEADER_HEIGHT), rect.max);
+    let x = |time: Time| timeline.left() + (time - START_OF_DAY) as f32 / (last - START_OF_DAY) as f32 * timeline.width();
+    let row_height = timeline.height() / (DayEnum::ALL.len() * 2) as f32;
+
+    let text_colour = ui.visuals().text_color();
+    let grid_stroke = ui.visuals().widgets.noninteractive.bg_stroke;
+    for hour in (START_OF_DAY / HOUR)..=(last / HOUR) {
+        let time = hour * HOUR;
+        painter.line_segment([Pos2::new(x(time), timeline.top()), Pos2::new(x(time), timeline.bottom())], grid_stroke);
+        painter.text(Pos2::new(x(time), rect.top()), Align2::CENTER_TOP, clock_time(time), FontId::proportional(11.0), text_colour);
+    }
+
+    let pointer = response.hover_pos();
+    let mut hovered = None;
+    let rows = [TruckEnum::Truck1, TruckEnum::Truck2].into_iter().flat_map(|truck| DayEnum::ALL.map(|day| (truck, day)));
+    for (row, (truck, day)) in rows.enumerate() {
+        let top = timeline.top() + row as f32 * row_height;
+        painter.text(
+            Pos2::new(rect.left(), top + row_height / 2.0),
+            Align2::LEFT_CENTER,
+            format!("{truck:?} {day:?}"),
+            FontId::proportional(12.0),
+            text_colour,
+        );
+        let bar = |from: Time, to: Time, thickness: f32| {
+            let margin = row_height * (1.0 - thickness) / 2.0;
+            Rect::from_min_max(Pos2::new(x(from), top + margin), Pos2::new(x(to), top + row_height - margin))
+        };
+
+        let mut previous_departure = START_OF_DAY;
+        for stop in stops.iter().filter(|stop| stop.truck == truck && stop.day == day) {
+            painter.rect_filled(bar(previous_departure, stop.arrival, 0.2), 0.0, trip_colour(stop.trip).gamma_multiply(0.5));
+
+            let block = bar(stop.arrival, stop.departure, 0.7);
+            let colour = if stop.order.is_none() { Color32::GREEN } else { trip_colour(stop.trip) };
+            painter.rect_filled(block, 1.0, colour);
+            let outline = if stop.load > TRUCK_CAPACITY {
+                Stroke::new(1.5_f32, Color32::RED)
+            } else {
+                Stroke::new(0.5_f32, Color32::DARK_GRAY)
+            };
+            painter.rect_stroke(block, 1.0, outline, StrokeKind::Inside);
+            if stop.break_time > 0 {
+                painter.rect_filled(bar(stop.departure - stop.break_time, stop.departure, 0.7), 1.0, Color32::GRAY);
+            }
+
+            if stop.departure > end_of_day {
+                let late_from = previous_departure.max(end_of_day);
+                painter.rect_filled(bar(late_from, stop.departure, 0.7), 0.0, Color32::RED.gamma_multiply(0.7));
+            }
+            if pointer.is_some_and(|pointer| bar(previous_departure, stop.departure, 1.0).contains(pointer)) {
+                hovered = Some((stop, previous_departure));
+            }
+            previous_departure = stop.departure;
+        }
+    }
+
+    painter.line_segment(
+        [Pos2::new(x(end_of_day), timeline.top()), Pos2::new(x(end_of_day), timeline.bottom())],
+        Stroke::new(2.0_f32, Color32::RED),
+    );
+
+    if let Some((stop, previous_departure)) = hovered {
+        response.on_hover_text_at_pointer(describe_stop(stop, previous_departure));
+    }
+}
+
+fn trip_colour(trip: TimeOfDay) -> Color32 {
+    match trip {
+        TimeOfDay::Morning => Color32::LIGHT_BLUE,
+        TimeOfDay::Afternoon => Color32::from_rgb(255, 200, 120),
+    }
+}
+
+fn describe_stop(stop: &ManifestStop, previous_departure: Time) -> String {
+    let minutes = |time: Time| format!("{:.1} min", time as f32 / MINUTE as f32);
+    let name = match stop.order {
+        Some(order) => format!("Order {order}, {}", stop.place),
+        None => format!("Dropoff, {}", stop.place),
+    };
+    let break_text = if stop.break_time > 0 { format!(" (break {})", minutes(stop.break_time)) } else { String::new() };
+    format!(
+        "{name}\n{} trip, stop {}\n{} - {}\nDriving there: {}\nAt the stop: {}{break_text}\nLoad: {}L / {}L",
+        stop.trip,
+        stop.sequence,
+        clock_time(stop.arrival),
+        clock_time(stop.departure),
+        minutes(stop.arrival - previous_departure),
+        minutes(stop.departure - stop.arrival),
+        stop.load,
+        TRUCK_CAPACITY,
+    )
+}
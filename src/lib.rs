@@ -0,0 +1,108 @@
+#![feature(iter_map_windows)]
+
+use std::{
+    error::Error,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    parser::{parse_distance_matrix, parse_drivers, parse_objective, parse_orderfile},
+    resource::{Company, DistanceMatrix, Sites, TravelTable},
+    simulated_annealing::neighbor_lists::{NEIGHBOR_COUNT, NeighborLists},
+    simulated_annealing::driver::Driver,
+    simulated_annealing::objective::Objective,
+};
+
+pub mod cli;
+pub mod datastructures;
+pub mod gui;
+pub mod parser;
+pub mod printer;
+pub mod resource;
+pub mod simulated_annealing;
+
+pub static ORDERS: OnceLock<Arc<[Company]>> = const { OnceLock::new() };
+
+#[inline(always)]
+/// If you call this function before orders are parsed I will call you silly and make you wear a dunce hat.
+pub fn get_orders() -> &'static Arc<[Company]> {
+    // this is naughty (and faster) but unless you're *really* silly and try
+    // getting the orders before parsing them, this should be fine.
+    unsafe { ORDERS.get().unwrap_unchecked() }
+}
+
+pub static DISTANCE_MATRIX: OnceLock<DistanceMatrix> = const { OnceLock::new() };
+
+#[inline(always)]
+/// If you call this function before the distance matrix is parsed I will call you silly and make you wear a dunce hat.
+pub fn get_distance_matrix() -> &'static DistanceMatrix {
+    unsafe { DISTANCE_MATRIX.get().unwrap_unchecked() }
+}
+
+pub static TRAVEL_TABLE: OnceLock<TravelTable> = const { OnceLock::new() };
+
+#[inline(always)]
+/// Use this instead of the distance matrix for anything that needs to be fast.
+pub fn get_travel_table() -> &'static TravelTable {
+    unsafe { TRAVEL_TABLE.get().unwrap_unchecked() }
+}
+
+pub static SITES: OnceLock<Sites> = const { OnceLock::new() };
+
+#[inline(always)]
+/// The depots and dump sites in the orders. These are found with the travel table, so they're set right after it.
+pub fn get_sites() -> &'static Sites {
+    unsafe { SITES.get().unwrap_unchecked() }
+}
+
+pub static NEIGHBOR_LISTS: OnceLock<NeighborLists> = const { OnceLock::new() };
+
+#[inline(always)]
+/// These are built from the orders and the distance matrix, so they only exist after both are parsed.
+pub fn get_neighbor_lists() -> &'static NeighborLists {
+    unsafe { NEIGHBOR_LISTS.get().unwrap_unchecked() }
+}
+
+pub static OBJECTIVE: OnceLock<Objective> = const { OnceLock::new() };
+
+/// The weights of the score and the fuel use of the trucks.
+/// Tests that want other weights set this before anything reads it, the others get the data files.
+pub fn get_objective() -> &'static Objective {
+    OBJECTIVE.get_or_init(|| parse_objective().unwrap())
+}
+
+pub static DRIVERS: OnceLock<[Driver; 2]> = const { OnceLock::new() };
+
+/// The driving time rules, indexed by truck. Like the objective, tests can set their own before anything reads them.
+pub fn get_drivers() -> &'static [Driver; 2] {
+    DRIVERS.get_or_init(|| parse_drivers().unwrap())
+}
+
+/// Parses the orders and the distance matrix and builds everything that is derived from them.
+/// This has to be called once before doing anything else.
+pub fn initialize() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let order_vec = parse_orderfile()?;
+    ORDERS.set(order_vec.into()).ok();
+    let distance_matrix = parse_distance_matrix()?;
+    DISTANCE_MATRIX.set(distance_matrix).ok();
+    TRAVEL_TABLE
+        .set(TravelTable::new(get_distance_matrix(), get_orders()))
+        .ok();
+    SITES.set(Sites::new(get_orders(), get_travel_table())?).ok();
+    OBJECTIVE.set(parse_objective()?).ok();
+    DRIVERS.set(parse_drivers()?).ok();
+    NEIGHBOR_LISTS
+        .set(NeighborLists::new(get_travel_table(), get_orders(), NEIGHBOR_COUNT))
+        .ok();
+    Ok(())
+}
+
+/// Sets the orders and the travel times for unit tests that don't care about the frequencies.
+/// All unit tests share one process, so another test might have set the orders already, with other frequencies.
+#[cfg(test)]
+pub(crate) fn init_orders_for_tests() {
+    ORDERS.set(parse_orderfile().unwrap().into()).ok();
+    DISTANCE_MATRIX.set(parse_distance_matrix().unwrap()).ok();
+    TRAVEL_TABLE.set(TravelTable::new(get_distance_matrix(), get_orders())).ok();
+    SITES.set(Sites::new(get_orders(), get_travel_table()).unwrap()).ok();
+}
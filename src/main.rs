@@ -1,51 +1,33 @@
-#![feature(iter_map_windows)]
-
-use std::{
-    error::Error,
-    sync::{Arc, OnceLock},
-};
+use std::error::Error;
 
 use eframe::UserEvent;
+use route_finder::cli::{Command, USAGE, parse_args, run_headless};
+use route_finder::{gui::GuiApp, initialize};
 use winit::event_loop::{ControlFlow, EventLoop};
 
-use crate::{
-    gui::GuiApp,
-    parser::{parse_distance_matrix, parse_orderfile},
-    resource::{Company, DistanceMatrix},
-};
-
-mod datastructures;
-mod gui;
-mod parser;
-mod printer;
-mod resource;
-pub mod simulated_annealing;
-
-pub static ORDERS: OnceLock<Arc<[Company]>> = const { OnceLock::new() };
-pub const EXTREME_TEST_FLAG:bool = false;
-
-#[inline(always)]
-/// If you call this function before orders are parsed I will call you silly and make you wear a dunce hat.
-pub fn get_orders() -> &'static Arc<[Company]> {
-    // this is naughty (and faster) but unless you're *really* silly and try
-    // getting the orders before parsing them, this should be fine.
-    unsafe { ORDERS.get().unwrap_unchecked() }
-}
-
-pub static DISTANCE_MATRIX: OnceLock<DistanceMatrix> = const { OnceLock::new() };
-
-#[inline(always)]
-/// If you call this function before the distance matrix is parsed I will call you silly and make you wear a dunce hat.
-pub fn get_distance_matrix() -> &'static DistanceMatrix {
-    unsafe { DISTANCE_MATRIX.get().unwrap_unchecked() }
-}
-
 fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     let instant = std::time::Instant::now();
-    let order_vec = parse_orderfile()?;
-    ORDERS.set(order_vec.into()).ok();
-    let distance_matrix = parse_distance_matrix()?;
-    DISTANCE_MATRIX.set(distance_matrix).ok();
+    let command = match parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    if command == Command::Help {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    initialize()?;
+
+    if command != Command::Gui {
+        run_headless(command)?;
+        println!(
+            "Total program runtime: {}s",
+            instant.elapsed().as_secs_f64()
+        );
+        return Ok(());
+    }
 
     // let mut dot_file = File::create("dotfile.dot")?;
     // Don't actually try to use dot on this file, it will break your PC
@@ -2,7 +2,12 @@ use std::error::Error;
 
 use petgraph::matrix_graph::MatrixGraph;
 
-use crate::resource::{Company, Distance, DistanceMatrix, Frequency, Time, MINUTE};
+use crate::resource::{Company, DayPattern, Distance, DistanceMatrix, Frequency, Site, Time, MINUTE};
+use crate::simulated_annealing::driver::Driver;
+use crate::simulated_annealing::objective::{BalanceMeasure, Objective, TruckType};
+use crate::simulated_annealing::order_day_flags::OrderFlags;
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use crate::simulated_annealing::week::DayEnum;
 
 // Small helper function for getting columns
 fn get_next(
@@ -15,8 +20,78 @@ fn get_next(
         .ok_or_else(|| format!("Error column missing {}", field_name))?)
 }
 
+const ORDER_FILE: &str = include_str!("../data/Orderbestand.txt");
+const DISTANCE_MATRIX_FILE: &str = include_str!("../data/AfstandenMatrix.txt");
+// SITE_FILE, TRUCK_FILE, OBJECTIVE_FILE and DRIVER_FILE, which are None when the file isn't in data/, see build.rs
+include!(concat!(env!("OUT_DIR"), "/optional_data_files.rs"));
+
+/// Locaties.txt, the depots and dump sites. Soort is Depot, Stort or Depot+Stort.
+/// Wagens are the trucks that start and end their days at a depot, like 1+2. Every truck needs exactly one depot.
+/// By default, both trucks start in Maarheeze and unload there.
+const DEFAULT_SITE_FILE: &str = "\
+Naam;Soort;MatrixID;XCoordinaat;YCoordinaat;Wagens
+Maarheeze;Depot+Stort;287;56343016;513026712;1+2
+";
+
+/// Wagens.txt, how much fuel every truck uses per kilometre, empty and full, and the CO2 of a litre of it.
+const DEFAULT_TRUCK_FILE: &str = "\
+Wagen;Type;LiterPerKmLeeg;LiterPerKmVol;KgCO2PerLiter
+1;Vuilniswagen 26t;0.45;0.60;2.64
+2;Vuilniswagen 26t;0.45;0.60;2.64
+";
+
+/// Doelfunctie.txt, how many minutes a kilometre, a litre of fuel and a kg of CO2 are worth in the score.
+/// BalansDagen and BalansWagens weigh how uneven the days and the trucks are, measured with a Balansmaat of Variantie or Max.
+/// By default, only the time counts.
+const DEFAULT_OBJECTIVE_FILE: &str = "\
+Onderdeel;Waarde
+Kilometer;0
+Liter;0
+KgCO2;0
+BalansDagen;0
+BalansWagens;0
+Balansmaat;Variantie
+";
+
+/// Chauffeurs.txt, the driving time rules of the driver of every truck. By default, what the law says.
+const DEFAULT_DRIVER_FILE: &str = "\
+Wagen;Naam;PauzeNaRijtijdMinuten;PauzeMinuten;MaxAaneengeslotenRijtijdMinuten;MaxWerktijdPerWeekUren
+1;Chauffeur wagen 1;270;30;270;60
+2;Chauffeur wagen 2;270;30;270;60
+";
+
+const SITE_TEXT: &str = match SITE_FILE {
+    Some(text) => text,
+    None => DEFAULT_SITE_FILE,
+};
+const TRUCK_TEXT: &str = match TRUCK_FILE {
+    Some(text) => text,
+    None => DEFAULT_TRUCK_FILE,
+};
+const OBJECTIVE_TEXT: &str = match OBJECTIVE_FILE {
+    Some(text) => text,
+    None => DEFAULT_OBJECTIVE_FILE,
+};
+const DRIVER_TEXT: &str = match DRIVER_FILE {
+    Some(text) => text,
+    None => DEFAULT_DRIVER_FILE,
+};
+
+/// A fingerprint of the data files this program was built with, so we can tell which data a run used.
+/// This is 64 bit FNV-1a, because the hashers in std are allowed to change between Rust versions.
+pub fn dataset_hash() -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    // The objective and the driving time rules are in here too, because the scores depend on them
+    let files = [ORDER_FILE, DISTANCE_MATRIX_FILE, SITE_TEXT, TRUCK_TEXT, OBJECTIVE_TEXT, DRIVER_TEXT];
+    for byte in files.iter().flat_map(|file| file.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
 pub fn parse_orderfile() -> Result<Vec<Company>, Box<dyn Error + Send + Sync>> {
-    let orderfile = include_str!("../data/Orderbestand.txt");
+    let orderfile = ORDER_FILE;
 
     // Split in lines, skip headers
     let mut list = orderfile
@@ -27,10 +102,18 @@ pub fn parse_orderfile() -> Result<Vec<Company>, Box<dyn Error + Send + Sync>> {
 
             let order = get_next(&mut columns, "Order")?.parse()?;
             let place = String::from(get_next(&mut columns, "Plaats")?.trim());
-            let frequency = get_next(&mut columns, "Frequentie")?.parse()?;
+            let frequency: Frequency = get_next(&mut columns, "Frequentie")?.parse()?;
             let container_count = get_next(&mut columns, "AantContainers")?.parse()?;
             let container_volume = get_next(&mut columns, "VolumePerContainer")?.parse()?;
             let emptying_time = ((get_next(&mut columns, "LedigingsDuurMinuten")?.parse::<f32>()?) * MINUTE as f32) as Time;
+            let matrix_id = get_next(&mut columns, "MatrixID")?.parse::<u16>()?.into();
+            let x_coordinate = get_next(&mut columns, "XCoordinaat")?.parse()?;
+            let y_coordinate = get_next(&mut columns, "YCoordinaat")?.parse()?;
+            // The day patterns are an optional last column, most customers go with the defaults of their frequency
+            let allowed_patterns = match columns.next().map(str::trim).filter(|text| !text.is_empty()) {
+                Some(text) => parse_day_patterns(text, frequency).map_err(|error| format!("Order {order}: {error}"))?,
+                None => frequency.default_patterns().to_vec(),
+            };
             Ok(Company {
                 order,
                 place,
@@ -38,35 +121,226 @@ pub fn parse_orderfile() -> Result<Vec<Company>, Box<dyn Error + Send + Sync>> {
                 container_count,
                 container_volume,
                 emptying_time,
-                matrix_id: get_next(&mut columns, "MatrixID")?.parse::<u16>()?.into(),
-                x_coordinate: get_next(&mut columns, "XCoordinaat")?.parse()?,
-                y_coordinate: get_next(&mut columns, "YCoordinaat")?.parse()?,
+                matrix_id,
+                x_coordinate,
+                y_coordinate,
                 total_container_volume: container_count as u32 * container_volume as u32,
                 penalty: 3 * frequency as Time * emptying_time,
+                allowed_patterns,
+                site: None,
             })
         })
         .collect::<Result<Vec<Company>, Box<dyn Error + Send + Sync>>>();
 
-    if let Ok(list) = &mut list{
-        list.push(Company{
-            order: 0,
-            place: "Dropoff".to_string(),
-            frequency: Frequency::None,
-            container_count: 0,
-            container_volume: 0,
-            emptying_time: 0,
-            matrix_id: 287.into(),
-            x_coordinate: 56343016,
-            y_coordinate: 513026712,
-            total_container_volume: 0,
-            penalty: 0,
-        })
+    if let Ok(list) = &mut list {
+        list.extend(parse_sitefile()?);
     }
     list
 }
 
+/// Parses the depots and dump sites. They go after the customers in the orders, with frequency 0,
+/// so routes can start and end at them like at any other order.
+pub fn parse_sitefile() -> Result<Vec<Company>, Box<dyn Error + Send + Sync>> {
+    parse_sites_from(SITE_TEXT)
+}
+
+/// Parses the depots and dump sites from text in the format of Locaties.txt.
+pub fn parse_sites_from(text: &str) -> Result<Vec<Company>, Box<dyn Error + Send + Sync>> {
+    text.lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| -> Result<Company, Box<dyn Error + Send + Sync>> {
+            let mut columns = line.split(';');
+
+            let place = String::from(get_next(&mut columns, "Naam")?.trim());
+            let kinds = get_next(&mut columns, "Soort")?;
+            let mut is_depot = false;
+            let mut is_dump = false;
+            for kind in kinds.split('+') {
+                match kind.trim().to_lowercase().as_str() {
+                    "depot" => is_depot = true,
+                    "stort" => is_dump = true,
+                    other => return Err(format!("{place}: invalid kind of site {other:?}").into()),
+                }
+            }
+            let matrix_id = get_next(&mut columns, "MatrixID")?.parse::<u16>()?.into();
+            let x_coordinate = get_next(&mut columns, "XCoordinaat")?.parse()?;
+            let y_coordinate = get_next(&mut columns, "YCoordinaat")?.parse()?;
+            let home_of = columns
+                .next()
+                .unwrap_or_default()
+                .split('+')
+                .map(str::trim)
+                .filter(|truck| !truck.is_empty())
+                .map(|truck| match truck {
+                    "1" => Ok(TruckEnum::Truck1),
+                    "2" => Ok(TruckEnum::Truck2),
+                    other => Err(format!("{place}: invalid truck {other:?}")),
+                })
+                .collect::<Result<Vec<TruckEnum>, String>>()?;
+            if !home_of.is_empty() && !is_depot {
+                return Err(format!("{place}: trucks can only start at a depot").into());
+            }
+
+            Ok(Company {
+                // The text solutions write 0 for every visit to a dump site
+                order: 0,
+                place,
+                frequency: Frequency::None,
+                container_count: 0,
+                container_volume: 0,
+                emptying_time: 0,
+                matrix_id,
+                x_coordinate,
+                y_coordinate,
+                total_container_volume: 0,
+                penalty: 0,
+                allowed_patterns: vec![],
+                site: Some(Site { is_dump, home_of }),
+            })
+        })
+        .collect()
+}
+
+/// Parses the Dagpatronen column: the day sets a customer may be collected on, separated by `|`.
+/// The days in a set are separated by `+`, so twice a week but never on Monday is `di+vr|wo+vr`.
+/// Every set needs as many days as the frequency.
+pub fn parse_day_patterns(text: &str, frequency: Frequency) -> Result<Vec<DayPattern>, String> {
+    let mut patterns = Vec::new();
+    for pattern_text in text.split('|') {
+        let mut pattern: DayPattern = 0;
+        for day_text in pattern_text.split('+') {
+            let day = match day_text.trim().to_lowercase().as_str() {
+                "ma" => DayEnum::Monday,
+                "di" => DayEnum::Tuesday,
+                "wo" => DayEnum::Wednesday,
+                "do" => DayEnum::Thursday,
+                "vr" => DayEnum::Friday,
+                other => return Err(format!("Invalid day in the day patterns: {other:?}")),
+            };
+            pattern |= OrderFlags::day_to_flags(day);
+        }
+        if pattern.count_ones() != frequency as u32 {
+            return Err(format!("Day pattern {pattern_text:?} doesn't have {} different days", frequency as u8));
+        }
+        if !patterns.contains(&pattern) {
+            patterns.push(pattern);
+        }
+    }
+    Ok(patterns)
+}
+
+/// Parses the weights of the objective and the fuel use of the trucks.
+/// Both trucks and the weights of the kilometres, fuel and CO2 have to be in the files, the balance can be left out.
+pub fn parse_objective() -> Result<Objective, Box<dyn Error + Send + Sync>> {
+    parse_objective_from(TRUCK_TEXT, OBJECTIVE_TEXT)
+}
+
+/// Parses the objective from text in the format of Wagens.txt and Doelfunctie.txt.
+pub fn parse_objective_from(truck_text: &str, objective_text: &str) -> Result<Objective, Box<dyn Error + Send + Sync>> {
+    let mut truck_types: [Option<TruckType>; 2] = [None, None];
+    for line in truck_text.lines().skip(1).filter(|line| !line.trim().is_empty()) {
+        let mut columns = line.split(';');
+        let truck = match get_next(&mut columns, "Wagen")?.trim() {
+            "1" => TruckEnum::Truck1,
+            "2" => TruckEnum::Truck2,
+            other => return Err(format!("invalid truck {other:?}").into()),
+        };
+        truck_types[truck as usize] = Some(TruckType {
+            name: String::from(get_next(&mut columns, "Type")?.trim()),
+            liters_per_km_empty: get_next(&mut columns, "LiterPerKmLeeg")?.trim().parse()?,
+            liters_per_km_full: get_next(&mut columns, "LiterPerKmVol")?.trim().parse()?,
+            kg_co2_per_liter: get_next(&mut columns, "KgCO2PerLiter")?.trim().parse()?,
+        });
+    }
+    let [Some(truck1), Some(truck2)] = truck_types else {
+        return Err("both trucks need a type".into());
+    };
+
+    // The balance is optional, without it the workload can be as uneven as it likes
+    let mut weights = [None; 3];
+    let mut balance_weights = [0.0; 2];
+    let mut balance_measure = BalanceMeasure::Variance;
+    for line in objective_text.lines().skip(1).filter(|line| !line.trim().is_empty()) {
+        let mut columns = line.split(';');
+        let name = String::from(get_next(&mut columns, "Onderdeel")?.trim());
+        let part = name.to_lowercase();
+        let value = get_next(&mut columns, "Waarde")?.trim().to_lowercase();
+        // a negative weight rewards driving or an uneven workload, then the search makes plans worse on purpose
+        let weight = || -> Result<f64, Box<dyn Error + Send + Sync>> {
+            let weight: f64 = value.parse()?;
+            if weight < 0.0 {
+                return Err(format!("the weight of {name} is {weight}, it can't be negative").into());
+            }
+            Ok(weight)
+        };
+        match part.as_str() {
+            "kilometer" => weights[0] = Some(weight()?),
+            "liter" => weights[1] = Some(weight()?),
+            "kgco2" => weights[2] = Some(weight()?),
+            "balansdagen" => balance_weights[0] = weight()?,
+            "balanswagens" => balance_weights[1] = weight()?,
+            "balansmaat" => {
+                balance_measure = match value.as_str() {
+                    "variantie" => BalanceMeasure::Variance,
+                    "max" => BalanceMeasure::Max,
+                    other => return Err(format!("invalid balance measure {other:?}, it's Variantie or Max").into()),
+                }
+            }
+            other => return Err(format!("invalid part of the objective {other:?}").into()),
+        }
+    }
+    let [Some(minutes_per_km), Some(minutes_per_liter), Some(minutes_per_kg_co2)] = weights else {
+        return Err("the objective needs a weight for Kilometer, Liter and KgCO2".into());
+    };
+
+    Ok(Objective {
+        minutes_per_km,
+        minutes_per_liter,
+        minutes_per_kg_co2,
+        truck_types: [truck1, truck2],
+        balance_measure,
+        minutes_per_day_balance: balance_weights[0],
+        minutes_per_truck_balance: balance_weights[1],
+    })
+}
+
+/// Parses the driving time rules of the driver of every truck. The times before a break, of a break and of continuous driving
+/// are in minutes, the weekly working time is in hours.
+pub fn parse_drivers() -> Result<[Driver; 2], Box<dyn Error + Send + Sync>> {
+    parse_drivers_from(DRIVER_TEXT)
+}
+
+/// Parses the drivers from text in the format of Chauffeurs.txt.
+pub fn parse_drivers_from(text: &str) -> Result<[Driver; 2], Box<dyn Error + Send + Sync>> {
+    let mut drivers: [Option<Driver>; 2] = [None, None];
+    for line in text.lines().skip(1).filter(|line| !line.trim().is_empty()) {
+        let mut columns = line.split(';');
+        let truck = match get_next(&mut columns, "Wagen")?.trim() {
+            "1" => TruckEnum::Truck1,
+            "2" => TruckEnum::Truck2,
+            other => return Err(format!("invalid truck {other:?}").into()),
+        };
+        let name = String::from(get_next(&mut columns, "Naam")?.trim());
+        let mut minutes = |field_name: &str| -> Result<Time, Box<dyn Error + Send + Sync>> {
+            Ok(get_next(&mut columns, field_name)?.trim().parse::<Time>()? * MINUTE)
+        };
+        drivers[truck as usize] = Some(Driver {
+            name,
+            break_after: minutes("PauzeNaRijtijdMinuten")?,
+            break_length: minutes("PauzeMinuten")?,
+            max_continuous_driving: minutes("MaxAaneengeslotenRijtijdMinuten")?,
+            max_weekly_time: minutes("MaxWerktijdPerWeekUren")? * 60,
+        });
+    }
+    let [Some(driver1), Some(driver2)] = drivers else {
+        return Err("both trucks need a driver".into());
+    };
+    Ok([driver1, driver2])
+}
+
 pub fn parse_distance_matrix() -> Result<DistanceMatrix, Box<dyn Error + Send + Sync>> {
-    let distance_matrix_file = include_str!("../data/AfstandenMatrix.txt");
+    let distance_matrix_file = DISTANCE_MATRIX_FILE;
 
     distance_matrix_file.lines().skip(1).try_fold(
         MatrixGraph::new(),
@@ -86,3 +360,115 @@ pub fn parse_distance_matrix() -> Result<DistanceMatrix, Box<dyn Error + Send +
         },
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DEFAULT_DRIVER_FILE, DEFAULT_OBJECTIVE_FILE, DEFAULT_SITE_FILE, DEFAULT_TRUCK_FILE, parse_day_patterns,
+        parse_drivers, parse_drivers_from, parse_objective, parse_objective_from, parse_sitefile, parse_sites_from,
+    };
+    use crate::simulated_annealing::driver::Driver;
+    use crate::resource::Frequency;
+    use crate::simulated_annealing::simulated_annealing::TruckEnum;
+
+    #[test]
+    fn maarheeze_is_the_depot_and_dump_site_of_both_trucks() {
+        let sites = parse_sites_from(DEFAULT_SITE_FILE).unwrap();
+        assert_eq!(sites.len(), 1);
+        let site = sites[0].site.as_ref().unwrap();
+        assert_eq!(sites[0].place, "Maarheeze");
+        assert_eq!(sites[0].matrix_id.index(), 287);
+        assert!(site.is_dump);
+        assert_eq!(site.home_of, [TruckEnum::Truck1, TruckEnum::Truck2]);
+    }
+
+    #[test]
+    fn the_objective_is_only_time_by_default() {
+        let objective = parse_objective_from(DEFAULT_TRUCK_FILE, DEFAULT_OBJECTIVE_FILE).unwrap();
+        assert!(!objective.needs_load());
+        assert_eq!(objective.minutes_per_km, 0.0);
+        assert!(!objective.balances());
+        assert!(objective.truck_types.iter().all(|truck_type| truck_type.liters_per_km_full > truck_type.liters_per_km_empty));
+    }
+
+    #[test]
+    fn objective_weights_cannot_be_negative() {
+        let objective_text = DEFAULT_OBJECTIVE_FILE.replace("BalansWagens;0", "BalansWagens;-0.5");
+        let error = parse_objective_from(DEFAULT_TRUCK_FILE, &objective_text).unwrap_err();
+        assert_eq!(error.to_string(), "the weight of BalansWagens is -0.5, it can't be negative");
+    }
+
+    #[test]
+    fn the_drivers_follow_the_law_by_default() {
+        let drivers = parse_drivers_from(DEFAULT_DRIVER_FILE).unwrap();
+        for driver in drivers {
+            assert_eq!(Driver { name: driver.name.clone(), ..Driver::legal() }, driver);
+        }
+    }
+
+    #[test]
+    fn the_data_files_parse_when_they_are_there() {
+        parse_sitefile().unwrap();
+        parse_objective().unwrap();
+        parse_drivers().unwrap();
+    }
+
+    #[test]
+    fn every_truck_can_have_its_own_depot() {
+        let text = "\
+Naam;Soort;MatrixID;XCoordinaat;YCoordinaat;Wagens
+Maarheeze;Depot;287;56343016;513026712;1
+Eindhoven;depot + stort;12;54780000;514410000;2
+
+Weert;Stort;150;56900000;512500000
+";
+        let sites = parse_sites_from(text).unwrap();
+        assert_eq!(sites.iter().map(|site| site.place.as_str()).collect::<Vec<_>>(), ["Maarheeze", "Eindhoven", "Weert"]);
+        let [maarheeze, eindhoven, weert] = [0, 1, 2].map(|index| sites[index].site.as_ref().unwrap());
+        assert!(!maarheeze.is_dump);
+        assert_eq!(maarheeze.home_of, [TruckEnum::Truck1]);
+        assert!(eindhoven.is_dump);
+        assert_eq!(eindhoven.home_of, [TruckEnum::Truck2]);
+        assert!(weert.is_dump);
+        assert!(weert.home_of.is_empty());
+    }
+
+    #[test]
+    fn sites_that_make_no_sense_are_errors() {
+        let header = "Naam;Soort;MatrixID;XCoordinaat;YCoordinaat;Wagens\n";
+        for line in [
+            "Weert;Stort;150;56900000;512500000;1",
+            "Weert;Garage;150;56900000;512500000",
+            "Weert;Depot;150;56900000;512500000;3",
+            "Weert;Depot;150",
+        ] {
+            assert!(parse_sites_from(&format!("{header}{line}")).is_err(), "{line}");
+        }
+    }
+
+    #[test]
+    fn day_patterns_are_read_as_flags() {
+        assert_eq!(parse_day_patterns("ma+do|di+vr", Frequency::Twice), Ok(vec![0b10010, 0b01001]));
+        assert_eq!(parse_day_patterns(" Di + Vr | wo+vr |di+vr", Frequency::Twice), Ok(vec![0b01001, 0b00101]));
+        assert_eq!(parse_day_patterns("ma|wo|vr", Frequency::Once), Ok(vec![0b10000, 0b00100, 0b00001]));
+        assert_eq!(parse_day_patterns("ma+di+wo+do+vr", Frequency::FiveTimes), Ok(vec![0b11111]));
+    }
+
+    #[test]
+    fn every_frequency_can_be_parsed() {
+        let frequencies: Vec<u8> = ["1PWK", "2PWK", "3PWK", "4PWK", "5PWK"]
+            .iter()
+            .map(|text| text.parse::<Frequency>().unwrap() as u8)
+            .collect();
+        assert_eq!(frequencies, [1, 2, 3, 4, 5]);
+        assert!("6PWK".parse::<Frequency>().is_err());
+        assert!(Frequency::FiveTimes.default_patterns().iter().all(|pattern| pattern.count_ones() == 5));
+    }
+
+    #[test]
+    fn day_patterns_need_the_frequency_in_days() {
+        assert!(parse_day_patterns("ma+wo", Frequency::Thrice).is_err());
+        assert!(parse_day_patterns("ma+ma", Frequency::Twice).is_err());
+        assert!(parse_day_patterns("ma+za", Frequency::Twice).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use crate::printer::json::SolutionFile;
+use crate::simulated_annealing::constraints::{PenaltyWeights, Repair};
+use crate::simulated_annealing::search_statistics::SearchStatistics;
+use crate::simulated_annealing::simulated_annealing::SolverParameters;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{File, read_to_string, rename};
+use std::io::Write;
+
+/// Bump this whenever the format changes in a way that old checkpoints can't be resumed anymore.
+pub const CHECKPOINT_FORMAT_VERSION: u32 = 1;
+
+/// Everything a search needs to carry on where it stopped. It won't make the same moves as the search that wrote it,
+/// see SimulatedAnnealing::checkpoint.
+/// Gets written to checkpoint.json in the output folder of the run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    /// The output folder of the run, a resumed search keeps writing its solutions and trace there.
+    pub output_dir: String,
+    /// The seed the run was started with
+    pub seed: u64,
+    pub parameters: SolverParameters,
+    pub temp: f32,
+    pub step_count: u32,
+    pub num_perturbations: u32,
+    /// The ILS iteration
+    pub iteration: u32,
+    /// How long the search had been running, so the trace keeps counting from there
+    pub elapsed_seconds: f64,
+    pub statistics: SearchStatistics,
+    /// What breaking the soft constraints cost when the checkpoint was written
+    #[serde(default)]
+    pub penalty_weights: PenaltyWeights,
+    /// The solution the annealing is working on
+    pub current: SolutionFile,
+    /// The best solution of all finished ILS iterations
+    pub best: SolutionFile,
+    /// What the repair did to the best solution
+    #[serde(default)]
+    pub best_repair: Repair,
+}
+
+/// Writes checkpoint.json. It's written next to it first and then moved,
+/// so getting killed halfway through doesn't destroy the previous checkpoint.
+pub fn print_checkpoint(checkpoint: &Checkpoint, dir: &str) -> std::io::Result<()> {
+    let temp_file = format!("{dir}/checkpoint.json.tmp");
+    let mut buffer = File::create(&temp_file)?;
+    writeln!(buffer, "{}", serde_json::to_string(checkpoint)?)?;
+    buffer.sync_all()?;
+    rename(temp_file, format!("{dir}/checkpoint.json"))
+}
+
+pub fn load_checkpoint(path: &str) -> Result<Checkpoint, Box<dyn Error + Send + Sync>> {
+    let checkpoint: Checkpoint = serde_json::from_str(&read_to_string(path)?)?;
+    if checkpoint.version != CHECKPOINT_FORMAT_VERSION {
+        return Err(format!(
+            "This is a version {} checkpoint, but we can only resume version {}",
+            checkpoint.version, CHECKPOINT_FORMAT_VERSION
+        )
+        .into());
+    }
+    Ok(checkpoint)
+}
@@ -0,0 +1,341 @@
+use crate::{get_orders, get_sites};
+use crate::resource::{MINUTE, Time};
+use crate::simulated_annealing::day::TimeOfDay;
+use crate::simulated_annealing::route::{OrderIndex, Route};
+use crate::simulated_annealing::score_calculator::{calculate_balance_cost, calculate_objective_cost, calculate_penalty};
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use crate::simulated_annealing::solution::Solution;
+use crate::simulated_annealing::week::DayEnum;
+use std::io::Write;
+
+const TRUCKS: [TruckEnum; 2] = [TruckEnum::Truck1, TruckEnum::Truck2];
+
+/// A day on which an order gets collected, and the truck that does it.
+pub type Visit = (DayEnum, TruckEnum);
+
+/// An order that gets collected on other days, or by another truck.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderChange {
+    pub order: u16,
+    pub place: String,
+    /// Sorted by day, empty if the order isn't planned
+    pub before: Vec<Visit>,
+    pub after: Vec<Visit>,
+}
+
+impl OrderChange {
+    /// False if only the truck changed. The customer doesn't care about that, but does care about the days.
+    pub fn days_changed(&self) -> bool {
+        let days = |visits: &[Visit]| visits.iter().map(|(day, _)| *day).collect::<Vec<_>>();
+        days(&self.before) != days(&self.after)
+    }
+}
+
+/// A route that visits other customers, visits them in another order, or takes a different time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteChange {
+    pub truck: TruckEnum,
+    pub day: DayEnum,
+    pub trip: TimeOfDay,
+    pub sequence_changed: bool,
+    /// 0 for an empty route, like in the score
+    pub time_before: Time,
+    pub time_after: Time,
+    pub capacity_before: u32,
+    pub capacity_after: u32,
+}
+
+impl RouteChange {
+    pub fn time_delta(&self) -> Time {
+        self.time_after - self.time_before
+    }
+
+    pub fn capacity_delta(&self) -> i64 {
+        self.capacity_after as i64 - self.capacity_before as i64
+    }
+}
+
+/// After minus before, so negative is better.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScoreDelta {
+    pub total: Time,
+    pub travel_time: Time,
+    /// The kilometres, fuel and CO2, see Objective
+    pub objective_cost: Time,
+    pub balance_cost: Time,
+    pub penalty: Time,
+    /// In metres
+    pub distance: i64,
+}
+
+/// Everything that's different between two schedules.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolutionDiff {
+    pub score_before: Time,
+    pub score_after: Time,
+    pub score: ScoreDelta,
+    /// Sorted by order number
+    pub orders: Vec<OrderChange>,
+    /// Sorted by truck, day and trip
+    pub routes: Vec<RouteChange>,
+}
+
+impl SolutionDiff {
+    pub fn new(before: &Solution, after: &Solution) -> Self {
+        let orders = get_orders();
+        let visits_before = visits(before);
+        let visits_after = visits(after);
+
+        let mut order_changes: Vec<OrderChange> = visits_before
+            .into_iter()
+            .zip(visits_after)
+            .enumerate()
+            .filter(|(_, (before, after))| before != after)
+            .map(|(order_index, (before, after))| OrderChange {
+                order: orders[order_index].order,
+                place: orders[order_index].place.clone(),
+                before,
+                after,
+            })
+            .collect();
+        order_changes.sort_by_key(|change| change.order);
+
+        let mut routes = Vec::new();
+        for truck in TRUCKS {
+            for day in DayEnum::ALL {
+                for trip in [TimeOfDay::Morning, TimeOfDay::Afternoon] {
+                    let route_before = before.get_truck(truck).get(day).get(trip);
+                    let route_after = after.get_truck(truck).get(day).get(trip);
+                    let sequence_changed = customers(route_before) != customers(route_after);
+                    let time_before = route_time(route_before);
+                    let time_after = route_time(route_after);
+                    if sequence_changed || time_before != time_after || route_before.capacity != route_after.capacity {
+                        routes.push(RouteChange {
+                            truck,
+                            day,
+                            trip,
+                            sequence_changed,
+                            time_before,
+                            time_after,
+                            capacity_before: route_before.capacity,
+                            capacity_after: route_after.capacity,
+                        });
+                    }
+                }
+            }
+        }
+
+        let penalty_before = calculate_penalty(before, &before.order_flags);
+        let penalty_after = calculate_penalty(after, &after.order_flags);
+        let penalty = penalty_after - penalty_before;
+        let objective_cost = calculate_objective_cost(after) - calculate_objective_cost(before);
+        let balance_cost = calculate_balance_cost(after) - calculate_balance_cost(before);
+        let total = after.score - before.score;
+
+        SolutionDiff {
+            score_before: before.score,
+            score_after: after.score,
+            score: ScoreDelta {
+                total,
+                travel_time: total - objective_cost - balance_cost - penalty,
+                objective_cost,
+                balance_cost,
+                penalty,
+                distance: driven_distance(after) - driven_distance(before),
+            },
+            orders: order_changes,
+            routes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty() && self.routes.is_empty() && self.score.total == 0
+    }
+
+    /// How many customers get collected on other days than before.
+    /// Orders that were or become unplanned count as well.
+    pub fn customers_with_new_days(&self) -> usize {
+        self.orders.iter().filter(|change| change.days_changed()).count()
+    }
+}
+
+/// For every order index, the days and trucks it gets collected on.
+fn visits(solution: &Solution) -> Vec<Vec<Visit>> {
+    let mut visits = vec![Vec::new(); get_orders().len()];
+    for day in DayEnum::ALL {
+        for truck in TRUCKS {
+            for trip in [TimeOfDay::Morning, TimeOfDay::Afternoon] {
+                for order_index in customers(solution.get_truck(truck).get(day).get(trip)) {
+                    visits[order_index].push((day, truck));
+                }
+            }
+        }
+    }
+    visits
+}
+
+/// The metres of all routes that count for the score.
+fn driven_distance(solution: &Solution) -> i64 {
+    TRUCKS
+        .iter()
+        .flat_map(|truck| solution.get_truck(*truck).iter())
+        .filter(|route| !route.is_empty())
+        .map(|route| route.distance as i64)
+        .sum()
+}
+
+/// Empty routes still have the time for the dropoff stored, but they don't count for the score.
+fn route_time(route: &Route) -> Time {
+    if route.is_empty() { 0 } else { route.time }
+}
+
+/// The orders in the route, in the order they're visited, without the depot at the start and the dump site at the end.
+fn customers(route: &Route) -> Vec<OrderIndex> {
+    let sites = get_sites();
+    route
+        .linked_vector
+        .iter()
+        .map(|(_, order_index)| *order_index)
+        .filter(|order_index| !sites.is_site(*order_index))
+        .collect()
+}
+
+/// Writes how the score moved, split into its parts, and the change in kilometres.
+/// Then every order that moved to another day or truck, with its visits before and after,
+/// and every route whose time, load or order of stops changed.
+pub fn write_diff(buffer: &mut impl Write, diff: &SolutionDiff) -> std::io::Result<()> {
+    writeln!(
+        buffer,
+        "Score: {} -> {} ({}: travel time {}, objective {}, balance {}, penalty {})",
+        minutes(diff.score_before),
+        minutes(diff.score_after),
+        signed_minutes(diff.score.total),
+        signed_minutes(diff.score.travel_time),
+        signed_minutes(diff.score.objective_cost),
+        signed_minutes(diff.score.balance_cost),
+        signed_minutes(diff.score.penalty),
+    )?;
+    writeln!(buffer, "Distance: {:+.1} km", diff.score.distance as f64 / 1000.0)?;
+    writeln!(buffer, "Customers collected on other days: {}", diff.customers_with_new_days())?;
+    writeln!(buffer)?;
+
+    writeln!(buffer, "Orders that changed day or truck: {}", diff.orders.len())?;
+    for change in &diff.orders {
+        writeln!(
+            buffer,
+            "  {} {}: {} -> {}{}",
+            change.order,
+            change.place,
+            visit_list(&change.before),
+            visit_list(&change.after),
+            if change.days_changed() { "" } else { " (same days)" },
+        )?;
+    }
+    writeln!(buffer)?;
+
+    writeln!(buffer, "Routes that changed: {}", diff.routes.len())?;
+    for change in &diff.routes {
+        writeln!(
+            buffer,
+            "  {:?} {:?} {}:{} time {} -> {} ({}), load {}L -> {}L ({:+}L)",
+            change.truck,
+            change.day,
+            change.trip,
+            if change.sequence_changed { " new sequence," } else { "" },
+            minutes(change.time_before),
+            minutes(change.time_after),
+            signed_minutes(change.time_delta()),
+            change.capacity_before,
+            change.capacity_after,
+            change.capacity_delta(),
+        )?;
+    }
+    Ok(())
+}
+
+fn minutes(time: Time) -> String {
+    format!("{:.1} min", time as f64 / MINUTE as f64)
+}
+
+fn signed_minutes(time: Time) -> String {
+    format!("{:+.1} min", time as f64 / MINUTE as f64)
+}
+
+fn visit_list(visits: &[Visit]) -> String {
+    if visits.is_empty() {
+        return "not planned".to_string();
+    }
+    visits
+        .iter()
+        .map(|(day, truck)| format!("{day:?} {truck:?}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SolutionDiff;
+    use crate::datastructures::linked_vectors::LinkedVector;
+    use crate::simulated_annealing::day::TimeOfDay;
+    use crate::simulated_annealing::route::OrderIndex;
+    use crate::simulated_annealing::score_calculator::calculate_score;
+    use crate::simulated_annealing::simulated_annealing::TruckEnum;
+    use crate::simulated_annealing::solution::Solution;
+    use crate::simulated_annealing::week::DayEnum;
+    use crate::{get_orders, init_orders_for_tests};
+
+    fn solution_with(visits: &[(OrderIndex, DayEnum, TruckEnum)]) -> Solution {
+        let mut solution = Solution::new();
+        for (order_index, day, truck) in visits {
+            let route = solution.get_truck_mut(*truck).get_mut(*day).get_mut(TimeOfDay::Morning);
+            let head = route.linked_vector.get_head_index().unwrap();
+            route.apply_add_order(head, *order_index);
+            solution.order_flags.add_order(*order_index, *day);
+        }
+        solution.score = calculate_score(&solution, &solution.order_flags);
+        solution
+    }
+
+    #[test]
+    fn same_solution_has_no_diff() {
+        init_orders_for_tests();
+        let solution = solution_with(&[(0, DayEnum::Monday, TruckEnum::Truck1), (1, DayEnum::Monday, TruckEnum::Truck1)]);
+        assert!(SolutionDiff::new(&solution, &solution.clone()).is_empty());
+    }
+
+    #[test]
+    fn moved_order_changes_both_routes() {
+        init_orders_for_tests();
+        let before = solution_with(&[(0, DayEnum::Monday, TruckEnum::Truck1), (1, DayEnum::Monday, TruckEnum::Truck1)]);
+        let after = solution_with(&[(0, DayEnum::Monday, TruckEnum::Truck1), (1, DayEnum::Tuesday, TruckEnum::Truck2)]);
+        let diff = SolutionDiff::new(&before, &after);
+
+        assert_eq!(diff.orders.len(), 1);
+        assert_eq!(diff.orders[0].order, get_orders()[1].order);
+        assert_eq!(diff.orders[0].before, [(DayEnum::Monday, TruckEnum::Truck1)]);
+        assert_eq!(diff.orders[0].after, [(DayEnum::Tuesday, TruckEnum::Truck2)]);
+        assert_eq!(diff.customers_with_new_days(), 1);
+
+        assert_eq!(diff.routes.len(), 2);
+        assert!(diff.routes.iter().all(|route| route.sequence_changed));
+        let route_time_delta: i32 = diff.routes.iter().map(|route| route.time_delta()).sum();
+        assert_eq!(diff.score.travel_time, route_time_delta);
+        assert_eq!(
+            diff.score.travel_time + diff.score.objective_cost + diff.score.balance_cost + diff.score.penalty,
+            diff.score.total
+        );
+    }
+
+    #[test]
+    fn other_truck_on_the_same_day_keeps_the_days() {
+        init_orders_for_tests();
+        let before = solution_with(&[(0, DayEnum::Friday, TruckEnum::Truck1)]);
+        let after = solution_with(&[(0, DayEnum::Friday, TruckEnum::Truck2)]);
+        let diff = SolutionDiff::new(&before, &after);
+
+        assert_eq!(diff.orders.len(), 1);
+        assert!(!diff.orders[0].days_changed());
+        assert_eq!(diff.customers_with_new_days(), 0);
+        assert_eq!(diff.score.total, 0);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::{get_orders, get_sites};
+use crate::resource::{MINUTE, Time};
+use crate::simulated_annealing::day::TimeOfDay;
+use crate::simulated_annealing::route::Route;
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use crate::simulated_annealing::week::{DayEnum, Week};
+use serde_json::{Value, json};
+use std::fs::File;
+use std::io::Write;
+
+/// Turns the coordinates from the order file into the coordinates that end up in the GeoJSON.
+///
+/// The order file stores WGS84 longitude and latitude multiplied by 10^7,
+/// so the default just divides them back.
+/// If the data ever comes with another projection (RD New for example), change the scale and offset,
+/// and tell the GIS tool which CRS the file is in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoordinateTransform {
+    pub x_scale: f64,
+    pub x_offset: f64,
+    pub y_scale: f64,
+    pub y_offset: f64,
+}
+
+impl CoordinateTransform {
+    /// Leaves the coordinates exactly like they are in the order file.
+    pub const IDENTITY: CoordinateTransform = CoordinateTransform {
+        x_scale: 1.0,
+        x_offset: 0.0,
+        y_scale: 1.0,
+        y_offset: 0.0,
+    };
+
+    pub fn apply(&self, x: u32, y: u32) -> [f64; 2] {
+        [
+            x as f64 * self.x_scale + self.x_offset,
+            y as f64 * self.y_scale + self.y_offset,
+        ]
+    }
+}
+
+impl Default for CoordinateTransform {
+    fn default() -> Self {
+        CoordinateTransform {
+            x_scale: 1e-7,
+            x_offset: 0.0,
+            y_scale: 1e-7,
+            y_offset: 0.0,
+        }
+    }
+}
+
+/// Writes the weekly plan as a GeoJSON FeatureCollection, which can be opened in QGIS and friends.
+///
+/// Every route that isn't empty becomes a LineString, and every order (and the dropoff) becomes a Point.
+pub fn print_geojson(
+    truck1: &Week,
+    truck2: &Week,
+    path: &str,
+    transform: &CoordinateTransform,
+) -> std::io::Result<()> {
+    let mut buffer = File::create(path)?;
+    let collection = solution_to_geojson(truck1, truck2, transform);
+    writeln!(buffer, "{}", serde_json::to_string_pretty(&collection)?)?;
+    Ok(())
+}
+
+pub fn solution_to_geojson(truck1: &Week, truck2: &Week, transform: &CoordinateTransform) -> Value {
+    let mut features = Vec::new();
+
+    for (truck, truck_enum) in [(truck1, TruckEnum::Truck1), (truck2, TruckEnum::Truck2)] {
+        for day_enum in DayEnum::ALL {
+            for time_of_day in [TimeOfDay::Morning, TimeOfDay::Afternoon] {
+                let route = truck.get(day_enum).get(time_of_day);
+                if route.is_empty() {
+                    continue;
+                }
+                features.push(route_feature(route, truck_enum, day_enum, time_of_day, transform));
+            }
+        }
+    }
+
+    let assigned_days = assigned_days(truck1, truck2);
+    for (order_index, order) in get_orders().iter().enumerate() {
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Point",
+                "coordinates": transform.apply(order.x_coordinate, order.y_coordinate),
+            },
+            "properties": {
+                "order": order.order,
+                "place": order.place,
+                "frequency": order.frequency as u8,
+                "assigned_days": assigned_days[order_index]
+                    .iter()
+                    .map(|day| format!("{day:?}"))
+                    .collect::<Vec<_>>(),
+            },
+        }));
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+fn route_feature(
+    route: &Route,
+    truck_enum: TruckEnum,
+    day_enum: DayEnum,
+    time_of_day: TimeOfDay,
+    transform: &CoordinateTransform,
+) -> Value {
+    let orders = get_orders();
+    let coordinates: Vec<[f64; 2]> = route
+        .linked_vector
+        .iter()
+        .map(|(_, order_index)| {
+            let order = &orders[*order_index];
+            transform.apply(order.x_coordinate, order.y_coordinate)
+        })
+        .collect();
+
+    json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "LineString",
+            "coordinates": coordinates,
+        },
+        "properties": {
+            "truck": truck_enum.number(),
+            "day": format!("{day_enum:?}"),
+            "trip": time_of_day.to_string(),
+            "time_minutes": minutes(route.time),
+            "capacity": route.capacity,
+            "stops": route.linked_vector.len() - 2,
+        },
+    })
+}
+
+/// For every order, the days on which one of the trucks picks it up.
+fn assigned_days(truck1: &Week, truck2: &Week) -> Vec<Vec<DayEnum>> {
+    let mut days = vec![Vec::new(); get_orders().len()];
+    let sites = get_sites();
+    for truck in [truck1, truck2] {
+        for day_enum in DayEnum::ALL {
+            let day = truck.get(day_enum);
+            for route in [&day.morning, &day.afternoon] {
+                for (_, order_index) in route.linked_vector.iter() {
+                    if !sites.is_site(*order_index) && !days[*order_index].contains(&day_enum) {
+                        days[*order_index].push(day_enum);
+                    }
+                }
+            }
+        }
+    }
+    days.iter_mut().for_each(|days| days.sort());
+    days
+}
+
+fn minutes(time: Time) -> f64 {
+    time as f64 / MINUTE as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CoordinateTransform, solution_to_geojson};
+    use crate::datastructures::linked_vectors::LinkedVector;
+    use crate::simulated_annealing::day::TimeOfDay;
+    use crate::simulated_annealing::simulated_annealing::TruckEnum;
+    use crate::simulated_annealing::week::{DayEnum, Week};
+    use crate::{get_orders, init_orders_for_tests};
+
+    #[test]
+    fn default_transform_gives_longitude_latitude() {
+        // The dropoff in Maarheeze
+        let [lon, lat] = CoordinateTransform::default().apply(56343016, 513026712);
+        assert!((lon - 5.6343016).abs() < 1e-9);
+        assert!((lat - 51.3026712).abs() < 1e-9);
+    }
+
+    #[test]
+    fn one_line_per_route_and_one_point_per_order() {
+        init_orders_for_tests();
+        let mut truck1 = Week::new(TruckEnum::Truck1);
+        let route = truck1.get_mut(DayEnum::Tuesday).get_mut(TimeOfDay::Afternoon);
+        let head = route.linked_vector.get_head_index().unwrap();
+        route.apply_add_order(head, 0);
+
+        let collection = solution_to_geojson(&truck1, &Week::new(TruckEnum::Truck2), &CoordinateTransform::IDENTITY);
+        let features = collection["features"].as_array().unwrap();
+        assert_eq!(features.len(), 1 + get_orders().len());
+
+        let line = &features[0];
+        assert_eq!(line["geometry"]["type"], "LineString");
+        assert_eq!(line["geometry"]["coordinates"].as_array().unwrap().len(), 3);
+        assert_eq!(line["properties"]["day"], "Tuesday");
+        assert_eq!(line["properties"]["stops"], 1);
+
+        let point = &features[1];
+        assert_eq!(point["properties"]["order"], get_orders()[0].order);
+        assert_eq!(point["properties"]["assigned_days"], serde_json::json!(["Tuesday"]));
+    }
+}
@@ -0,0 +1,339 @@
+use crate::datastructures::compact_linked_vector::CompactLinkedVector;
+use crate::datastructures::linked_vectors::LinkedVector;
+use crate::{get_orders, get_sites};
+use crate::resource::Time;
+use crate::simulated_annealing::day::TimeOfDay;
+use crate::simulated_annealing::locks::{Locks, OrderLock};
+use crate::simulated_annealing::route::{OrderIndex, Route};
+use crate::simulated_annealing::score_calculator::{calculate_balance_cost, calculate_objective_cost, calculate_penalty};
+use crate::simulated_annealing::simulated_annealing::{SolverParameters, TruckEnum};
+use crate::simulated_annealing::solution::Solution;
+use crate::simulated_annealing::week::DayEnum;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{File, read_to_string};
+use std::io::Write;
+
+/// Bump this whenever the format changes in a way that old files can't be read anymore.
+pub const SOLUTION_FORMAT_VERSION: u32 = 1;
+
+/// Where a solution came from. Both are None for solutions that weren't made by a search (like a hand made one).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunInfo {
+    pub seed: Option<u64>,
+    pub parameters: Option<SolverParameters>,
+}
+
+/// The JSON solution file. Everything refers to orders by their order number from the order file,
+/// not by their index, so the files stay readable for other tools.
+///
+/// All times are in centiseconds, like everywhere else.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolutionFile {
+    pub version: u32,
+    pub run: RunInfo,
+    pub score: ScoreComponents,
+    pub trucks: Vec<TruckFile>,
+    /// In the same order as the unfilled orders list of the solution
+    pub unfilled_orders: Vec<u16>,
+    /// Left out when nothing is locked, files from before locks existed don't have it either
+    #[serde(default, skip_serializing_if = "LocksFile::is_empty")]
+    pub locks: LocksFile,
+}
+
+/// The orders and routes the search has to leave alone.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LocksFile {
+    pub orders: Vec<LockedOrder>,
+    pub frozen_routes: Vec<FrozenRoute>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedOrder {
+    pub order: u16,
+    pub lock: OrderLock,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrozenRoute {
+    pub truck: TruckEnum,
+    pub day: DayEnum,
+    pub trip: TimeOfDay,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreComponents {
+    pub total: Time,
+    pub travel_time: Time,
+    /// The kilometres, fuel and CO2, see Objective. Files from before the objective don't have it.
+    #[serde(default)]
+    pub objective_cost: Time,
+    /// What the uneven workload costs, 0 when balancing is off
+    #[serde(default)]
+    pub balance_cost: Time,
+    pub penalty: Time,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TruckFile {
+    pub truck: TruckEnum,
+    pub days: Vec<DayFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayFile {
+    pub day: DayEnum,
+    pub routes: Vec<RouteFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteFile {
+    pub trip: TimeOfDay,
+    pub time: Time,
+    pub capacity: u32,
+    /// Only the customers, the depot at the start and the dump site at the end of every route are left out
+    pub orders: Vec<u16>,
+    /// The name of the dump site, left out when the trip unloads at the one closest to the depot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dump_site: Option<String>,
+    /// The visits to a dump site between the customers, left out when there are none
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unload_stops: Vec<UnloadStopFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnloadStopFile {
+    /// How many customers of `orders` come before it
+    pub after: usize,
+    pub dump_site: String,
+}
+
+impl SolutionFile {
+    pub fn new(solution: &Solution, run: RunInfo) -> Self {
+        let orders = get_orders();
+        let penalty = calculate_penalty(solution, &solution.order_flags);
+        let objective_cost = calculate_objective_cost(solution);
+        let balance_cost = calculate_balance_cost(solution);
+
+        SolutionFile {
+            version: SOLUTION_FORMAT_VERSION,
+            run,
+            score: ScoreComponents {
+                total: solution.score,
+                travel_time: solution.score - objective_cost - balance_cost - penalty,
+                objective_cost,
+                balance_cost,
+                penalty,
+            },
+            trucks: [TruckEnum::Truck1, TruckEnum::Truck2]
+                .into_iter()
+                .map(|truck_enum| TruckFile {
+                    truck: truck_enum,
+                    days: DayEnum::ALL
+                        .into_iter()
+                        .map(|day_enum| DayFile {
+                            day: day_enum,
+                            routes: [TimeOfDay::Morning, TimeOfDay::Afternoon]
+                                .into_iter()
+                                .map(|time_of_day| {
+                                    let route = solution.get_truck(truck_enum).get(day_enum).get(time_of_day);
+                                    RouteFile::new(route, truck_enum, time_of_day)
+                                })
+                                .collect(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            unfilled_orders: solution
+                .unfilled_orders
+                .iter()
+                .map(|(_, order_index)| orders[*order_index].order)
+                .collect(),
+            locks: LocksFile::new(&solution.locks),
+        }
+    }
+
+    /// Rebuilds the solution exactly like it was saved.
+    ///
+    /// The stored times, capacities and score are checked against the routes,
+    /// so a file that was edited by hand can't sneak in an inconsistent solution.
+    pub fn to_solution(&self) -> Result<Solution, String> {
+        if self.version != SOLUTION_FORMAT_VERSION {
+            return Err(format!(
+                "This is a version {} solution file, but we can only read version {}",
+                self.version, SOLUTION_FORMAT_VERSION
+            ));
+        }
+
+        let id_to_index = Solution::order_id_to_index_hash_map();
+        let to_index = |order: &u16| {
+            id_to_index
+                .get(order)
+                .copied()
+                .ok_or_else(|| format!("Order {order} is not in the order file"))
+        };
+
+        let mut solution = Solution::new();
+        solution.unfilled_orders = CompactLinkedVector::new();
+        for order in &self.unfilled_orders {
+            solution.unfilled_orders.push_back(to_index(order)?);
+        }
+
+        for truck_file in &self.trucks {
+            for day_file in &truck_file.days {
+                for route_file in &day_file.routes {
+                    // not get_truck_mut, because we need the order flags at the same time
+                    let truck = match truck_file.truck {
+                        TruckEnum::Truck1 => &mut solution.truck1,
+                        TruckEnum::Truck2 => &mut solution.truck2,
+                    };
+                    let route = truck.get_mut(day_file.day).get_mut(route_file.trip);
+                    if !route.is_empty() {
+                        return Err(format!(
+                            "{:?} {:?} {} is in the file twice",
+                            truck_file.truck, day_file.day, route_file.trip
+                        ));
+                    }
+
+                    if let Some(name) = &route_file.dump_site {
+                        route.apply_change_dump_site(dump_site_by_name(name)?);
+                    }
+
+                    let order_indices = route_file.orders.iter().map(to_index).collect::<Result<Vec<OrderIndex>, String>>()?;
+                    let customer_count = order_indices.len();
+                    let mut unload_stops = route_file.unload_stops.iter().peekable();
+                    for (i, order_index) in order_indices.into_iter().map(Some).chain([None]).enumerate() {
+                        while let Some(unload_stop) = unload_stops.next_if(|unload_stop| unload_stop.after == i) {
+                            add_to_end(route, dump_site_by_name(&unload_stop.dump_site)?);
+                        }
+                        if let Some(order_index) = order_index {
+                            add_to_end(route, order_index);
+                            solution.order_flags.add_order(order_index, day_file.day);
+                        }
+                    }
+                    if let Some(unload_stop) = unload_stops.next() {
+                        return Err(format!(
+                            "{:?} {:?} {} has an unload stop after customer {}, but only {} customers",
+                            truck_file.truck,
+                            day_file.day,
+                            route_file.trip,
+                            unload_stop.after,
+                            customer_count
+                        ));
+                    }
+                    route.time = route_file.time;
+                    route.capacity = route_file.capacity;
+                }
+            }
+        }
+
+        for locked in &self.locks.orders {
+            solution.locks.set_order(to_index(&locked.order)?, locked.lock);
+        }
+        for frozen in &self.locks.frozen_routes {
+            solution.locks.set_frozen(frozen.truck, frozen.day, frozen.trip, true);
+        }
+
+        solution.score = self.score.total;
+        solution.check_consistency()?;
+        Ok(solution)
+    }
+}
+
+fn dump_site_by_name(name: &str) -> Result<OrderIndex, String> {
+    get_sites()
+        .dump_sites
+        .iter()
+        .copied()
+        .find(|dump_site| get_orders()[*dump_site].place == name)
+        .ok_or_else(|| format!("{name} is not a dump site"))
+}
+
+fn add_to_end(route: &mut Route, order_index: OrderIndex) {
+    let tail = route.linked_vector.get_tail_index().unwrap();
+    let before_tail = route.linked_vector.get_prev_index(tail).unwrap();
+    route.apply_add_order(before_tail, order_index);
+}
+
+impl LocksFile {
+    fn new(locks: &Locks) -> Self {
+        let orders = get_orders();
+        LocksFile {
+            orders: locks
+                .locked_orders()
+                .map(|(order_index, lock)| LockedOrder {
+                    order: orders[order_index].order,
+                    lock,
+                })
+                .collect(),
+            frozen_routes: [TruckEnum::Truck1, TruckEnum::Truck2]
+                .into_iter()
+                .flat_map(|truck| DayEnum::ALL.map(|day| (truck, day)))
+                .flat_map(|(truck, day)| [TimeOfDay::Morning, TimeOfDay::Afternoon].map(|trip| FrozenRoute { truck, day, trip }))
+                .filter(|route| locks.is_frozen(route.truck, route.day, route.trip))
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty() && self.frozen_routes.is_empty()
+    }
+}
+
+impl RouteFile {
+    fn new(route: &Route, truck: TruckEnum, trip: TimeOfDay) -> Self {
+        let orders = get_orders();
+        let sites = get_sites();
+        let lv = &route.linked_vector;
+        let dump_site = route.dump_site();
+
+        let mut customers = Vec::new();
+        let mut unload_stops = Vec::new();
+        for (node_index, order_index) in lv.iter() {
+            if Some(node_index) == lv.get_head_index() || Some(node_index) == lv.get_tail_index() {
+                continue;
+            }
+            if sites.is_site(*order_index) {
+                unload_stops.push(UnloadStopFile {
+                    after: customers.len(),
+                    dump_site: orders[*order_index].place.clone(),
+                });
+            } else {
+                customers.push(orders[*order_index].order);
+            }
+        }
+
+        RouteFile {
+            trip,
+            time: route.time,
+            capacity: route.capacity,
+            orders: customers,
+            dump_site: (dump_site != sites.default_dump_site(truck)).then(|| orders[dump_site].place.clone()),
+            unload_stops,
+        }
+    }
+}
+
+/// Writes the solution as "{iteration} {score}.json", next to the text version.
+pub fn print_solution_json(solution: &Solution, dir: &String, iteration: u32, run: RunInfo) -> std::io::Result<()> {
+    let file_name = format!("{}/{} {}.json", dir, iteration, solution.score / 6000);
+    let mut buffer = File::create(file_name)?;
+    let solution_file = SolutionFile::new(solution, run);
+    writeln!(buffer, "{}", serde_json::to_string_pretty(&solution_file)?)?;
+    Ok(())
+}
+
+pub fn load_solution_json(path: &str) -> Result<(Solution, RunInfo), Box<dyn Error + Send + Sync>> {
+    let solution_file: SolutionFile = serde_json::from_str(&read_to_string(path)?)?;
+    let solution = solution_file.to_solution()?;
+    Ok((solution, solution_file.run))
+}
+
+/// Loads a .json solution, anything else gets read as the text format that print_solution writes.
+pub fn load_solution(path: &str) -> Result<Solution, Box<dyn Error + Send + Sync>> {
+    if path.ends_with(".json") {
+        Ok(load_solution_json(path)?.0)
+    } else {
+        Ok(Solution::from_file(path))
+    }
+}
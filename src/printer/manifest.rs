@@ -0,0 +1,305 @@
+use crate::{get_orders, get_sites};
+use crate::resource::{HALF_HOUR, MINUTE, TRUCK_CAPACITY, Time};
+use crate::simulated_annealing::day::{Day, TimeOfDay};
+use crate::simulated_annealing::neighbor_move::evaluation_helper::time_between_two_nodes;
+use crate::simulated_annealing::route::Route;
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use crate::simulated_annealing::week::{DayEnum, Week};
+use std::fs::File;
+use std::io::Write;
+
+/// The drivers leave the depot at 6:00, every arrival time in the manifest counts from here.
+pub const START_OF_DAY: Time = 6 * 60 * MINUTE;
+
+/// One line on the manifest: either a customer, or a visit to the dropoff at the end of a trip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestStop {
+    pub truck: TruckEnum,
+    pub day: DayEnum,
+    pub trip: TimeOfDay,
+    /// Counts up over the whole day, so the afternoon continues where the morning stopped.
+    pub sequence: usize,
+    /// None for the dropoff
+    pub order: Option<u16>,
+    pub place: String,
+    pub container_count: u8,
+    pub container_volume: u16,
+    /// Time of day in centiseconds
+    pub arrival: Time,
+    /// Time of day in centiseconds, after emptying the containers (or unloading at the dropoff) and the break
+    pub departure: Time,
+    /// The break the driver takes here before driving on, 0 for most stops
+    pub break_time: Time,
+    /// What's in the truck after this stop
+    pub load: u32,
+}
+
+/// Walks through every route of the truck and works out when it gets where, and how full it is.
+pub fn build_manifest(truck: &Week, truck_enum: TruckEnum) -> Vec<ManifestStop> {
+    let mut stops = Vec::new();
+    for day_enum in DayEnum::ALL {
+        build_day(truck.get(day_enum), truck_enum, day_enum, &mut stops);
+    }
+    stops
+}
+
+fn build_day(day: &Day, truck_enum: TruckEnum, day_enum: DayEnum, stops: &mut Vec<ManifestStop>) {
+    let mut clock = START_OF_DAY;
+    let mut sequence = 0;
+    for time_of_day in [TimeOfDay::Morning, TimeOfDay::Afternoon] {
+        let route = day.get(time_of_day);
+        if route.is_empty() {
+            continue;
+        }
+        clock = build_trip(route, truck_enum, day_enum, time_of_day, clock, &mut sequence, stops);
+    }
+}
+
+/// Returns the time at which the truck is back at its depot.
+fn build_trip(
+    route: &Route,
+    truck: TruckEnum,
+    day: DayEnum,
+    trip: TimeOfDay,
+    start: Time,
+    sequence: &mut usize,
+    stops: &mut Vec<ManifestStop>,
+) -> Time {
+    let orders = get_orders();
+    let sites = get_sites();
+    let mut clock = start;
+    let mut load = 0;
+    let mut previous = None;
+
+    let driver = route.driver();
+    let route_orders: Vec<_> = route.linked_vector.iter().map(|(_, order_index)| *order_index).collect();
+    let mut breaks = vec![false; route_orders.len()];
+    driver.plan_rests(&route_orders, |position| breaks[position] = true);
+
+    for (position, (_, order_index)) in route.linked_vector.iter().enumerate() {
+        let order = &orders[*order_index];
+        if let Some(previous) = previous {
+            clock += time_between_two_nodes(previous, order.matrix_id);
+            *sequence += 1;
+
+            let arrival = clock;
+            let is_dropoff = sites.is_site(*order_index);
+            if is_dropoff {
+                clock += HALF_HOUR;
+                load = 0;
+            } else {
+                clock += order.emptying_time;
+                load += order.total_container_volume;
+            }
+            let break_time = if breaks[position] { driver.break_length } else { 0 };
+            clock += break_time;
+
+            stops.push(ManifestStop {
+                truck,
+                day,
+                trip,
+                sequence: *sequence,
+                order: (!is_dropoff).then_some(order.order),
+                place: order.place.clone(),
+                container_count: order.container_count,
+                container_volume: order.container_volume,
+                arrival,
+                departure: clock,
+                break_time,
+                load,
+            });
+        }
+        previous = Some(order.matrix_id);
+    }
+    clock + time_between_two_nodes(orders[route.dump_site()].matrix_id, orders[route.depot()].matrix_id)
+}
+
+/// Writes manifest.csv and manifest.html to the given folder.
+pub fn print_manifest(truck1: &Week, truck2: &Week, dir: &str) -> std::io::Result<()> {
+    let mut stops = build_manifest(truck1, TruckEnum::Truck1);
+    stops.extend(build_manifest(truck2, TruckEnum::Truck2));
+
+    let mut csv = File::create(format!("{dir}/manifest.csv"))?;
+    write_csv(&mut csv, &stops)?;
+
+    let mut html = File::create(format!("{dir}/manifest.html"))?;
+    write_html(&mut html, &stops)?;
+    Ok(())
+}
+
+/// Semicolon separated, just like the files we get our data from.
+pub fn write_csv(buffer: &mut impl Write, stops: &[ManifestStop]) -> std::io::Result<()> {
+    writeln!(
+        buffer,
+        "Truck;Day;Trip;Stop;Order;Place;Containers;VolumePerContainer;Arrival;Departure;BreakMinutes;Load;Capacity"
+    )?;
+    for stop in stops {
+        writeln!(
+            buffer,
+            "{};{:?};{};{};{};{};{};{};{};{};{};{};{}",
+            stop.truck.number(),
+            stop.day,
+            stop.trip,
+            stop.sequence,
+            stop.order.map(|order| order.to_string()).unwrap_or_default(),
+            escape_csv(&stop.place),
+            stop.container_count,
+            stop.container_volume,
+            clock_time(stop.arrival),
+            clock_time(stop.departure),
+            stop.break_time / MINUTE,
+            stop.load,
+            TRUCK_CAPACITY,
+        )?;
+    }
+    Ok(())
+}
+
+/// One page with a table for every day of every truck, which can be printed straight from the browser.
+pub fn write_html(buffer: &mut impl Write, stops: &[ManifestStop]) -> std::io::Result<()> {
+    writeln!(buffer, "<!DOCTYPE html>")?;
+    writeln!(buffer, "<html><head><meta charset=\"utf-8\"><title>Route manifest</title>")?;
+    writeln!(
+        buffer,
+        "<style>\
+        body {{ font-family: sans-serif; }} \
+        table {{ border-collapse: collapse; margin-bottom: 2em; }} \
+        th, td {{ border: 1px solid #999; padding: 2px 8px; text-align: left; }} \
+        tr.dropoff {{ background: #ddd; font-weight: bold; }} \
+        td.overflow {{ color: red; }} \
+        section {{ page-break-after: always; }}\
+        </style>"
+    )?;
+    writeln!(buffer, "</head><body>")?;
+
+    for day_stops in stops.chunk_by(|a, b| a.truck == b.truck && a.day == b.day) {
+        let first = &day_stops[0];
+        writeln!(buffer, "<section>")?;
+        writeln!(buffer, "<h2>Truck {}, {:?}</h2>", first.truck.number(), first.day)?;
+        writeln!(
+            buffer,
+            "<table><tr><th>Stop</th><th>Trip</th><th>Order</th><th>Place</th><th>Containers</th>\
+            <th>Arrival</th><th>Departure</th><th>Break</th><th>Load</th></tr>"
+        )?;
+        for stop in day_stops {
+            let class = if stop.order.is_none() { " class=\"dropoff\"" } else { "" };
+            let load_class = if stop.load > TRUCK_CAPACITY { " class=\"overflow\"" } else { "" };
+            writeln!(
+                buffer,
+                "<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{} &times; {}L</td>\
+                <td>{}</td><td>{}</td><td>{}</td><td{}>{}L / {}L</td></tr>",
+                class,
+                stop.sequence,
+                stop.trip,
+                stop.order.map(|order| order.to_string()).unwrap_or_default(),
+                escape_html(&stop.place),
+                stop.container_count,
+                stop.container_volume,
+                clock_time(stop.arrival),
+                clock_time(stop.departure),
+                if stop.break_time > 0 { format!("{} min", stop.break_time / MINUTE) } else { String::new() },
+                load_class,
+                stop.load,
+                TRUCK_CAPACITY,
+            )?;
+        }
+        writeln!(buffer, "</table>")?;
+        writeln!(buffer, "</section>")?;
+    }
+
+    writeln!(buffer, "</body></html>")?;
+    Ok(())
+}
+
+/// Quotes the field if it has a separator, a quote or a line break in it, and doubles the quotes in it.
+fn escape_csv(text: &str) -> String {
+    if text.contains([';', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// hh:mm, rounded down to the minute
+pub(crate) fn clock_time(time: Time) -> String {
+    let minutes = time / MINUTE;
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ManifestStop, START_OF_DAY, build_manifest, clock_time, write_csv};
+    use crate::datastructures::linked_vectors::LinkedVector;
+    use crate::simulated_annealing::day::TimeOfDay;
+    use crate::simulated_annealing::simulated_annealing::TruckEnum;
+    use crate::simulated_annealing::week::{DayEnum, Week};
+    use crate::init_orders_for_tests;
+
+    #[test]
+    fn clock_time_is_hours_and_minutes() {
+        assert_eq!(clock_time(START_OF_DAY), "06:00");
+        assert_eq!(clock_time(START_OF_DAY + 90 * 6000 + 5999), "07:30");
+    }
+
+    #[test]
+    fn places_with_separators_are_quoted() {
+        let stop = ManifestStop {
+            truck: TruckEnum::Truck1,
+            day: DayEnum::Monday,
+            trip: TimeOfDay::Morning,
+            sequence: 1,
+            order: Some(7),
+            place: "Bakkerij \"De Zon\"; achterom".to_string(),
+            container_count: 1,
+            container_volume: 140,
+            arrival: START_OF_DAY,
+            departure: START_OF_DAY,
+            break_time: 0,
+            load: 140,
+        };
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &[stop]).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let line = csv.lines().nth(1).unwrap();
+
+        assert!(line.contains(";\"Bakkerij \"\"De Zon\"\"; achterom\";"));
+        // outside of the quotes, there are just as many separators as in the header
+        let outside_quotes: String = line.split('"').step_by(2).collect();
+        assert_eq!(outside_quotes.matches(';').count(), csv.lines().next().unwrap().matches(';').count());
+    }
+
+    #[test]
+    fn trips_take_as_long_as_the_route() {
+        init_orders_for_tests();
+        let mut truck = Week::new(TruckEnum::Truck1);
+        let day = truck.get_mut(DayEnum::Wednesday);
+        for (time_of_day, orders) in [(TimeOfDay::Morning, [0, 1]), (TimeOfDay::Afternoon, [2, 3])] {
+            let route = day.get_mut(time_of_day);
+            for order in orders {
+                let head = route.linked_vector.get_head_index().unwrap();
+                route.apply_add_order(head, order);
+            }
+        }
+
+        let stops = build_manifest(&truck, TruckEnum::Truck1);
+        let day = truck.get(DayEnum::Wednesday);
+        // two customers and a dropoff per trip
+        assert_eq!(stops.len(), 6);
+        assert!(stops.iter().all(|stop| stop.day == DayEnum::Wednesday));
+        assert_eq!(stops.iter().map(|stop| stop.sequence).collect::<Vec<_>>(), [1, 2, 3, 4, 5, 6]);
+
+        let morning_end = stops[2].departure;
+        assert_eq!(stops[2].order, None);
+        assert_eq!(stops[2].load, 0);
+        assert_eq!(morning_end - START_OF_DAY, day.morning.time);
+        assert_eq!(stops[5].departure - morning_end, day.afternoon.time);
+        assert_eq!(stops[1].load, day.morning.capacity);
+    }
+}
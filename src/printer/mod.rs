@@ -7,6 +7,14 @@ use crate::simulated_annealing::week::{DayEnum, Week};
 use std::fs::File;
 use std::io::Write;
 
+pub mod checkpoint;
+pub mod diff;
+pub mod geojson;
+pub mod json;
+pub mod manifest;
+pub mod run_log;
+pub mod score;
+
 pub fn print_solution(solution: &Solution, dir: &String, iteration: u32) -> std::io::Result<()>
 {
     let file_name = format!("{}/{} {}.txt", dir, iteration, solution.score/6000);
@@ -17,6 +25,22 @@ pub fn print_solution(solution: &Solution, dir: &String, iteration: u32) -> std:
 
     Ok(())
 }
+/// Writes everything we know about a solution that failed the paranoid checks.
+/// The solution itself goes in the normal format, so it can be loaded again with Solution::from_file.
+pub fn print_inconsistency(solution: &Solution, dir: &String, step: u32, inconsistency: &str, last_move: &str) -> std::io::Result<()>
+{
+    print_solution(solution, dir, step)?;
+
+    let mut buffer = File::create(format!("{}/inconsistency.txt", dir))?;
+    writeln!(buffer, "step: {}", step)?;
+    writeln!(buffer, "stored score: {}", solution.score)?;
+    writeln!(buffer, "inconsistency: {}", inconsistency)?;
+    writeln!(buffer)?;
+    writeln!(buffer, "last applied move:")?;
+    writeln!(buffer, "{}", last_move)?;
+
+    Ok(())
+}
 fn print_truck_schedule(buffer: &mut File, truck: &Week, truck_enum: TruckEnum)
     -> std::io::Result<()> {
     let truck_id = match truck_enum {
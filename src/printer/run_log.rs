@@ -0,0 +1,186 @@
+use crate::parser::dataset_hash;
+use crate::resource::Time;
+use crate::simulated_annealing::neighbor_chooser::MOVE_NAMES;
+use crate::simulated_annealing::search_statistics::SearchStatistics;
+use crate::simulated_annealing::simulated_annealing::SolverParameters;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::process::Command;
+
+/// Everything needed to find out later how the solutions in a run folder were made.
+/// Gets written to run.json when the search starts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub started: String,
+    pub thread: usize,
+    pub thread_count: usize,
+    pub seed: u64,
+    pub parameters: SolverParameters,
+    /// See parser::dataset_hash
+    pub dataset_hash: String,
+    /// None if the program wasn't started from inside the git repository
+    pub git_revision: Option<String>,
+}
+
+impl RunManifest {
+    pub fn new(started: String, thread: usize, thread_count: usize, seed: u64, parameters: SolverParameters) -> Self {
+        RunManifest {
+            started,
+            thread,
+            thread_count,
+            seed,
+            parameters,
+            dataset_hash: format!("{:016x}", dataset_hash()),
+            git_revision: git_revision(),
+        }
+    }
+}
+
+pub fn print_run_manifest(manifest: &RunManifest, dir: &str) -> std::io::Result<()> {
+    let mut buffer = File::create(format!("{dir}/run.json"))?;
+    writeln!(buffer, "{}", serde_json::to_string_pretty(manifest)?)?;
+    Ok(())
+}
+
+/// The commit we're running, with "-dirty" behind it if there are uncommitted changes.
+fn git_revision() -> Option<String> {
+    let output = Command::new("git").args(["rev-parse", "HEAD"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let revision = String::from_utf8(output.stdout).ok()?.trim().to_string();
+
+    let dirty = Command::new("git")
+        .args(["status", "--porcelain", "--untracked-files=no"])
+        .output()
+        .is_ok_and(|status| !status.stdout.is_empty());
+
+    Some(if dirty { format!("{revision}-dirty") } else { revision })
+}
+
+/// One line in the trace
+pub struct TracePoint {
+    pub step: u32,
+    pub iteration: u32,
+    pub elapsed_seconds: f64,
+    pub temp: f32,
+    pub score: Time,
+    pub best_score: Time,
+    /// Only the moves since the previous line
+    pub statistics: SearchStatistics,
+}
+
+/// Writes trace.csv, which shows how the search converged.
+pub struct TraceWriter {
+    buffer: BufWriter<File>,
+}
+
+impl TraceWriter {
+    pub fn new(dir: &str) -> std::io::Result<Self> {
+        let mut buffer = BufWriter::new(File::create(format!("{dir}/trace.csv"))?);
+
+        write!(buffer, "Step;Iteration;Seconds;Temperature;Score;BestScore;AcceptanceRate")?;
+        for name in MOVE_NAMES {
+            write!(buffer, ";{name}_tried;{name}_accepted;{name}_improved")?;
+        }
+        writeln!(buffer)?;
+
+        Ok(TraceWriter { buffer })
+    }
+
+    /// Continues the trace of a resumed search.
+    pub fn append(dir: &str) -> std::io::Result<Self> {
+        let file = OpenOptions::new().append(true).open(format!("{dir}/trace.csv"))?;
+        Ok(TraceWriter { buffer: BufWriter::new(file) })
+    }
+
+    pub fn write(&mut self, point: &TracePoint) -> std::io::Result<()> {
+        write!(
+            self.buffer,
+            "{};{};{:.3};{};{};{};{:.4}",
+            point.step,
+            point.iteration,
+            point.elapsed_seconds,
+            point.temp,
+            point.score,
+            point.best_score,
+            point.statistics.acceptance_rate(),
+        )?;
+        for i in 0..MOVE_NAMES.len() {
+            write!(
+                self.buffer,
+                ";{};{};{}",
+                point.statistics.tried[i], point.statistics.accepted[i], point.statistics.improved[i]
+            )?;
+        }
+        writeln!(self.buffer)?;
+        // Flush every line, so the trace is still complete when the search gets killed
+        self.buffer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RunManifest, TracePoint, TraceWriter, print_run_manifest};
+    use crate::simulated_annealing::constraints::ConstraintModes;
+    use crate::simulated_annealing::neighbor_chooser::MOVE_COUNT;
+    use crate::simulated_annealing::search_statistics::SearchStatistics;
+    use crate::simulated_annealing::simulated_annealing::SolverParameters;
+    use std::fs::{create_dir_all, read_to_string};
+
+    fn test_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("route_finder_{name}_{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+        dir.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn manifest_can_be_read_back() {
+        let dir = test_dir("manifest");
+        let parameters = SolverParameters {
+            temp: 50_000.0,
+            end_temp: 10.0,
+            reheating_temp: 4000.0,
+            q: 500_000,
+            a: 0.99,
+            max_iterations: 100,
+            num_perturbations: 10,
+            search_weights: [1; MOVE_COUNT],
+            perturbation_weights: [0; MOVE_COUNT],
+            constraint_modes: ConstraintModes::default(),
+        };
+        let manifest = RunManifest::new("now".to_string(), 2, 4, 1234, parameters);
+        print_run_manifest(&manifest, &dir).unwrap();
+
+        let read: RunManifest = serde_json::from_str(&read_to_string(format!("{dir}/run.json")).unwrap()).unwrap();
+        assert_eq!(read, manifest);
+        assert_eq!(read.dataset_hash.len(), 16);
+    }
+
+    #[test]
+    fn trace_lines_match_the_header() {
+        let dir = test_dir("trace");
+        let mut trace = TraceWriter::new(&dir).unwrap();
+        let mut statistics = SearchStatistics::default();
+        statistics.record(0, true, true);
+        statistics.record(0, false, false);
+        trace
+            .write(&TracePoint {
+                step: 100_000,
+                iteration: 1,
+                elapsed_seconds: 1.5,
+                temp: 4000.0,
+                score: 600_000,
+                best_score: 500_000,
+                statistics,
+            })
+            .unwrap();
+
+        let contents = read_to_string(format!("{dir}/trace.csv")).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].split(';').count(), lines[1].split(';').count());
+        assert!(lines[1].starts_with("100000;1;1.500;4000;600000;500000;0.5000;2;1;1;"));
+    }
+}
@@ -0,0 +1,80 @@
+use crate::resource::{MINUTE, Time};
+use crate::simulated_annealing::score_calculator::ScoreBreakdown;
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use crate::simulated_annealing::solution::Solution;
+use std::fs::File;
+use std::io::Write;
+
+/// Writes the breakdown as "{iteration} {score} score.txt", next to the solution.
+pub fn print_score_breakdown(solution: &Solution, dir: &String, iteration: u32) -> std::io::Result<()> {
+    let file_name = format!("{}/{} {} score.txt", dir, iteration, solution.score / 6000);
+    let mut buffer = File::create(file_name)?;
+    write_score_breakdown(&mut buffer, &ScoreBreakdown::new(solution))
+}
+
+/// Writes the total score and its parts, how far every constraint is broken, and the kilometres, fuel and CO2.
+/// Then the time of every truck, with every day split the same way and what it breaks,
+/// and at the end the orders that aren't planned as often as they should be, with their penalty.
+pub fn write_score_breakdown(buffer: &mut impl Write, breakdown: &ScoreBreakdown) -> std::io::Result<()> {
+    writeln!(buffer, "Total:            {}", minutes(breakdown.total))?;
+    writeln!(buffer, "  Driving:        {}", minutes(breakdown.driving_time))?;
+    writeln!(buffer, "  Emptying:       {}", minutes(breakdown.emptying_time))?;
+    writeln!(buffer, "  Dropoff:        {}", minutes(breakdown.dropoff_time))?;
+    writeln!(buffer, "  Breaks:         {}", minutes(breakdown.break_time))?;
+    writeln!(buffer, "  Objective:      {}", minutes(breakdown.objective_cost))?;
+    writeln!(buffer, "  Balance:        {}", minutes(breakdown.balance_cost))?;
+    writeln!(buffer, "  Penalty:        {} ({} orders)", minutes(breakdown.penalty), breakdown.unfinished_orders.len())?;
+    writeln!(buffer, "Time overflow:    {}", minutes(breakdown.time_overflow))?;
+    writeln!(buffer, "Capacity overflow: {}L", breakdown.capacity_overflow)?;
+    writeln!(buffer, "Driving overflow: {}", minutes(breakdown.continuous_driving_overflow))?;
+    writeln!(buffer, "Weekly overflow:  {}", minutes(breakdown.weekly_overflow))?;
+    writeln!(buffer, "Distance:         {:.1} km", breakdown.emissions.km)?;
+    writeln!(buffer, "Fuel:             {:.1} L", breakdown.emissions.liters)?;
+    writeln!(buffer, "CO2:              {:.1} kg", breakdown.emissions.kg_co2)?;
+    writeln!(buffer)?;
+
+    for truck in [TruckEnum::Truck1, TruckEnum::Truck2] {
+        writeln!(buffer, "{truck:?}: {}", minutes(breakdown.truck_total(truck)))?;
+        for day in breakdown.days.iter().filter(|day| day.truck == truck) {
+            write!(
+                buffer,
+                "  {:<10} {} (driving {}, emptying {}, dropoff {}, breaks {}, {:.1} km)",
+                format!("{:?}", day.day),
+                minutes(day.total()),
+                minutes(day.driving_time),
+                minutes(day.emptying_time),
+                minutes(day.dropoff_time),
+                minutes(day.break_time),
+                day.emissions.km,
+            )?;
+            if day.time_overflow > 0 {
+                write!(buffer, ", {} too long", minutes(day.time_overflow))?;
+            }
+            if day.capacity_overflow > 0 {
+                write!(buffer, ", {}L too much", day.capacity_overflow)?;
+            }
+            if day.continuous_driving_overflow > 0 {
+                write!(buffer, ", {} driven too long without a rest", minutes(day.continuous_driving_overflow))?;
+            }
+            writeln!(buffer)?;
+        }
+    }
+    writeln!(buffer)?;
+
+    writeln!(buffer, "Unfinished orders:")?;
+    for order in &breakdown.unfinished_orders {
+        writeln!(
+            buffer,
+            "  {}: planned {} of {} times, {}",
+            order.order,
+            order.planned,
+            order.frequency,
+            minutes(order.penalty)
+        )?;
+    }
+    Ok(())
+}
+
+fn minutes(time: Time) -> String {
+    format!("{:.1} min", time as f64 / MINUTE as f64)
+}
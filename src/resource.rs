@@ -1,6 +1,9 @@
-use std::{fmt::Display, str::FromStr};
+use std::{collections::BTreeSet, fmt::Display, str::FromStr};
 
 use petgraph::{matrix_graph::DiMatrix, prelude::*};
+use rand::Rng;
+use rand::distr::{Distribution, StandardUniform};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub struct Company {
@@ -15,8 +18,37 @@ pub struct Company {
     pub y_coordinate: u32, // maybe turn this into a nalgebra vector if we need it
     pub total_container_volume: u32,
     pub penalty: Time,
+    /// The sets of days the order may be collected on, every one with `frequency` days.
+    /// These are the defaults of the frequency, unless the order file has other ones for this customer.
+    pub allowed_patterns: Vec<DayPattern>,
+    /// Set for the depots and dump sites, which come after the customers in the orders
+    pub site: Option<Site>,
+}
+
+/// What a location that isn't a customer is used for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Site {
+    /// Trucks can unload here
+    pub is_dump: bool,
+    /// The trucks that start every trip from here
+    pub home_of: Vec<TruckEnum>,
+}
+
+impl Company {
+    /// How long a stop in the middle of a route takes: emptying the containers at a customer,
+    /// or unloading the truck at a dump site.
+    pub fn service_time(&self) -> Time {
+        match &self.site {
+            Some(site) if site.is_dump => HALF_HOUR,
+            _ => self.emptying_time,
+        }
+    }
 }
 pub type MatrixID = NodeIndex<u16>;
+/// Where an order is in the orders, the sites come after the customers
+pub type OrderIndex = usize;
+/// A set of weekdays, with the same bits as the order flags: Monday is 0b10000 and Friday is 0b00001.
+pub type DayPattern = u8;
 /// time in centiseconds
 pub type Time = i32;
 /// 60 * 100 centiseconds
@@ -25,6 +57,8 @@ pub const MINUTE:Time = 60*100;
 pub const HALF_HOUR:Time = 30*60*100;
 /// 12 * 60 * 60 * 100 centiseconds;
 pub const FULL_DAY:Time = 12*60*60*100;
+/// How much volume fits in a truck before it has to unload at a dump site
+pub const TRUCK_CAPACITY:u32 = 100_000;
 
 #[derive(Debug, Clone, Copy)]
 pub enum Frequency {
@@ -33,6 +67,24 @@ pub enum Frequency {
     Twice = 2,
     Thrice = 3,
     FourTimes = 4,
+    /// Every weekday
+    FiveTimes = 5,
+}
+
+impl Frequency {
+    /// The day patterns a customer with this frequency is collected on, if the order file doesn't say otherwise.
+    /// Twice a week has to be Monday-Thursday or Tuesday-Friday, three times a week Monday-Wednesday-Friday.
+    /// Once and four times a week can be any days, five times a week is every day.
+    pub fn default_patterns(self) -> &'static [DayPattern] {
+        match self {
+            Frequency::None => &[],
+            Frequency::Once => &[0b10000, 0b01000, 0b00100, 0b00010, 0b00001],
+            Frequency::Twice => &[0b10010, 0b01001],
+            Frequency::Thrice => &[0b10101],
+            Frequency::FourTimes => &[0b01111, 0b10111, 0b11011, 0b11101, 0b11110],
+            Frequency::FiveTimes => &[0b11111],
+        }
+    }
 }
 
 impl FromStr for Frequency {
@@ -44,11 +96,107 @@ impl FromStr for Frequency {
             "2PWK" => Ok(Frequency::Twice),
             "3PWK" => Ok(Frequency::Thrice),
             "4PWK" => Ok(Frequency::FourTimes),
+            "5PWK" => Ok(Frequency::FiveTimes),
             _ => Err(format!("Invalid frequency: {}", s)),
         }
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TruckEnum {
+    Truck1,
+    Truck2,
+}
+impl TruckEnum {
+    /// The number the drivers know the truck by
+    pub fn number(self) -> u8 {
+        match self {
+            TruckEnum::Truck1 => 1,
+            TruckEnum::Truck2 => 2,
+        }
+    }
+}
+impl Distribution<TruckEnum> for StandardUniform {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> TruckEnum {
+        match rng.random_range(0..2) {
+            0 => TruckEnum::Truck1,
+            _ => TruckEnum::Truck2,
+        }
+    }
+}
+
+/// Where the sites are in the orders, so routes can find their depot and dump sites without searching.
+#[derive(Debug, Clone)]
+pub struct Sites {
+    /// The order index of the first site. Everything before it is a customer.
+    pub first: OrderIndex,
+    /// Where every truck starts its trips, indexed by truck
+    pub home_depots: [OrderIndex; 2],
+    /// Every site where trucks can unload
+    pub dump_sites: Vec<OrderIndex>,
+    /// The dump site closest to the home depot of every truck. New routes unload here.
+    pub default_dump_sites: [OrderIndex; 2],
+}
+
+impl Sites {
+    /// Finds the sites at the end of the orders. Every truck needs exactly one home depot,
+    /// and there has to be at least one dump site.
+    pub fn new(orders: &[Company], travel_table: &TravelTable) -> Result<Self, String> {
+        let first = orders
+            .iter()
+            .position(|order| order.site.is_some())
+            .ok_or("There are no depots or dump sites")?;
+        if let Some(order) = orders[first..].iter().find(|order| order.site.is_none()) {
+            return Err(format!("Order {} comes after the sites", order.order));
+        }
+        let site = |order_index: OrderIndex| orders[order_index].site.as_ref().unwrap();
+
+        let dump_sites: Vec<OrderIndex> = (first..orders.len()).filter(|order_index| site(*order_index).is_dump).collect();
+        if dump_sites.is_empty() {
+            return Err("There are no dump sites".to_string());
+        }
+
+        let home_depot = |truck: TruckEnum| {
+            let depots: Vec<OrderIndex> = (first..orders.len())
+                .filter(|order_index| site(*order_index).home_of.contains(&truck))
+                .collect();
+            match depots[..] {
+                [depot] => Ok(depot),
+                [] => Err(format!("{truck:?} has no home depot")),
+                _ => Err(format!("{truck:?} has more than one home depot")),
+            }
+        };
+        let home_depots = [home_depot(TruckEnum::Truck1)?, home_depot(TruckEnum::Truck2)?];
+
+        let closest_dump_site = |depot: OrderIndex| {
+            *dump_sites
+                .iter()
+                .min_by_key(|dump_site| travel_table.travel_time(orders[depot].matrix_id, orders[**dump_site].matrix_id))
+                .unwrap()
+        };
+        let default_dump_sites = home_depots.map(closest_dump_site);
+
+        Ok(Sites {
+            first,
+            home_depots,
+            dump_sites,
+            default_dump_sites,
+        })
+    }
+
+    pub fn is_site(&self, order_index: OrderIndex) -> bool {
+        order_index >= self.first
+    }
+
+    pub fn home_depot(&self, truck: TruckEnum) -> OrderIndex {
+        self.home_depots[truck as usize]
+    }
+
+    pub fn default_dump_site(&self, truck: TruckEnum) -> OrderIndex {
+        self.default_dump_sites[truck as usize]
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Distance {
     pub absolute_distance: u16,
@@ -66,3 +214,57 @@ impl Display for Distance {
 }
 
 pub type DistanceMatrix = DiMatrix<MatrixID, Distance>;
+
+/// A dense copy of the distance matrix, indexed by MatrixID.
+///
+/// Getting a value out of the petgraph DiMatrix goes through a couple of Option checks and an indirection per lookup.
+/// Every evaluation does multiple lookups, so this is the hot path of the whole solver.
+/// It has a row and a column for every MatrixID up to the largest one the orders use, also for the ids no order uses.
+/// That takes more memory than numbering the used ids again, but it saves a lookup from MatrixID to row on every call.
+pub struct TravelTable {
+    node_count: usize,
+    travel_times: Vec<Time>,
+    distances: Vec<u16>,
+}
+
+impl TravelTable {
+    /// Builds the table for all locations that are used by the given orders.
+    pub fn new(distance_matrix: &DistanceMatrix, orders: &[Company]) -> Self {
+        let used: BTreeSet<MatrixID> = orders.iter().map(|order| order.matrix_id).collect();
+        let node_count = used
+            .iter()
+            .map(|matrix_id| matrix_id.index() + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut travel_times = vec![0; node_count * node_count];
+        let mut distances = vec![0; node_count * node_count];
+        for &from in &used {
+            for &to in &used {
+                // Travelling to the same place takes no time, even if the file says otherwise.
+                if from == to {
+                    continue;
+                }
+                let distance = distance_matrix.get_edge_weight(from, to).unwrap();
+                travel_times[from.index() * node_count + to.index()] = distance.travel_time;
+                distances[from.index() * node_count + to.index()] = distance.absolute_distance;
+            }
+        }
+
+        TravelTable {
+            node_count,
+            travel_times,
+            distances,
+        }
+    }
+
+    #[inline(always)]
+    pub fn travel_time(&self, from: MatrixID, to: MatrixID) -> Time {
+        self.travel_times[from.index() * self.node_count + to.index()]
+    }
+
+    #[inline(always)]
+    pub fn distance(&self, from: MatrixID, to: MatrixID) -> u16 {
+        self.distances[from.index() * self.node_count + to.index()]
+    }
+}
@@ -1,4 +1,4 @@
-use crate::get_orders;
+use crate::{get_orders, get_sites};
 use crate::simulated_annealing::day::{Day, TimeOfDay};
 use crate::simulated_annealing::route::Route;
 use crate::simulated_annealing::week::{DayEnum, Week};
@@ -18,15 +18,14 @@ pub fn fixplzplzplzpl(solution: &mut Solution) {
         .filter(|(order_i, freq)| orders[**order_i].frequency as usize != **freq)
         .collect();
 
-    let dropoff_index = get_orders().len() - 1;
+    let sites = get_sites();
     let bad: Vec<usize> = a.iter().map(|(a, _)| **a).collect();
-    let filtered_bad: Vec<&usize> = bad.iter().filter(|i| **i != dropoff_index).collect();
+    let filtered_bad: Vec<&usize> = bad.iter().filter(|i| !sites.is_site(**i)).collect();
     let good_bad: Vec<usize> = filtered_bad.iter().map(|i| **i).collect();
 
     for bad_order in &good_bad{
-        for _ in 0..solution.order_flags.get_filled_count(*bad_order){
-            solution.unfilled_orders.push_back(*bad_order);
-        }
+        // The add move always adds all days of an order at once, so it only has to be in the list once.
+        solution.unfilled_orders.push_back(*bad_order);
         solution.order_flags.clear(*bad_order);
     }
 
@@ -0,0 +1,93 @@
+//! The workload balance in the objective: how evenly the time is spread over the days and over the trucks,
+//! and the weekly working time of the drivers.
+//! Both only depend on the 10 day times, so a move only has to say how much the days it touches change.
+
+use std::ops::Add;
+
+use crate::{get_drivers, get_objective};
+use crate::resource::Time;
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use crate::simulated_annealing::week::{DayEnum, Week};
+
+/// A time for every day of both trucks, indexed by truck and then by day.
+/// The day times of a solution, or how much a move changes them.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DayTimes(pub [[Time; 5]; 2]);
+
+impl DayTimes {
+    /// The time of every day, like Day::get_total_time, so without the objective cost.
+    pub fn of(truck1: &Week, truck2: &Week) -> Self {
+        DayTimes([truck1, truck2].map(|week| DayEnum::ALL.map(|day| week.get(day).get_total_time())))
+    }
+
+    /// Only this day changes.
+    pub fn single(truck: TruckEnum, day: DayEnum, time: Time) -> Self {
+        let mut day_times = DayTimes::default();
+        day_times.0[truck as usize][day as usize] = time;
+        day_times
+    }
+
+    pub fn truck_totals(&self) -> [Time; 2] {
+        self.0.map(|days| days.iter().sum())
+    }
+
+    /// What the balance costs in the score, in centiseconds. 0 when balancing is off.
+    pub fn balance_cost(&self) -> Time {
+        let objective = get_objective();
+        if !objective.balances() {
+            return 0;
+        }
+        let days: Vec<Time> = self.0.iter().flatten().copied().collect();
+        objective.balance_cost(&days, &self.truck_totals())
+    }
+
+    /// How much longer than allowed the weeks of the drivers are, summed over both.
+    pub fn weekly_overflow(&self) -> Time {
+        let drivers = get_drivers();
+        self.truck_totals().iter().zip(drivers).map(|(time, driver)| driver.weekly_overflow(*time)).sum()
+    }
+
+    /// How much the weekly overflow changes when the day times change by `delta`.
+    pub fn weekly_overflow_delta(&self, delta: &DayTimes) -> Time {
+        (*self + *delta).weekly_overflow() - self.weekly_overflow()
+    }
+
+    /// How much the balance cost changes when the day times change by `delta`.
+    pub fn balance_cost_delta(&self, delta: &DayTimes) -> Time {
+        if !get_objective().balances() {
+            return 0;
+        }
+        (*self + *delta).balance_cost() - self.balance_cost()
+    }
+}
+
+impl Add for DayTimes {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        for (days, other_days) in self.0.iter_mut().zip(other.0) {
+            for (time, other_time) in days.iter_mut().zip(other_days) {
+                *time += other_time;
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DayTimes;
+    use crate::resource::MINUTE;
+    use crate::simulated_annealing::simulated_annealing::TruckEnum;
+    use crate::simulated_annealing::week::DayEnum;
+
+    #[test]
+    fn deltas_add_up_per_day() {
+        let delta = DayTimes::single(TruckEnum::Truck2, DayEnum::Wednesday, 5 * MINUTE)
+            + DayTimes::single(TruckEnum::Truck2, DayEnum::Wednesday, -2 * MINUTE)
+            + DayTimes::single(TruckEnum::Truck1, DayEnum::Monday, MINUTE);
+        assert_eq!(delta.0[1][2], 3 * MINUTE);
+        assert_eq!(delta.0[0][0], MINUTE);
+        assert_eq!(delta.truck_totals(), [MINUTE, 3 * MINUTE]);
+    }
+}
@@ -0,0 +1,192 @@
+//! A lower bound on the score of any feasible plan, so we know how far the best solution can still be from the optimum.
+//!
+//! Every order is either done as often as it should be, or it costs its penalty. Done, every visit costs at least
+//! - its emptying time,
+//! - half of the quickest drive to it and half of the quickest drive away from it. Every drive has two ends,
+//!   so these halves together are never more than the driving of the plan, like in the assignment bound of a TSP,
+//! - the half hour at a dump site for every litre of it, divided by the capacity of the truck,
+//!   because a feasible load never has more than that.
+//!
+//! An order that's only partly done costs its penalty and its visits, so the cheapest of done and penalty is a bound.
+//! The breaks, the way back to the depots, the objective and the balance can only add to this.
+//! That only holds for plans that keep the constraints, and as long as no weight of the objective is negative.
+
+use crate::resource::{Company, HALF_HOUR, MINUTE, MatrixID, TRUCK_CAPACITY, Time};
+use crate::{get_objective, get_orders};
+use crate::simulated_annealing::neighbor_move::evaluation_helper::time_between_two_nodes;
+
+/// All times are in centiseconds. The parts add up to the total.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LowerBound {
+    pub total: Time,
+    /// Emptying the orders the bound does
+    pub emptying_time: Time,
+    /// The quickest drives to and from the orders the bound does
+    pub driving_time: Time,
+    /// Unloading what the bound collects
+    pub dropoff_time: Time,
+    /// The orders for which the penalty is cheaper than doing them
+    pub penalty: Time,
+    /// A negative weight makes some plans score less than their time, then the bound doesn't hold
+    pub negative_weights: bool,
+}
+
+impl LowerBound {
+    /// Goes over all pairs of locations, so compute it once and keep it.
+    pub fn new() -> Self {
+        Self::of(get_orders(), time_between_two_nodes, get_objective().has_negative_weights())
+    }
+
+    /// The bound of these orders with these travel times.
+    pub fn of(orders: &[Company], travel_time: impl Fn(MatrixID, MatrixID) -> Time, negative_weights: bool) -> Self {
+        let (mut emptying, mut driving, mut dropoff, mut penalty) = (0.0, 0.0, 0.0, 0.0);
+
+        for (order_index, order) in orders.iter().enumerate() {
+            let frequency = order.frequency as usize as f64;
+            // the depots and dump sites aren't orders
+            if order.site.is_some() || frequency == 0.0 {
+                continue;
+            }
+
+            // anything can come before or after it, the sites too
+            let others = || orders.iter().enumerate().filter(|(other_index, _)| *other_index != order_index);
+            let drive_to = others().map(|(_, other)| travel_time(other.matrix_id, order.matrix_id)).min().unwrap_or(0);
+            let drive_away = others().map(|(_, other)| travel_time(order.matrix_id, other.matrix_id)).min().unwrap_or(0);
+
+            let visit_emptying = order.emptying_time as f64;
+            let visit_driving = (drive_to + drive_away) as f64 / 2.0;
+            let visit_dropoff = HALF_HOUR as f64 * order.total_container_volume as f64 / TRUCK_CAPACITY as f64;
+            let order_penalty = frequency * order.emptying_time as f64 * 3.0;
+
+            if frequency * (visit_emptying + visit_driving + visit_dropoff) <= order_penalty {
+                emptying += frequency * visit_emptying;
+                driving += frequency * visit_driving;
+                dropoff += frequency * visit_dropoff;
+            } else {
+                penalty += order_penalty;
+            }
+        }
+
+        // rounded down, so it stays a lower bound
+        let (emptying_time, driving_time, dropoff_time, penalty) =
+            (emptying as Time, driving as Time, dropoff as Time, penalty as Time);
+        LowerBound {
+            total: emptying_time + driving_time + dropoff_time + penalty,
+            emptying_time,
+            driving_time,
+            dropoff_time,
+            penalty,
+            negative_weights,
+        }
+    }
+
+    /// Why the bound says nothing about a plan, if it doesn't.
+    pub fn caveat(&self, feasible: bool) -> Option<&'static str> {
+        if self.negative_weights {
+            Some("the objective has a negative weight")
+        } else if !feasible {
+            Some("the plan breaks the constraints")
+        } else {
+            None
+        }
+    }
+
+    /// How much worse than the bound the score of a plan is, as a fraction of the score. The optimum is somewhere in between.
+    /// None when the bound doesn't hold for the plan, see `caveat`.
+    pub fn gap(&self, score: Time, feasible: bool) -> Option<f64> {
+        if self.caveat(feasible).is_some() {
+            return None;
+        }
+        if score <= 0 {
+            return Some(0.0);
+        }
+        Some((score - self.total) as f64 / score as f64)
+    }
+
+    /// For people: the bound in minutes and the gap of `score` to it, or why there is none.
+    pub fn describe_gap(&self, score: Time, feasible: bool) -> String {
+        let bound = self.total as f64 / MINUTE as f64;
+        match (self.gap(score, feasible), self.caveat(feasible)) {
+            (Some(gap), _) => format!("lower bound: {bound:.1} min, gap: {:.2}%", gap * 100.0),
+            (None, caveat) => format!("lower bound: {bound:.1} min, no gap because {}", caveat.unwrap_or_default()),
+        }
+    }
+}
+
+impl Default for LowerBound {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LowerBound;
+    use crate::initialize;
+    use crate::resource::{Company, Frequency, MatrixID, Site, Time};
+    use crate::simulated_annealing::score_calculator::calculate_starting_score;
+
+    #[test]
+    fn the_empty_plan_is_above_the_bound() {
+        initialize().unwrap();
+        let bound = LowerBound::new();
+        assert!(bound.total > 0);
+        assert_eq!(bound.total, bound.emptying_time + bound.driving_time + bound.dropoff_time + bound.penalty);
+        // doing nothing costs all penalties, the bound never costs more than that
+        assert!(bound.total <= calculate_starting_score());
+        assert_eq!(bound.gap(bound.total, true), Some(0.0));
+    }
+
+    fn order(matrix_id: u16, frequency: Frequency, emptying_time: Time, total_container_volume: u32) -> Company {
+        Company {
+            order: matrix_id,
+            place: "Ergens".to_string(),
+            frequency,
+            container_count: 1,
+            container_volume: total_container_volume as u16,
+            emptying_time,
+            matrix_id: MatrixID::new(matrix_id as usize),
+            x_coordinate: 0,
+            y_coordinate: 0,
+            total_container_volume,
+            penalty: 0,
+            allowed_patterns: frequency.default_patterns().to_vec(),
+            site: None,
+        }
+    }
+
+    #[test]
+    fn a_small_bound_adds_up() {
+        let depot = Company {
+            site: Some(Site { is_dump: true, home_of: vec![] }),
+            ..order(0, Frequency::None, 0, 0)
+        };
+        // close to the depot, doing it twice is cheaper than its penalty
+        let near = order(1, Frequency::Twice, 6000, 1000);
+        // far from everything, only its penalty counts
+        let far = order(2, Frequency::Once, 100, 0);
+        let times = [[0, 10, 1000], [20, 0, 1000], [1000, 1000, 0]];
+        let bound = LowerBound::of(&[near, far, depot], |from, to| times[from.index()][to.index()], false);
+
+        assert_eq!(bound.emptying_time, 2 * 6000);
+        // half of the 10 to it from the depot and half of the 20 back, twice
+        assert_eq!(bound.driving_time, 2 * 15);
+        // a hundredth of a truck at a dump site, twice
+        assert_eq!(bound.dropoff_time, 2 * 1800);
+        // emptying it three times over, because 100 + 1000 of driving costs more
+        assert_eq!(bound.penalty, 300);
+        assert_eq!(bound.total, 15_930);
+        assert_eq!(bound.gap(2 * 15_930, true), Some(0.5));
+    }
+
+    #[test]
+    fn there_is_no_gap_when_the_bound_does_not_hold() {
+        let bound = LowerBound::of(&[], |_, _| 0, false);
+        assert_eq!(bound.gap(100, false), None);
+        assert!(bound.describe_gap(100, false).contains("breaks the constraints"));
+
+        let bound = LowerBound::of(&[], |_, _| 0, true);
+        assert_eq!(bound.gap(100, true), None);
+        assert!(bound.describe_gap(100, true).contains("negative weight"));
+    }
+}
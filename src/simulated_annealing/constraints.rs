@@ -0,0 +1,383 @@
+//! The constraints a plan has to keep: days of at most 12 hours, loads that fit in the truck and the driving time rules.
+//!
+//! Each one is hard, then the search never accepts a move that breaks it more, or soft, then breaking it costs a penalty.
+//! Hard doesn't make a plan that starts out broken feasible, only the repair does that.
+//! The weights of the soft ones follow the search: every now and then we look at how often the solution broke them lately,
+//! and make them heavier when that's more than TARGET_INFEASIBLE_FRACTION, or lighter when it's less.
+//! Whatever is still broken at the end of an ILS iteration gets repaired by taking orders out,
+//! so the plans that come out are feasible unless the locks keep them broken.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::get_sites;
+use crate::resource::FULL_DAY;
+use crate::simulated_annealing::balance::DayTimes;
+use crate::simulated_annealing::manual_edit::ManualEdit;
+use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
+use crate::simulated_annealing::route::{OrderIndex, Route};
+use crate::simulated_annealing::simulated_annealing::{SimulatedAnnealing, TruckEnum};
+use crate::simulated_annealing::solution::Solution;
+
+pub const CONSTRAINT_COUNT: usize = 4;
+
+/// How many steps there are between two looks at whether the solution breaks the constraints
+pub const SAMPLE_INTERVAL: u32 = 100;
+/// How many steps there are between two changes of the weights
+pub const ADAPT_INTERVAL: u32 = 100_000;
+
+/// The weights go up when the solution breaks a constraint more often than this, and down when it's less often.
+/// A search that's allowed to go through infeasible plans now and then finds better feasible ones.
+const TARGET_INFEASIBLE_FRACTION: f64 = 0.2;
+/// Close enough to the target, the weight stays the same
+const TARGET_MARGIN: f64 = 0.05;
+const ADAPT_FACTOR: f64 = 1.2;
+/// The weights stay within this factor of where they started
+const MAX_WEIGHT_FACTOR: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Constraint {
+    /// A day of a truck takes at most FULL_DAY
+    DayLength,
+    /// A load fits in the truck
+    Capacity,
+    /// No driving for longer than the driver is allowed to without a rest
+    ContinuousDriving,
+    /// A week of a truck takes at most what the driver is allowed to work
+    WeeklyTime,
+}
+
+impl Constraint {
+    pub const ALL: [Constraint; CONSTRAINT_COUNT] = [
+        Constraint::DayLength,
+        Constraint::Capacity,
+        Constraint::ContinuousDriving,
+        Constraint::WeeklyTime,
+    ];
+
+    /// What it's called on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Constraint::DayLength => "day",
+            Constraint::Capacity => "capacity",
+            Constraint::ContinuousDriving => "driving",
+            Constraint::WeeklyTime => "week",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|constraint| constraint.name() == name)
+    }
+
+    /// In score per centisecond or litre too much. These are the multipliers the search always had.
+    fn start_weight(self) -> f64 {
+        match self {
+            Constraint::DayLength | Constraint::ContinuousDriving | Constraint::WeeklyTime => 0.06,
+            Constraint::Capacity => 25.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConstraintMode {
+    /// Never broken more than it already is
+    Hard,
+    /// Broken for a penalty
+    #[default]
+    Soft,
+}
+
+/// The mode of every constraint, indexed like Constraint::ALL.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstraintModes(pub [ConstraintMode; CONSTRAINT_COUNT]);
+
+impl ConstraintModes {
+    pub fn get(&self, constraint: Constraint) -> ConstraintMode {
+        self.0[constraint as usize]
+    }
+
+    pub fn set(&mut self, constraint: Constraint, mode: ConstraintMode) {
+        self.0[constraint as usize] = mode;
+    }
+}
+
+/// How much every constraint is broken, in centiseconds or litres, indexed like Constraint::ALL.
+/// Or how much a move changes that.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Violations(pub [i64; CONSTRAINT_COUNT]);
+
+impl Violations {
+    /// From the times and rests stored in the routes, the loads are counted again.
+    pub fn of(solution: &Solution) -> Self {
+        let day_times = DayTimes::of(&solution.truck1, &solution.truck2);
+        let routes = || solution.truck1.iter().chain(solution.truck2.iter());
+        Violations([
+            day_times.0.iter().flatten().map(|time| (*time - FULL_DAY).max(0) as i64).sum(),
+            routes().map(|route| route.capacity_overflow() as i64).sum(),
+            routes().map(|route| route.rests.continuous_overflow as i64).sum(),
+            day_times.weekly_overflow() as i64,
+        ])
+    }
+
+    /// What the move does to the constraints
+    pub fn of_evaluation(evaluation: &Evaluation) -> Self {
+        Violations([
+            evaluation.time_overflow_delta as i64,
+            evaluation.capacity_overflow_delta as i64,
+            evaluation.continuous_driving_overflow_delta as i64,
+            evaluation.weekly_overflow_delta as i64,
+        ])
+    }
+
+    pub fn get(&self, constraint: Constraint) -> i64 {
+        self.0[constraint as usize]
+    }
+
+    pub fn is_feasible(&self) -> bool {
+        self.0.iter().all(|violation| *violation <= 0)
+    }
+
+    /// The command line names of the broken constraints, like "day, capacity"
+    pub fn broken_names(&self) -> String {
+        Constraint::ALL
+            .iter()
+            .filter(|constraint| self.get(**constraint) > 0)
+            .map(|constraint| constraint.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// What breaking the soft constraints costs, and what the search saw since the weights last changed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PenaltyWeights {
+    /// Indexed like Constraint::ALL, see Constraint::start_weight for the units
+    pub weights: [f64; CONSTRAINT_COUNT],
+    infeasible_samples: [u32; CONSTRAINT_COUNT],
+    samples: u32,
+}
+
+impl Default for PenaltyWeights {
+    fn default() -> Self {
+        PenaltyWeights {
+            weights: Constraint::ALL.map(Constraint::start_weight),
+            infeasible_samples: [0; CONSTRAINT_COUNT],
+            samples: 0,
+        }
+    }
+}
+
+impl PenaltyWeights {
+    pub fn weight(&self, constraint: Constraint) -> f64 {
+        self.weights[constraint as usize]
+    }
+
+    /// What the changes in the constraints add to the cost of a move.
+    /// None when the move breaks a hard constraint more, then it can't be accepted at all.
+    pub fn penalty(&self, modes: &ConstraintModes, changes: &Violations) -> Option<f64> {
+        let mut penalty = 0.0;
+        for constraint in Constraint::ALL {
+            let change = changes.get(constraint);
+            match modes.get(constraint) {
+                ConstraintMode::Hard if change > 0 => return None,
+                ConstraintMode::Hard => {}
+                ConstraintMode::Soft => penalty += change as f64 * self.weight(constraint),
+            }
+        }
+        Some(penalty)
+    }
+
+    /// Remembers which constraints the current solution breaks.
+    pub fn record(&mut self, violations: &Violations) {
+        self.samples += 1;
+        for constraint in Constraint::ALL {
+            self.infeasible_samples[constraint as usize] += (violations.get(constraint) > 0) as u32;
+        }
+    }
+
+    /// Makes the weights of the constraints that were broken too often heavier, and the others lighter.
+    /// Hard constraints don't use their weight, so it doesn't matter that theirs change too.
+    pub fn adapt(&mut self) {
+        if self.samples == 0 {
+            return;
+        }
+        for constraint in Constraint::ALL {
+            let i = constraint as usize;
+            let fraction = self.infeasible_samples[i] as f64 / self.samples as f64;
+            if fraction > TARGET_INFEASIBLE_FRACTION + TARGET_MARGIN {
+                self.weights[i] *= ADAPT_FACTOR;
+            } else if fraction < TARGET_INFEASIBLE_FRACTION - TARGET_MARGIN {
+                self.weights[i] /= ADAPT_FACTOR;
+            }
+            let start = constraint.start_weight();
+            self.weights[i] = self.weights[i].clamp(start / MAX_WEIGHT_FACTOR, start * MAX_WEIGHT_FACTOR);
+        }
+        self.infeasible_samples = [0; CONSTRAINT_COUNT];
+        self.samples = 0;
+    }
+
+    /// What the violations cost with these weights, soft or not. The repair uses this to compare constraints.
+    fn cost_of(&self, violations: &Violations) -> f64 {
+        Constraint::ALL.iter().map(|constraint| violations.get(*constraint) as f64 * self.weight(*constraint)).sum()
+    }
+}
+
+/// What the repair did to a plan.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Repair {
+    /// The orders it took out, in the order it took them out
+    pub removed: Vec<OrderIndex>,
+    /// Why the plan still breaks the constraints, None if it doesn't anymore.
+    /// That only happens when the locked orders and frozen routes break them.
+    pub failure: Option<String>,
+}
+
+impl Repair {
+    /// What to tell the user, None if the plan didn't need repairing
+    pub fn summary(&self) -> Option<String> {
+        match &self.failure {
+            Some(failure) => Some(failure.clone()),
+            None if self.removed.is_empty() => None,
+            None => Some(format!("Took {} orders out to make the plan feasible", self.removed.len())),
+        }
+    }
+}
+
+/// Takes orders out until the solution doesn't break any constraint, hard or soft.
+///
+/// Every round takes out the order in a broken route for which the score plus the penalties gets the lowest,
+/// as long as that makes the plan less broken. When no single order does, like when the drive to the first customer
+/// is already too long, it empties a broken route instead. Empty routes don't break anything, so this always ends
+/// with a feasible plan, unless it's the locked orders or frozen routes that break the constraints.
+pub fn repair(solution: &mut Solution, weights: &PenaltyWeights) -> Repair {
+    let mut removed = Vec::new();
+    loop {
+        let violations = Violations::of(solution);
+        if violations.is_feasible() {
+            return Repair { removed, failure: None };
+        }
+
+        let broken_routes = orders_in_broken_routes(solution, &violations);
+        let best = broken_routes
+            .iter()
+            .flatten()
+            .filter_map(|order_index| {
+                let (neighbor_move, _) = ManualEdit::Remove { order: *order_index }.to_move(solution).ok()?;
+                let evaluation = neighbor_move.evaluate(solution);
+                let penalty = weights.cost_of(&Violations::of_evaluation(&evaluation));
+                // only what makes it less broken
+                (penalty < 0.0).then_some((*order_index, evaluation.cost as f64 + penalty))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((order_index, _)) = best {
+            remove(solution, order_index);
+            removed.push(order_index);
+            continue;
+        }
+
+        // The first broken route with anything in it that may be taken out
+        let removable = |order_index: OrderIndex| ManualEdit::Remove { order: order_index }.to_move(solution).is_ok();
+        let Some(route) = broken_routes.iter().find(|route| route.iter().any(|order_index| removable(*order_index))) else {
+            let failure = format!(
+                "Could not repair the plan, the locked orders and frozen routes keep breaking the constraints ({})",
+                violations.broken_names()
+            );
+            return Repair { removed, failure: Some(failure) };
+        };
+        for order_index in route.clone() {
+            // an order that's in the route twice is gone after the first time
+            if (ManualEdit::Remove { order: order_index }).to_move(solution).is_ok() {
+                remove(solution, order_index);
+                removed.push(order_index);
+            }
+        }
+    }
+}
+
+fn remove(solution: &mut Solution, order_index: OrderIndex) {
+    let (neighbor_move, end_of_step_info) = ManualEdit::Remove { order: order_index }.to_move(solution).unwrap();
+    SimulatedAnnealing::apply_move(solution, neighbor_move.as_ref(), end_of_step_info);
+}
+
+/// The customers in every route that breaks a constraint, or is on a day or in a week that's too long.
+fn orders_in_broken_routes(solution: &Solution, violations: &Violations) -> Vec<BTreeSet<OrderIndex>> {
+    let sites = get_sites();
+    let day_times = DayTimes::of(&solution.truck1, &solution.truck2);
+    let truck_totals = day_times.truck_totals();
+    let drivers = crate::get_drivers();
+
+    let mut broken: Vec<&Route> = Vec::new();
+    for (truck, week) in [(TruckEnum::Truck1, &solution.truck1), (TruckEnum::Truck2, &solution.truck2)] {
+        let long_week = violations.get(Constraint::WeeklyTime) > 0 && drivers[truck as usize].weekly_overflow(truck_totals[truck as usize]) > 0;
+        for (i, route) in week.iter().enumerate() {
+            // the routes go morning and afternoon, day by day
+            let long_day = day_times.0[truck as usize][i / 2] > FULL_DAY;
+            if long_week || long_day || route.capacity_overflow() > 0 || route.rests.continuous_overflow > 0 {
+                broken.push(route);
+            }
+        }
+    }
+    broken
+        .into_iter()
+        .map(|route| {
+            route.linked_vector.iter()
+                .map(|(_, order_index)| *order_index)
+                .filter(|order_index| !sites.is_site(*order_index))
+                .collect()
+        })
+        .collect()
+}
+
+/// For the tests, and for checking plans that come from somewhere else
+pub fn is_feasible(solution: &Solution) -> bool {
+    Violations::of(solution).is_feasible()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Constraint, ConstraintMode, ConstraintModes, PenaltyWeights, Violations};
+
+    #[test]
+    fn hard_constraints_reject_getting_worse() {
+        let weights = PenaltyWeights::default();
+        let mut modes = ConstraintModes::default();
+        modes.set(Constraint::Capacity, ConstraintMode::Hard);
+
+        assert_eq!(weights.penalty(&modes, &Violations([0, 1, 0, 0])), None);
+        // getting better or staying the same is fine, and the penalty of a hard constraint is nothing
+        assert_eq!(weights.penalty(&modes, &Violations([0, -10, 0, 0])), Some(0.0));
+        assert_eq!(weights.penalty(&modes, &Violations([100, 0, 0, 0])), Some(6.0));
+    }
+
+    #[test]
+    fn weights_follow_how_often_constraints_are_broken() {
+        let mut weights = PenaltyWeights::default();
+        let start = weights.clone();
+        // the days are always too long, the loads never too heavy, the driving one in five times
+        for i in 0..100 {
+            weights.record(&Violations([1, 0, (i % 5 == 0) as i64, 0]));
+        }
+        weights.adapt();
+
+        assert!(weights.weight(Constraint::DayLength) > start.weight(Constraint::DayLength));
+        assert!(weights.weight(Constraint::Capacity) < start.weight(Constraint::Capacity));
+        assert_eq!(weights.weight(Constraint::ContinuousDriving), start.weight(Constraint::ContinuousDriving));
+
+        // and they don't run away
+        for _ in 0..1000 {
+            weights.record(&Violations([1, 0, 0, 0]));
+            weights.adapt();
+        }
+        assert_eq!(weights.weight(Constraint::DayLength), start.weight(Constraint::DayLength) * 100.0);
+    }
+
+    #[test]
+    fn constraints_have_names() {
+        for constraint in Constraint::ALL {
+            assert_eq!(Constraint::from_name(constraint.name()), Some(constraint));
+        }
+        assert_eq!(Constraint::from_name("weekend"), None);
+        assert_eq!(Violations([5, 0, -3, 2]).broken_names(), "day, week");
+    }
+}
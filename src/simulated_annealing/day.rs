@@ -1,9 +1,11 @@
 use std::fmt::Display;
 
 use super::route::Route;
+use super::simulated_annealing::TruckEnum;
 use crate::resource::Time;
 use rand::Rng;
 use rand::distr::{Distribution, StandardUniform};
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone)]
 pub struct Day {
@@ -11,7 +13,7 @@ pub struct Day {
     pub afternoon: Route,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum TimeOfDay {
     Morning,
     Afternoon,
@@ -37,10 +39,10 @@ impl Distribution<TimeOfDay> for StandardUniform {
     }
 }
 impl Day {
-    pub fn new() -> Self {
+    pub fn new(truck: TruckEnum) -> Self {
         Day {
-            morning: Route::new(),
-            afternoon: Route::new(),
+            morning: Route::for_truck(truck),
+            afternoon: Route::for_truck(truck),
         }
     }
     pub fn get_random<R: Rng + ?Sized>(&self, rng: &mut R) -> (&Route, TimeOfDay) {
@@ -80,9 +82,3 @@ impl Day {
         self.afternoon.recalculate_total_time();
     }
 }
-
-impl Default for Day {
-    fn default() -> Self {
-        Self::new()
-    }
-}
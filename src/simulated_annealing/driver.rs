@@ -0,0 +1,97 @@
+//! The driving time rules for the drivers: breaks, how long they may drive in one go and how long they may work in a week.
+//!
+//! Every trip is planned on its own, so the driving since the last rest starts at 0 at the depot.
+//! Unloading at a dump site takes half an hour, which counts as a rest too.
+//! When the next drive would take the driver over the driving time before a break, the break is taken at the stop before it.
+
+use crate::get_orders;
+use crate::get_sites;
+use crate::resource::{HALF_HOUR, Time};
+use crate::simulated_annealing::neighbor_move::evaluation_helper::time_between_two_nodes;
+use crate::simulated_annealing::route::OrderIndex;
+
+/// The rules for the driver of one truck. Every truck has the same driver all week.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Driver {
+    pub name: String,
+    /// How long the driver may drive before taking a break
+    pub break_after: Time,
+    pub break_length: Time,
+    /// How long a stretch of driving without a rest may be. This can only be broken by a single very long drive,
+    /// or when it's shorter than `break_after`, then it's penalised in the search.
+    pub max_continuous_driving: Time,
+    /// How long all days of the week may take together, penalised in the search like a day that's too long
+    pub max_weekly_time: Time,
+}
+
+/// What the rules do to one route.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rests {
+    pub breaks: u32,
+    /// How much longer than allowed the stretches of driving are, summed over the route
+    pub continuous_overflow: Time,
+}
+
+impl Driver {
+    /// 30 minutes after 4.5 hours of driving and at most 60 hours a week, like the law says.
+    pub fn legal() -> Self {
+        Driver {
+            name: "Chauffeur".to_string(),
+            break_after: 9 * HALF_HOUR,
+            break_length: HALF_HOUR,
+            max_continuous_driving: 9 * HALF_HOUR,
+            max_weekly_time: 120 * HALF_HOUR,
+        }
+    }
+
+    /// A route with less driving than this in total can't need a break or drive too long in one go.
+    pub fn driving_without_rests(&self) -> Time {
+        self.break_after.min(self.max_continuous_driving)
+    }
+
+    /// How much longer than allowed a week of `time` is.
+    pub fn weekly_overflow(&self, time: Time) -> Time {
+        (time - self.max_weekly_time).max(0)
+    }
+
+    /// Walks the route from the depot to the dump site, and back to the depot, and calls `on_break`
+    /// with the position in `route` of every stop where the driver takes a break.
+    pub fn plan_rests(&self, route: &[OrderIndex], mut on_break: impl FnMut(usize)) -> Rests {
+        let orders = get_orders();
+        let sites = get_sites();
+        let (Some(depot), Some(dump_site)) = (route.first(), route.last()) else {
+            return Rests::default();
+        };
+
+        let mut rests = Rests::default();
+        let mut driving = 0;
+        let end_stretch = |driving: &mut Time, rests: &mut Rests| {
+            rests.continuous_overflow += (*driving - self.max_continuous_driving).max(0);
+            *driving = 0;
+        };
+        let legs = route.windows(2).map(|pair| (pair[0], pair[1])).chain([(*dump_site, *depot)]);
+        for (position, (from, to)) in legs.enumerate() {
+            let leg = time_between_two_nodes(orders[from].matrix_id, orders[to].matrix_id);
+            if driving > 0 && driving + leg > self.break_after {
+                rests.breaks += 1;
+                on_break(position);
+                end_stretch(&mut driving, &mut rests);
+            }
+            driving += leg;
+            // the tail is where the trip unloads, the other sites in a route are unload stops
+            if position + 2 == route.len() || sites.is_site(to) {
+                end_stretch(&mut driving, &mut rests);
+            }
+        }
+        end_stretch(&mut driving, &mut rests);
+        rests
+    }
+
+    pub fn rests(&self, route: &[OrderIndex]) -> Rests {
+        self.plan_rests(route, |_| {})
+    }
+
+    pub fn break_time(&self, rests: Rests) -> Time {
+        rests.breaks as Time * self.break_length
+    }
+}
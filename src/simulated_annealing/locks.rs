@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+
+use crate::get_orders;
+use crate::simulated_annealing::day::TimeOfDay;
+use crate::simulated_annealing::route::OrderIndex;
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use crate::simulated_annealing::week::DayEnum;
+
+/// How much of an order has to stay like it is. Every level keeps what the ones before it keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash, Serialize, Deserialize)]
+pub enum OrderLock {
+    #[default]
+    Free,
+    /// The order stays planned on the days it's on now, but can change truck and trip.
+    Day,
+    /// The order stays with the same truck on the same days, but can change trip.
+    Truck,
+    /// The order stays where it is. Other orders can still be put before or after it.
+    Position,
+}
+
+impl OrderLock {
+    pub const ALL: [OrderLock; 4] = [OrderLock::Free, OrderLock::Day, OrderLock::Truck, OrderLock::Position];
+}
+
+/// Two trucks, five days, mornings and afternoons
+const ROUTE_COUNT: u32 = 20;
+
+/// Orders and routes the search has to leave alone, like customers with a collection day they agreed on.
+/// A frozen route doesn't change at all: nothing gets added, removed or reordered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locks {
+    orders: Vec<OrderLock>,
+    /// One bit per route, see route_bit
+    frozen_routes: u32,
+}
+
+impl Locks {
+    pub fn new(size: usize) -> Self {
+        Locks {
+            orders: vec![OrderLock::Free; size],
+            frozen_routes: 0,
+        }
+    }
+
+    pub fn order(&self, order_index: OrderIndex) -> OrderLock {
+        self.orders[order_index]
+    }
+
+    pub fn set_order(&mut self, order_index: OrderIndex, lock: OrderLock) {
+        self.orders[order_index] = lock;
+    }
+
+    pub fn is_frozen(&self, truck: TruckEnum, day: DayEnum, time_of_day: TimeOfDay) -> bool {
+        self.frozen_routes & Self::route_bit(truck, day, time_of_day) != 0
+    }
+
+    pub fn set_frozen(&mut self, truck: TruckEnum, day: DayEnum, time_of_day: TimeOfDay, frozen: bool) {
+        if frozen {
+            self.frozen_routes |= Self::route_bit(truck, day, time_of_day);
+        } else {
+            self.frozen_routes &= !Self::route_bit(truck, day, time_of_day);
+        }
+    }
+
+    /// True if nothing is locked, so the moves don't have to look.
+    pub fn is_empty(&self) -> bool {
+        self.frozen_routes == 0 && self.orders.iter().all(|lock| *lock == OrderLock::Free)
+    }
+
+    /// The locked orders, without the free ones.
+    pub fn locked_orders(&self) -> impl Iterator<Item = (OrderIndex, OrderLock)> + '_ {
+        self.orders
+            .iter()
+            .enumerate()
+            .filter(|(_, lock)| **lock != OrderLock::Free)
+            .map(|(order_index, lock)| (order_index, *lock))
+    }
+
+    /// Can the order be taken out of the plan, or moved to another day?
+    pub fn can_change_days(&self, order_index: OrderIndex) -> bool {
+        self.orders[order_index] == OrderLock::Free
+    }
+
+    /// Can the order move to another route on the same day, with the given truck?
+    pub fn can_change_route(&self, order_index: OrderIndex, from: TruckEnum, to: TruckEnum) -> bool {
+        match self.orders[order_index] {
+            OrderLock::Free | OrderLock::Day => true,
+            OrderLock::Truck => from == to,
+            OrderLock::Position => false,
+        }
+    }
+
+    /// Can the order move within its route?
+    pub fn can_change_position(&self, order_index: OrderIndex) -> bool {
+        self.orders[order_index] != OrderLock::Position
+    }
+
+    /// Then no move can change anything.
+    pub fn all_routes_frozen(&self) -> bool {
+        self.frozen_routes == (1 << ROUTE_COUNT) - 1
+    }
+
+    fn route_bit(truck: TruckEnum, day: DayEnum, time_of_day: TimeOfDay) -> u32 {
+        1 << (truck as u32 * 10 + day as u32 * 2 + time_of_day as u32)
+    }
+}
+
+impl Default for Locks {
+    fn default() -> Self {
+        Self::new(get_orders().len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Locks, OrderLock};
+    use crate::simulated_annealing::day::TimeOfDay;
+    use crate::simulated_annealing::simulated_annealing::TruckEnum;
+    use crate::simulated_annealing::week::DayEnum;
+
+    #[test]
+    fn every_route_has_its_own_bit() {
+        let mut locks = Locks::new(3);
+        assert!(locks.is_empty());
+        locks.set_frozen(TruckEnum::Truck2, DayEnum::Friday, TimeOfDay::Afternoon, true);
+        assert!(locks.is_frozen(TruckEnum::Truck2, DayEnum::Friday, TimeOfDay::Afternoon));
+        assert!(!locks.is_frozen(TruckEnum::Truck1, DayEnum::Friday, TimeOfDay::Afternoon));
+        assert!(!locks.is_frozen(TruckEnum::Truck2, DayEnum::Friday, TimeOfDay::Morning));
+        assert!(!locks.is_frozen(TruckEnum::Truck2, DayEnum::Thursday, TimeOfDay::Afternoon));
+        locks.set_frozen(TruckEnum::Truck2, DayEnum::Friday, TimeOfDay::Afternoon, false);
+        assert!(locks.is_empty());
+    }
+
+    #[test]
+    fn all_routes_frozen_needs_every_route() {
+        let mut locks = Locks::new(3);
+        for truck in [TruckEnum::Truck1, TruckEnum::Truck2] {
+            for day in DayEnum::ALL {
+                for time_of_day in [TimeOfDay::Morning, TimeOfDay::Afternoon] {
+                    assert!(!locks.all_routes_frozen());
+                    locks.set_frozen(truck, day, time_of_day, true);
+                }
+            }
+        }
+        assert!(locks.all_routes_frozen());
+    }
+
+    #[test]
+    fn order_locks_keep_what_the_levels_before_them_keep() {
+        let mut locks = Locks::new(4);
+        locks.set_order(1, OrderLock::Day);
+        locks.set_order(2, OrderLock::Truck);
+        locks.set_order(3, OrderLock::Position);
+
+        assert!(locks.can_change_days(0));
+        assert!((1..4).all(|order| !locks.can_change_days(order)));
+        assert!(locks.can_change_route(1, TruckEnum::Truck1, TruckEnum::Truck2));
+        assert!(!locks.can_change_route(2, TruckEnum::Truck1, TruckEnum::Truck2));
+        assert!(locks.can_change_route(2, TruckEnum::Truck1, TruckEnum::Truck1));
+        assert!(!locks.can_change_route(3, TruckEnum::Truck1, TruckEnum::Truck1));
+        assert!(locks.can_change_position(2));
+        assert!(!locks.can_change_position(3));
+        assert_eq!(locks.locked_orders().count(), 3);
+    }
+}
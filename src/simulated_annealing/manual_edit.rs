@@ -0,0 +1,141 @@
+//! Changes a planner makes by hand. They are made with the same moves as the search,
+//! so the solution stays consistent and the score stays up to date.
+
+use crate::get_orders;
+use crate::simulated_annealing::neighbor_move::add_multiple_at_once::AddMultipleNewOrders;
+use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
+use crate::simulated_annealing::neighbor_move::neighbor_move_trait::NeighborMove;
+use crate::simulated_annealing::neighbor_move::remove_multiple_at_once::RemoveMultipleOrders;
+use crate::simulated_annealing::neighbor_move::route_position::RoutePosition;
+use crate::simulated_annealing::neighbor_move::shift_between_days::ShiftBetweenDays;
+use crate::simulated_annealing::neighbor_move::shift_in_day::ShiftInDay;
+use crate::simulated_annealing::neighbor_move::shift_in_route::ShiftInRoute;
+use crate::simulated_annealing::locks::OrderLock;
+use crate::simulated_annealing::order_day_flags::OrderFlags;
+use crate::simulated_annealing::route::OrderIndex;
+use crate::simulated_annealing::simulated_annealing::{EndOfStepInfo, SimulatedAnnealing};
+use crate::simulated_annealing::solution::Solution;
+use crate::resource::Company;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManualEdit {
+    /// Moves the stop to right after another node, in the same route, another route or on another day.
+    Move { stop: RoutePosition, after: RoutePosition },
+    /// Takes the order out of the plan on all its days.
+    Remove { order: OrderIndex },
+    /// Plans an unplanned order on the days and places where it takes the least extra time.
+    /// With `after`, it goes right after that node and the other days are picked around it.
+    Insert { order: OrderIndex, after: Option<RoutePosition> },
+}
+
+impl ManualEdit {
+    /// Builds the move that makes this edit, or says why it can't be done.
+    pub fn to_move(&self, solution: &Solution) -> Result<(Box<dyn NeighborMove>, EndOfStepInfo), String> {
+        let orders = get_orders();
+        match *self {
+            ManualEdit::Move { stop, after } => {
+                let order_index = stop.order(solution).ok_or("That stop is not in the route")?;
+                if stop.is_head(solution) || stop.is_tail(solution) {
+                    return Err("The depot and dump site can't be moved".to_string());
+                }
+                if after.is_tail(solution) {
+                    return Err("Nothing can come after the end of a route".to_string());
+                }
+                let order = &orders[order_index];
+
+                let neighbor_move: Box<dyn NeighborMove> = if stop.same_route(&after) {
+                    Box::new(ShiftInRoute::between(solution, &stop, after.node_index).ok_or("The stop is already there")?)
+                } else if stop.day == after.day {
+                    Box::new(ShiftInDay::between(solution, &stop, &after).ok_or("That stop can't move there")?)
+                } else {
+                    if solution.order_flags.get_flag(order_index) & OrderFlags::day_to_flags(after.day) != 0 {
+                        return Err(format!("Order {} is already collected on {:?}", order.order, after.day));
+                    }
+                    Box::new(ShiftBetweenDays::between(solution, &stop, &after).ok_or_else(|| {
+                        format!("Order {} is collected {}, so it can't move to {:?}", order.order, pattern_name(order), after.day)
+                    })?)
+                };
+                if !neighbor_move.respects_locks(solution) {
+                    return Err(locked_message(solution, order_index));
+                }
+                Ok((neighbor_move, EndOfStepInfo::Nothing))
+            }
+            ManualEdit::Remove { order: order_index } => {
+                let neighbor_move = RemoveMultipleOrders::of_order(solution, order_index)
+                    .ok_or_else(|| format!("Order {} is not planned", orders[order_index].order))?;
+                if !neighbor_move.respects_locks(solution) {
+                    return Err(locked_message(solution, order_index));
+                }
+                Ok((Box::new(neighbor_move), EndOfStepInfo::Add(order_index)))
+            }
+            ManualEdit::Insert { order: order_index, after } => {
+                let order = &orders[order_index];
+                let Some((unfilled_node, _)) = solution.unfilled_orders.iter().find(|(_, unfilled)| **unfilled == order_index) else {
+                    return Err(format!("Order {} is already planned", order.order));
+                };
+                let neighbor_move = AddMultipleNewOrders::cheapest(solution, order_index, after.as_ref()).ok_or_else(|| match after {
+                    Some(after) => format!("Order {} is collected {}, so not on {:?}", order.order, pattern_name(order), after.day),
+                    None => format!("Order {} can't be planned", order.order),
+                })?;
+                if !neighbor_move.respects_locks(solution) {
+                    return Err(locked_message(solution, order_index));
+                }
+                Ok((Box::new(neighbor_move), EndOfStepInfo::Remove(unfilled_node)))
+            }
+        }
+    }
+
+    /// Makes the edit, and returns what it did to the score and the overflows.
+    /// When the edit can't be done, the solution is left alone.
+    pub fn apply(&self, solution: &mut Solution) -> Result<Evaluation, String> {
+        let (neighbor_move, end_of_step_info) = self.to_move(solution)?;
+        let evaluation = neighbor_move.evaluate(solution);
+        SimulatedAnnealing::apply_move(solution, neighbor_move.as_ref(), end_of_step_info);
+        Ok(evaluation)
+    }
+}
+
+fn locked_message(solution: &Solution, order_index: OrderIndex) -> String {
+    let order = get_orders()[order_index].order;
+    match solution.locks.order(order_index) {
+        OrderLock::Free => "That changes a frozen route".to_string(),
+        lock => format!("Order {order} is locked to its {}", lock_name(lock)),
+    }
+}
+
+fn lock_name(lock: OrderLock) -> &'static str {
+    match lock {
+        OrderLock::Free => "nothing",
+        OrderLock::Day => "days",
+        OrderLock::Truck => "truck and days",
+        OrderLock::Position => "position",
+    }
+}
+
+/// The days the order can be collected on, like "on Monday and Thursday or on Tuesday and Friday".
+fn pattern_name(order: &Company) -> String {
+    let patterns = &order.allowed_patterns;
+    let every_set = (1..=0b11111u8).filter(|flags| flags.count_ones() == order.frequency as u32).count();
+    if patterns.len() == every_set {
+        // Any days will do, as long as there are enough of them
+        return match order.frequency as u8 {
+            1 => "once a week".to_string(),
+            times => format!("{times} times a week"),
+        };
+    }
+    let pattern_names: Vec<String> = patterns
+        .iter()
+        .map(|pattern| {
+            let days: Vec<String> = OrderFlags::flags_to_days(*pattern).iter().map(|day| format!("{day:?}")).collect();
+            match days.split_last() {
+                Some((last, [])) => format!("on {last}"),
+                Some((last, rest)) => format!("on {} and {last}", rest.join(", ")),
+                None => "never".to_string(),
+            }
+        })
+        .collect();
+    if pattern_names.is_empty() {
+        return "never".to_string();
+    }
+    pattern_names.join(" or ")
+}
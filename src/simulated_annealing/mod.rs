@@ -1,10 +1,19 @@
 pub mod route;
 pub mod simulated_annealing;
-mod neighbor_move;
+pub mod neighbor_move;
 pub mod day;
 pub mod week;
-mod order_day_flags;
+pub mod order_day_flags;
 mod FIXTHISSHITANDWEAREDONE;
-mod neighbor_chooser;
-mod score_calculator;
-pub mod solution;
\ No newline at end of file
+pub mod neighbor_chooser;
+pub mod neighbor_lists;
+pub mod score_calculator;
+pub mod search_statistics;
+pub mod solution;
+pub mod manual_edit;
+pub mod locks;
+pub mod objective;
+pub mod balance;
+pub mod driver;
+pub mod constraints;
+pub mod bounds;
\ No newline at end of file
@@ -6,29 +6,81 @@ use rand::distr::weighted::WeightedIndex;
 use rand::prelude::*;
 use crate::datastructures::linked_vectors::LinkedVector;
 use crate::simulated_annealing::neighbor_move::add_multiple_at_once::AddMultipleNewOrders;
+use crate::simulated_annealing::neighbor_move::add_unload_stop::AddUnloadStop;
+use crate::simulated_annealing::neighbor_move::change_dump_site::ChangeDumpSite;
 use crate::simulated_annealing::neighbor_move::remove_multiple_at_once::RemoveMultipleOrders;
+use crate::simulated_annealing::neighbor_move::remove_unload_stop::RemoveUnloadStop;
 use crate::simulated_annealing::neighbor_move::shift_between_days::ShiftBetweenDays;
 use crate::simulated_annealing::neighbor_move::shift_in_day::ShiftInDay;
 use crate::simulated_annealing::solution::Solution;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+/// add, remove, shift in route, shift in day, shift between days,
+/// the granular versions of add, shift in route, shift in day and shift between days,
+/// changing the dump site of a trip, and adding or removing a visit to a dump site in the middle of a trip.
+pub const MOVE_COUNT: usize = 12;
+
+/// Names for the moves, in the same order as the weights. Used in the logs.
+pub const MOVE_NAMES: [&str; MOVE_COUNT] = [
+    "add",
+    "remove",
+    "shift_in_route",
+    "shift_in_day",
+    "shift_between_days",
+    "granular_add",
+    "granular_shift_in_route",
+    "granular_shift_in_day",
+    "granular_shift_between_days",
+    "change_dump_site",
+    "add_unload_stop",
+    "remove_unload_stop",
+];
+
+/// For the arrays in the saved files that have a value for every move, like the weights and the statistics.
+/// New moves only ever get added at the end, so files from before that have shorter lists.
+/// The moves they don't know about get the default, those weren't used in that run.
+pub fn deserialize_per_move<'de, D, T>(deserializer: D) -> Result<[T; MOVE_COUNT], D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default + Copy,
+{
+    let values = Vec::<T>::deserialize(deserializer)?;
+    if values.len() > MOVE_COUNT {
+        return Err(D::Error::invalid_length(values.len(), &"at most one value for every move"));
+    }
+    let mut per_move = [T::default(); MOVE_COUNT];
+    per_move[..values.len()].copy_from_slice(&values);
+    Ok(per_move)
+}
+
+/// How many random moves choose_neighbor tries before it gives up, and the step gets skipped.
+/// Most moves that can't be built are just unlucky, but when every route is frozen none of them ever respects the locks.
+pub const MAX_ATTEMPTS: u32 = 1_000;
 
 impl SimulatedAnnealing {
-    pub fn choose_neighbor<R: Rng + ?Sized>(&mut self, rng: &mut R, weights: [i32;5], solution: &mut Solution) -> (Box<dyn NeighborMove>, EndOfStepInfo) {
+    /// Returns None if none of MAX_ATTEMPTS moves could be built and respected the locks.
+    pub fn choose_neighbor<R: Rng + ?Sized>(&mut self, rng: &mut R, weights: [i32; MOVE_COUNT], solution: &mut Solution) -> Option<(Box<dyn NeighborMove>, EndOfStepInfo)> {
         // https://docs.rs/rand_distr/latest/rand_distr/weighted/struct.WeightedIndex.html
         let weights = WeightedIndex::new(weights).unwrap();
         let mut order_to_add:EndOfStepInfo = EndOfStepInfo::Nothing;
-        loop {
+        for _ in 0..MAX_ATTEMPTS {
             let a = weights.sample(rng);
+            self.last_move_kind = a;
+
+            // the granular variants (5 to 8) only insert orders next to one of their neighbours
+            let granular = (5..9).contains(&a);
 
             // something to decide which thing to choose
             let transactionthingy: Box<dyn NeighborMove> = match a {
-                0 => {
+                0 | 5 => {
                     if let Some((node_index, random_order)) = solution.unfilled_orders.get_random(rng) {
                         let new_order = AddMultipleNewOrders::new(
                             solution,
                             rng,
-                            *random_order);
+                            *random_order,
+                            granular);
                         if new_order.is_none() {
-                            solution.unfilled_orders.push_back(*random_order);
                             continue;
                         }
                         order_to_add = EndOfStepInfo::Remove(node_index);
@@ -48,39 +100,133 @@ impl SimulatedAnnealing {
                         continue;
                     }
                 }
-                2 => {
+                2 | 6 => {
                     let shift = ShiftInRoute::new(
                         solution,
-                        rng
+                        rng,
+                        granular,
                     );
                     if shift.is_none() {
                         continue;
                     }
                     Box::new(shift.unwrap())
                 }
-                3 => {
+                3 | 7 => {
                     let shift = ShiftInDay::new(
                         solution,
-                        rng
+                        rng,
+                        granular,
                     );
                     if shift.is_none() {
                         continue;
                     }
                     Box::new(shift.unwrap())
                 }
-                4 => {
+                4 | 8 => {
                     let shift = ShiftBetweenDays::new(
                         solution,
                         rng,
+                        granular,
                     );
                     if shift.is_none() {
                         continue;
                     }
                     Box::new(shift.unwrap())
                 }
+                9 => {
+                    let Some(change) = ChangeDumpSite::new(solution, rng) else {
+                        continue;
+                    };
+                    Box::new(change)
+                }
+                10 => {
+                    let Some(add) = AddUnloadStop::new(solution, rng) else {
+                        continue;
+                    };
+                    Box::new(add)
+                }
+                11 => {
+                    let Some(remove) = RemoveUnloadStop::new(solution, rng) else {
+                        continue;
+                    };
+                    Box::new(remove)
+                }
                 _ => unreachable!(),
             };
-            return (transactionthingy, order_to_add);
+            if !transactionthingy.respects_locks(solution) {
+                order_to_add = EndOfStepInfo::Nothing;
+                continue;
+            }
+            return Some((transactionthingy, order_to_add));
         }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::SmallRng;
+    use crate::initialize;
+    use crate::simulated_annealing::day::TimeOfDay;
+    use crate::simulated_annealing::neighbor_chooser::MOVE_COUNT;
+    use crate::simulated_annealing::simulated_annealing::{SEARCH_WEIGHTS, SimulatedAnnealing, SimulatedAnnealingConfig, TruckEnum};
+    use crate::simulated_annealing::solution::Solution;
+    use crate::simulated_annealing::week::DayEnum;
+
+    #[test]
+    fn failed_adds_leave_the_unfilled_orders_alone() {
+        initialize().unwrap();
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut annealing = SimulatedAnnealing::new(&mut rng, SimulatedAnnealingConfig::headless(1.0, 1.0, 1, 0.99));
+        let mut solution = Solution::new();
+        let unfilled = solution.unfilled_orders.len();
+
+        // Only the add and the granular add. The routes are empty, so most orders have no neighbour to go next to
+        // and the granular add fails. The order is still in the unfilled orders then, it must not be put in again.
+        let mut weights = [0; MOVE_COUNT];
+        weights[0] = 1;
+        weights[5] = 1;
+        for _ in 0..1000 {
+            annealing.choose_neighbor(&mut rng, weights, &mut solution);
+        }
+        assert_eq!(solution.unfilled_orders.len(), unfilled);
+    }
+    #[test]
+    fn perturbations_are_not_in_the_statistics() {
+        initialize().unwrap();
+        let mut rng = SmallRng::seed_from_u64(2);
+        let mut annealing = SimulatedAnnealing::new(&mut rng, SimulatedAnnealingConfig::headless(50_000.0, 10.0, 1_000, 0.99));
+        let mut solution = Solution::new();
+        annealing.run_steps(&mut rng, &mut solution, 2_000);
+        let statistics = annealing.statistics.clone();
+        let score = solution.score;
+
+        annealing.perturb(&mut rng, &mut solution);
+        assert_ne!(solution.score, score);
+        assert_eq!(annealing.statistics, statistics);
+    }
+
+    #[test]
+    fn nothing_is_chosen_when_every_route_is_frozen() {
+        initialize().unwrap();
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut annealing = SimulatedAnnealing::new(&mut rng, SimulatedAnnealingConfig::headless(50_000.0, 10.0, 1_000, 0.99));
+        let mut solution = Solution::new();
+        annealing.run_steps(&mut rng, &mut solution, 5_000);
+        for truck in [TruckEnum::Truck1, TruckEnum::Truck2] {
+            for day in DayEnum::ALL {
+                for time_of_day in [TimeOfDay::Morning, TimeOfDay::Afternoon] {
+                    solution.locks.set_frozen(truck, day, time_of_day, true);
+                }
+            }
+        }
+
+        let (score, unfilled) = (solution.score, solution.unfilled_orders.len());
+        assert!(annealing.choose_neighbor(&mut rng, SEARCH_WEIGHTS, &mut solution).is_none());
+        // the steps get skipped instead of looking for a move forever
+        annealing.run_steps(&mut rng, &mut solution, 100);
+        assert_eq!(solution.score, score);
+        assert_eq!(solution.unfilled_orders.len(), unfilled);
     }
-}
\ No newline at end of file
+}
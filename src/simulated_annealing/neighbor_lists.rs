@@ -0,0 +1,89 @@
+use crate::resource::{Company, MatrixID, TravelTable};
+use std::collections::BTreeSet;
+
+/// How many neighbours we keep for every location.
+pub const NEIGHBOR_COUNT: usize = 20;
+
+/// For every MatrixID that is used by an order, this stores the k locations that are the closest to it (by travel time).
+/// Moves can use this to only insert an order next to one of its neighbours,
+/// instead of at a completely random position on the other side of the map.
+/// The search statistics in trace.csv show how much more often these moves get accepted than the plain ones.
+pub struct NeighborLists {
+    node_count: usize,
+    neighbors: Vec<Vec<MatrixID>>,
+    // node_count * node_count lookup table, so checking if two nodes are neighbours is O(1)
+    is_neighbor: Vec<bool>,
+}
+
+impl NeighborLists {
+    pub fn new(travel_table: &TravelTable, orders: &[Company], k: usize) -> Self {
+        // we only care about locations that actually show up in the order list
+        let used: BTreeSet<MatrixID> = orders.iter().map(|order| order.matrix_id).collect();
+        let node_count = used
+            .iter()
+            .map(|matrix_id| matrix_id.index() + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut neighbors = vec![Vec::new(); node_count];
+        let mut is_neighbor = vec![false; node_count * node_count];
+
+        for &from in &used {
+            let mut closest: Vec<MatrixID> = used.iter().copied().filter(|to| *to != from).collect();
+            closest.sort_by_key(|to| travel_table.travel_time(from, *to));
+            closest.truncate(k);
+
+            // a location is always its own neighbour, the travel time between them is 0.
+            is_neighbor[from.index() * node_count + from.index()] = true;
+            for to in &closest {
+                is_neighbor[from.index() * node_count + to.index()] = true;
+            }
+            neighbors[from.index()] = closest;
+        }
+
+        NeighborLists {
+            node_count,
+            neighbors,
+            is_neighbor,
+        }
+    }
+
+    /// The k closest locations of this location, sorted from close to far.
+    pub fn get_neighbors(&self, matrix_id: MatrixID) -> &[MatrixID] {
+        &self.neighbors[matrix_id.index()]
+    }
+
+    #[inline(always)]
+    pub fn is_neighbor(&self, from: MatrixID, to: MatrixID) -> bool {
+        self.is_neighbor[from.index() * self.node_count + to.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{parse_distance_matrix, parse_orderfile};
+    use crate::resource::TravelTable;
+    use crate::simulated_annealing::neighbor_lists::NeighborLists;
+
+    #[test]
+    fn neighbors_are_sorted_by_travel_time() {
+        let orders = parse_orderfile().unwrap();
+        let travel_table = TravelTable::new(&parse_distance_matrix().unwrap(), &orders);
+        let neighbor_lists = NeighborLists::new(&travel_table, &orders, 5);
+
+        for order in &orders {
+            let neighbors = neighbor_lists.get_neighbors(order.matrix_id);
+            assert!(neighbors.len() <= 5);
+            assert!(neighbor_lists.is_neighbor(order.matrix_id, order.matrix_id));
+
+            let times: Vec<_> = neighbors
+                .iter()
+                .map(|to| {
+                    assert!(neighbor_lists.is_neighbor(order.matrix_id, *to));
+                    travel_table.travel_time(order.matrix_id, *to)
+                })
+                .collect();
+            assert!(times.is_sorted());
+        }
+    }
+}
@@ -1,24 +1,27 @@
 use rand::Rng;
 use crate::datastructures::linked_vectors::{LinkedVector, LVNodeIndex};
 use crate::{get_orders};
-use crate::resource::{Company};
+use crate::simulated_annealing::balance::DayTimes;
 use crate::simulated_annealing::day::TimeOfDay;
-use crate::simulated_annealing::neighbor_move::evaluation_helper::{calculate_capacity_overflow, calculate_time_overflow};
+use crate::simulated_annealing::neighbor_move::evaluation_helper::calculate_time_overflow;
 use crate::simulated_annealing::order_day_flags::OrderFlags;
 use crate::simulated_annealing::route::{OrderIndex};
 use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
 use crate::simulated_annealing::neighbor_move::neighbor_move_trait::{NeighborMove, ScoreChange};
+use crate::simulated_annealing::neighbor_move::route_position::RoutePosition;
 use crate::simulated_annealing::simulated_annealing::TruckEnum;
 use crate::simulated_annealing::solution::Solution;
 use crate::simulated_annealing::week::{DayEnum};
 
 /// This will add an order to a random route where it is allowed to add it to.
 /// If you try to add an order, that doesn't have any allowed routes, it panics
+#[derive(Debug)]
 pub struct AddMultipleNewOrders {
     where_to_add_orders: Vec<AddOrderInfo>,
     order_index: OrderIndex
 }
 
+#[derive(Debug)]
 struct AddOrderInfo {
     truck_enum: TruckEnum,
     day: DayEnum,
@@ -27,17 +30,16 @@ struct AddOrderInfo {
 }
 /// This cannot coexist with the normal Add operation.
 impl AddMultipleNewOrders {
-    pub fn new<R: Rng+?Sized>(solution: &Solution, rng: &mut R, order_index: OrderIndex) ->  Option<Self>{
+    /// If granular is true, the order will only be inserted next to one of its neighbours.
+    /// When a chosen route has no neighbours of the order, this returns None.
+    pub fn new<R: Rng+?Sized>(solution: &Solution, rng: &mut R, order_index: OrderIndex, granular: bool) ->  Option<Self>{
         let order = &get_orders()[order_index];
         let mut orders_to_add: Vec<AddOrderInfo> = Vec::new();
         let mut flags = 0;
         for _ in 0..order.frequency as u8 {
-            if let Some(order_to_add) = Self::get_random_allowed_order(solution, rng, flags, order) {
-                flags |= OrderFlags::day_to_flags(order_to_add.day);
-                orders_to_add.push(order_to_add);
-            } else {
-                return None
-            }
+            let order_to_add = Self::get_random_allowed_order(solution, rng, flags, order_index, granular)?;
+            flags |= OrderFlags::day_to_flags(order_to_add.day);
+            orders_to_add.push(order_to_add);
         }
         Some(AddMultipleNewOrders{
             where_to_add_orders: orders_to_add,
@@ -45,16 +47,68 @@ impl AddMultipleNewOrders {
         })
     }
 
-    fn get_random_allowed_order<R: Rng + ?Sized>(solution: &Solution, rng: &mut R, flags: u8, order: &Company) -> Option<AddOrderInfo>{
+    /// Adds the order on the allowed days where it takes the least extra time, at the cheapest place in each of them.
+    /// With `first`, the order goes right after that node and only patterns with that day are tried.
+    /// Returns None if the order is already planned, or if no allowed pattern has the day of `first`.
+    pub fn cheapest(solution: &Solution, order_index: OrderIndex, first: Option<&RoutePosition>) -> Option<Self> {
+        if solution.order_flags.get_flag(order_index) != 0 {
+            return None;
+        }
+        if let Some(first) = first
+            && (first.is_tail(solution) || first.order(solution).is_none())
+        {
+            return None;
+        }
+        get_orders()[order_index]
+            .allowed_patterns
+            .iter()
+            .filter(|flags| first.is_none_or(|first| *flags & OrderFlags::day_to_flags(first.day) != 0))
+            .filter_map(|flags| {
+                OrderFlags::flags_to_days(*flags)
+                    .into_iter()
+                    .map(|day| match first {
+                        Some(first) if first.day == day => {
+                            Some((*first, first.route(solution).calculate_add_order(first.node_index, order_index)))
+                        }
+                        _ => RoutePosition::cheapest(solution, day, order_index),
+                    })
+                    .collect::<Option<Vec<_>>>()
+            })
+            .min_by_key(|positions| positions.iter().map(|(_, time_diff)| *time_diff).sum::<i32>())
+            .map(|positions| AddMultipleNewOrders {
+                where_to_add_orders: positions
+                    .into_iter()
+                    .map(|(position, _)| AddOrderInfo {
+                        truck_enum: position.truck,
+                        day: position.day,
+                        time_of_day: position.time_of_day,
+                        insert_after_index: position.node_index,
+                    })
+                    .collect(),
+                order_index,
+            })
+    }
+
+    fn get_random_allowed_order<R: Rng + ?Sized>(solution: &Solution, rng: &mut R, flags: u8, order_index: OrderIndex, granular: bool) -> Option<AddOrderInfo>{
+        let order = &get_orders()[order_index];
 
         let truck_enum: TruckEnum = rng.random();
         let truck = if truck_enum == TruckEnum::Truck1 {&solution.truck1} else {&solution.truck2};
 
         // check if there is still an allowed day open
-        if let Some(day_enum) = OrderFlags::_get_random_allowed_day(flags, order.frequency, rng){
+        if let Some(day_enum) = OrderFlags::_get_random_allowed_day(flags, &order.allowed_patterns, rng){
             let day = truck.get(day_enum);
             let (route, time_of_day_enum) = day.get_random(rng);
 
+            if granular {
+                return Some(AddOrderInfo {
+                    truck_enum,
+                    day: day_enum,
+                    time_of_day: time_of_day_enum,
+                    insert_after_index: route.get_random_neighbor_position(order_index, rng)?,
+                })
+            }
+
             let lv = &route.linked_vector;
             while let Some((index, _)) = lv.get_random(rng) {
                 if lv.get_tail_index() == Some(index) {
@@ -83,8 +137,6 @@ impl AddMultipleNewOrders {
 
 impl NeighborMove for AddMultipleNewOrders {
     fn evaluate(&self, solution: &Solution) -> Evaluation {
-        let orders = get_orders();
-        let order = &orders[self.order_index];
         let evaluation:Evaluation = self.where_to_add_orders
             .iter()
             .map(|order_info| {
@@ -92,18 +144,24 @@ impl NeighborMove for AddMultipleNewOrders {
                     .get(order_info.day);
                 let route = day.get(order_info.time_of_day);
                 // calculate the time it takes to do add this order
+                let rests = route.calculate_rests_delta(None, Some((order_info.insert_after_index, self.order_index)));
                 let time_diff =
-                    route.calculate_add_order(order_info.insert_after_index, self.order_index);
+                    route.calculate_add_order(order_info.insert_after_index, self.order_index) + rests.break_time;
 
                 let time_overflow_delta =
                     calculate_time_overflow(time_diff, day.get_total_time());
                 let capacity_overflow_delta =
-                    calculate_capacity_overflow(order.total_container_volume as i32, route.capacity as i32);
+                    route.calculate_capacity_overflow_delta(None, Some((order_info.insert_after_index, self.order_index)));
+                let objective = route.calculate_objective_delta(None, Some((order_info.insert_after_index, self.order_index)));
 
                 Evaluation{
-                    cost: time_diff,
+                    cost: time_diff + objective.cost,
                     time_overflow_delta,
-                    capacity_overflow_delta
+                    capacity_overflow_delta,
+                    distance_delta: objective.distance,
+                    continuous_driving_overflow_delta: rests.continuous_overflow,
+                    weekly_overflow_delta: 0,
+                    day_times: DayTimes::single(order_info.truck_enum, order_info.day, time_diff),
                 }
             })
             .sum();
@@ -112,7 +170,7 @@ impl NeighborMove for AddMultipleNewOrders {
         Evaluation{
             cost: evaluation.cost - get_orders()[self.order_index].penalty,
             ..evaluation
-        }
+        }.with_week(solution)
     }
 
     fn apply(&self, solution: &mut Solution) -> ScoreChange {
@@ -138,4 +196,11 @@ impl NeighborMove for AddMultipleNewOrders {
         // We can always subtract the penalty, because this operation will add the order on as many days as needed to mee the frequency requirement.
         total_score_change - get_orders()[self.order_index].penalty
     }
+
+    fn respects_locks(&self, solution: &Solution) -> bool {
+        // The order isn't planned, so only the routes matter
+        self.where_to_add_orders.iter().all(|order_info| {
+            !solution.locks.is_frozen(order_info.truck_enum, order_info.day, order_info.time_of_day)
+        })
+    }
 }
@@ -0,0 +1,92 @@
+use rand::Rng;
+use crate::get_sites;
+use crate::datastructures::linked_vectors::{LVNodeIndex, LinkedVector};
+use crate::simulated_annealing::balance::DayTimes;
+use crate::simulated_annealing::day::TimeOfDay;
+use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
+use crate::simulated_annealing::neighbor_move::evaluation_helper::calculate_time_overflow;
+use crate::simulated_annealing::neighbor_move::neighbor_move_trait::{NeighborMove, ScoreChange};
+use crate::simulated_annealing::route::OrderIndex;
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use crate::simulated_annealing::solution::Solution;
+use crate::simulated_annealing::week::DayEnum;
+
+/// Lets the truck unload at a dump site between two customers, so the rest of the route starts with an empty truck.
+/// It takes the dump site that is the smallest detour from there.
+#[derive(Debug)]
+pub struct AddUnloadStop {
+    truck: TruckEnum,
+    day: DayEnum,
+    time_of_day: TimeOfDay,
+    insert_after: LVNodeIndex,
+    dump_site: OrderIndex,
+}
+
+impl AddUnloadStop {
+    pub fn new<R: Rng + ?Sized>(solution: &Solution, rng: &mut R) -> Option<Self> {
+        let truck: TruckEnum = rng.random();
+        let day: DayEnum = rng.random();
+        let time_of_day: TimeOfDay = rng.random();
+        let route = solution.get_truck(truck).get(day).get(time_of_day);
+        let lv = &route.linked_vector;
+        let sites = get_sites();
+
+        // Right after the depot or right before the end, the truck has nothing to unload or unloads anyway.
+        // Two unload stops in a row are just as useless.
+        let (insert_after, order) = lv.get_random(rng)?;
+        let next = lv.get_next_index(insert_after)?;
+        if insert_after == lv.get_head_index()?
+            || next == lv.get_tail_index()?
+            || sites.is_site(*order)
+            || sites.is_site(*lv.get_value_unsafe(next))
+        {
+            return None;
+        }
+
+        let dump_site = *sites
+            .dump_sites
+            .iter()
+            .min_by_key(|dump_site| route.calculate_add_order(insert_after, **dump_site))?;
+
+        Some(AddUnloadStop {
+            truck,
+            day,
+            time_of_day,
+            insert_after,
+            dump_site,
+        })
+    }
+}
+
+impl NeighborMove for AddUnloadStop {
+    fn evaluate(&self, solution: &Solution) -> Evaluation {
+        let day = solution.get_truck(self.truck).get(self.day);
+        let route = day.get(self.time_of_day);
+        let rests = route.calculate_rests_delta(None, Some((self.insert_after, self.dump_site)));
+        let time_difference = route.calculate_add_order(self.insert_after, self.dump_site) + rests.break_time;
+        let objective = route.calculate_objective_delta(None, Some((self.insert_after, self.dump_site)));
+
+        Evaluation {
+            cost: time_difference + objective.cost,
+            time_overflow_delta: calculate_time_overflow(time_difference, day.get_total_time()),
+            capacity_overflow_delta: route.calculate_capacity_overflow_delta(None, Some((self.insert_after, self.dump_site))),
+            distance_delta: objective.distance,
+            continuous_driving_overflow_delta: rests.continuous_overflow,
+            weekly_overflow_delta: 0,
+            day_times: DayTimes::single(self.truck, self.day, time_difference),
+        }
+        .with_week(solution)
+    }
+
+    fn apply(&self, solution: &mut Solution) -> ScoreChange {
+        solution
+            .get_truck_mut(self.truck)
+            .get_mut(self.day)
+            .get_mut(self.time_of_day)
+            .apply_add_order(self.insert_after, self.dump_site)
+    }
+
+    fn respects_locks(&self, solution: &Solution) -> bool {
+        !solution.locks.is_frozen(self.truck, self.day, self.time_of_day)
+    }
+}
@@ -0,0 +1,82 @@
+use rand::Rng;
+use rand::prelude::IndexedRandom;
+use crate::get_sites;
+use crate::simulated_annealing::balance::DayTimes;
+use crate::simulated_annealing::day::TimeOfDay;
+use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
+use crate::simulated_annealing::neighbor_move::evaluation_helper::calculate_time_overflow;
+use crate::simulated_annealing::neighbor_move::neighbor_move_trait::{NeighborMove, ScoreChange};
+use crate::simulated_annealing::route::OrderIndex;
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use crate::simulated_annealing::solution::Solution;
+use crate::simulated_annealing::week::DayEnum;
+
+/// Lets a trip unload at another dump site. This only does something when there is more than one.
+#[derive(Debug)]
+pub struct ChangeDumpSite {
+    truck: TruckEnum,
+    day: DayEnum,
+    time_of_day: TimeOfDay,
+    dump_site: OrderIndex,
+}
+
+impl ChangeDumpSite {
+    pub fn new<R: Rng + ?Sized>(solution: &Solution, rng: &mut R) -> Option<Self> {
+        let truck: TruckEnum = rng.random();
+        let day: DayEnum = rng.random();
+        let time_of_day: TimeOfDay = rng.random();
+        let route = solution.get_truck(truck).get(day).get(time_of_day);
+        if route.is_empty() {
+            return None;
+        }
+
+        let current = route.dump_site();
+        let dump_site = **get_sites()
+            .dump_sites
+            .iter()
+            .filter(|dump_site| **dump_site != current)
+            .collect::<Vec<_>>()
+            .choose(rng)?;
+
+        Some(ChangeDumpSite {
+            truck,
+            day,
+            time_of_day,
+            dump_site,
+        })
+    }
+}
+
+impl NeighborMove for ChangeDumpSite {
+    fn evaluate(&self, solution: &Solution) -> Evaluation {
+        let day = solution.get_truck(self.truck).get(self.day);
+        let route = day.get(self.time_of_day);
+        let rests = route.calculate_rests_change_dump_site(self.dump_site);
+        let time_difference = route.calculate_change_dump_site(self.dump_site) + rests.break_time;
+        let objective = route.calculate_objective_change_dump_site(self.dump_site);
+
+        // The same containers get picked up, so the capacity doesn't change
+        Evaluation {
+            cost: time_difference + objective.cost,
+            time_overflow_delta: calculate_time_overflow(time_difference, day.get_total_time()),
+            capacity_overflow_delta: 0,
+            distance_delta: objective.distance,
+            continuous_driving_overflow_delta: rests.continuous_overflow,
+            weekly_overflow_delta: 0,
+            day_times: DayTimes::single(self.truck, self.day, time_difference),
+        }
+        .with_week(solution)
+    }
+
+    fn apply(&self, solution: &mut Solution) -> ScoreChange {
+        solution
+            .get_truck_mut(self.truck)
+            .get_mut(self.day)
+            .get_mut(self.time_of_day)
+            .apply_change_dump_site(self.dump_site)
+    }
+
+    fn respects_locks(&self, solution: &Solution) -> bool {
+        !solution.locks.is_frozen(self.truck, self.day, self.time_of_day)
+    }
+}
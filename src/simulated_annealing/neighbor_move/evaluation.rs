@@ -1,14 +1,24 @@
 use std::iter::Sum;
 use std::ops::Add;
 use crate::resource::Time;
+use crate::simulated_annealing::balance::DayTimes;
 use crate::simulated_annealing::neighbor_move::neighbor_move_trait::CostChange;
+use crate::simulated_annealing::solution::Solution;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Evaluation{
     pub cost: CostChange,
     // ben nog op zoek naar betere namen
     pub time_overflow_delta: Time,
     pub capacity_overflow_delta: i32,
+    /// The change in driven metres. The cost already has what that's worth in the objective.
+    pub distance_delta: i32,
+    /// The change in how much longer than allowed the drivers drive without a rest, see Driver
+    pub continuous_driving_overflow_delta: Time,
+    /// The change in how much longer than allowed the weeks of the drivers are. Set by with_week.
+    pub weekly_overflow_delta: Time,
+    /// How much longer every day gets, for the balance
+    pub day_times: DayTimes,
 }
 
 impl Add for Evaluation {
@@ -20,9 +30,25 @@ impl Add for Evaluation {
             time_overflow_delta: self.time_overflow_delta + other.time_overflow_delta,
             capacity_overflow_delta: self.capacity_overflow_delta
                 + other.capacity_overflow_delta,
+            distance_delta: self.distance_delta + other.distance_delta,
+            continuous_driving_overflow_delta: self.continuous_driving_overflow_delta
+                + other.continuous_driving_overflow_delta,
+            weekly_overflow_delta: self.weekly_overflow_delta + other.weekly_overflow_delta,
+            day_times: self.day_times + other.day_times,
         }
     }
 }
+
+impl Evaluation {
+    /// Adds what the change in day times does to the balance to the cost, and to the weekly working time of the drivers.
+    /// Both depend on all days together, so this goes on the evaluation of the whole move, once.
+    pub fn with_week(self, solution: &Solution) -> Self {
+        let day_times = DayTimes::of(&solution.truck1, &solution.truck2);
+        let weekly_overflow_delta = day_times.weekly_overflow_delta(&self.day_times);
+        let balance = day_times.balance_cost_delta(&self.day_times);
+        Evaluation { cost: self.cost + balance, weekly_overflow_delta, ..self }
+    }
+}
 impl Sum for Evaluation {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
         iter.fold(Self::default(), |a, b| a + b)
@@ -1,30 +1,27 @@
 use std::cmp::max;
-use crate::get_distance_matrix;
-use crate::resource::{Time, FULL_DAY};
+use crate::get_travel_table;
+use crate::resource::{Time, FULL_DAY, TRUCK_CAPACITY};
 use petgraph::matrix_graph::NodeIndex;
 
 pub fn time_between_three_nodes(i1: NodeIndex, i2: NodeIndex, i3: NodeIndex) -> Time {
-    let dist = get_distance_matrix();
-
-    (if i1 == i2 {
-        0
-    } else {
-        dist.get_edge_weight(i1, i2).unwrap().travel_time
-    } + if i2 == i3 {
-        0
-    } else {
-        dist.get_edge_weight(i2, i3).unwrap().travel_time
-    })
+    let table = get_travel_table();
+
+    // the table has 0 on the diagonal, so we don't need to check if the nodes are the same
+    table.travel_time(i1, i2) + table.travel_time(i2, i3)
 }
 
 pub fn time_between_two_nodes(i1: NodeIndex, i2: NodeIndex) -> Time {
-    let dist = get_distance_matrix();
+    get_travel_table().travel_time(i1, i2)
+}
 
-    if i1 == i2 {
-        0
-    } else {
-        dist.get_edge_weight(i1, i2).unwrap().travel_time
-    }
+/// In metres. These are i32 like the times, so the differences can be negative.
+pub fn distance_between_three_nodes(i1: NodeIndex, i2: NodeIndex, i3: NodeIndex) -> i32 {
+    let table = get_travel_table();
+    table.distance(i1, i2) as i32 + table.distance(i2, i3) as i32
+}
+
+pub fn distance_between_two_nodes(i1: NodeIndex, i2: NodeIndex) -> i32 {
+    get_travel_table().distance(i1, i2) as i32
 }
 
 /// calculates the time overflow delta.
@@ -46,8 +43,33 @@ pub fn calculate_time_overflow(time_difference: Time, total_day_time: Time) -> T
 ///
 /// For explanations, see calaculate_time_overflow. This code loks a lot like that
 pub fn calculate_capacity_overflow(capacity_difference: i32, route_capacity: i32) -> i32 {
-    let old_overflow = max(route_capacity - 100_000,0);
-    let new_overflow = max(route_capacity + capacity_difference - 100_000, 0);
+    let old_overflow = max(route_capacity - TRUCK_CAPACITY as i32, 0);
+    let new_overflow = max(route_capacity + capacity_difference - TRUCK_CAPACITY as i32, 0);
 
     new_overflow - old_overflow
 }
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{parse_distance_matrix, parse_orderfile};
+    use crate::resource::TravelTable;
+
+    #[test]
+    fn table_matches_matrix() {
+        let orders = parse_orderfile().unwrap();
+        let distance_matrix = parse_distance_matrix().unwrap();
+        let travel_table = TravelTable::new(&distance_matrix, &orders);
+        for from in orders.iter().map(|order| order.matrix_id) {
+            for to in orders.iter().map(|order| order.matrix_id) {
+                if from == to {
+                    assert_eq!(travel_table.travel_time(from, to), 0);
+                    continue;
+                }
+                let distance = distance_matrix.get_edge_weight(from, to).unwrap();
+                assert_eq!(travel_table.travel_time(from, to), distance.travel_time);
+                assert_eq!(travel_table.distance(from, to), distance.absolute_distance);
+            }
+        }
+    }
+}
@@ -6,4 +6,8 @@ pub mod evaluation_helper;
 pub mod shift_between_days;
 pub mod add_multiple_at_once;
 pub mod remove_multiple_at_once;
-pub mod shift_in_day;
\ No newline at end of file
+pub mod shift_in_day;
+pub mod route_position;
+pub mod change_dump_site;
+pub mod add_unload_stop;
+pub mod remove_unload_stop;
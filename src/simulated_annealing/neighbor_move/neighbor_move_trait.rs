@@ -1,13 +1,17 @@
+use std::fmt::Debug;
 use crate::resource::Time;
 use crate::simulated_annealing::solution::Solution;
 use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
 
-pub trait NeighborMove {
+/// Moves have to be Debug, so paranoid mode can write down which move broke the solution.
+pub trait NeighborMove: Debug {
     // this would return the difference in volume or time
     // (not sure how to implement this yet)
     fn evaluate(&self, solution: &Solution) -> Evaluation;
     // this would perform the thing on the schedules.
     fn apply(&self, solution: &mut Solution) -> ScoreChange;
+    /// False if the move would change an order or route that's locked, see Locks.
+    fn respects_locks(&self, solution: &Solution) -> bool;
 }
 
 pub type CostChange = Time;
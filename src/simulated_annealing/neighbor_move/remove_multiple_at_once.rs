@@ -1,20 +1,24 @@
 use rand::Rng;
 use crate::datastructures::linked_vectors::{LVNodeIndex, LinkedVector};
-use crate::get_orders;
+use crate::{get_orders, get_sites};
+use crate::simulated_annealing::balance::DayTimes;
 use crate::simulated_annealing::day::TimeOfDay;
-use crate::simulated_annealing::neighbor_move::evaluation_helper::{calculate_capacity_overflow, calculate_time_overflow};
+use crate::simulated_annealing::neighbor_move::evaluation_helper::calculate_time_overflow;
 use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
 use crate::simulated_annealing::neighbor_move::neighbor_move_trait::{NeighborMove, ScoreChange};
+use crate::simulated_annealing::order_day_flags::OrderFlags;
 use crate::simulated_annealing::route::{OrderIndex, Route};
 use crate::simulated_annealing::simulated_annealing::TruckEnum;
 use crate::simulated_annealing::solution::Solution;
 use crate::simulated_annealing::week::{DayEnum, Week};
 
+#[derive(Debug)]
 pub struct RemoveMultipleOrders{
     orders_to_remove: Vec<RemoveOrderInfo>,
     order_index: OrderIndex,
 }
 
+#[derive(Debug)]
 struct RemoveOrderInfo {
     truck_enum: TruckEnum,
     day_enum: DayEnum,
@@ -42,6 +46,10 @@ impl RemoveMultipleOrders{
             if node_index == lv.get_head_index()? || node_index == lv.get_tail_index()? {
                 continue;
             }
+            // unload stops are removed by RemoveUnloadStop, the route might not even have a customer left
+            if get_sites().is_site(*order_index) {
+                return None;
+            }
 
             let mut orders_to_remove = vec![
                 RemoveOrderInfo{
@@ -66,6 +74,22 @@ impl RemoveMultipleOrders{
         panic!("The linkedvector was completely empty when trying to remove an order");
     }
 
+    /// Removes the order on all the days it's collected. Returns None if it isn't planned.
+    pub fn of_order(solution: &Solution, order_index: OrderIndex) -> Option<Self> {
+        let flags = solution.order_flags.get_flag(order_index);
+        if flags == 0 {
+            return None;
+        }
+        let orders_to_remove = (0..5)
+            .filter_map(|i| OrderFlags::flag_to_day(flags & 1 << i))
+            .map(|day_enum| Self::find_other_day(solution, day_enum, order_index))
+            .collect();
+        Some(RemoveMultipleOrders {
+            orders_to_remove,
+            order_index,
+        })
+    }
+
     fn find_other_day(solution: &Solution, day_enum: DayEnum, order_index: OrderIndex) -> RemoveOrderInfo{
         if let Some((time_of_day, node_index)) = Self::find_other_day_in_truck(&solution.truck1, day_enum, order_index) {
             return RemoveOrderInfo{
@@ -109,25 +133,29 @@ impl RemoveMultipleOrders{
 
 impl NeighborMove for RemoveMultipleOrders {
     fn evaluate(&self, solution: &Solution) -> Evaluation {
-        let order = &get_orders()[self.order_index];
-
         let evaluation: Evaluation = self.orders_to_remove.iter()
             .map(|order_info|{
                 let day = if order_info.truck_enum == TruckEnum::Truck1 {&solution.truck1} else {&solution.truck2}.get(order_info.day_enum);
                 let route = day.get(order_info.time_of_day);
 
-                let time_diff = route.calculate_remove_node(order_info.node_index);
+                let rests = route.calculate_rests_delta(Some(order_info.node_index), None);
+                let time_diff = route.calculate_remove_node(order_info.node_index) + rests.break_time;
 
                 let time_overflow_delta =
                     calculate_time_overflow(time_diff, day.get_total_time());
 
                 let capacity_overflow_delta =
-                    calculate_capacity_overflow(-(order.total_container_volume as i32), route.capacity as i32);
+                    route.calculate_capacity_overflow_delta(Some(order_info.node_index), None);
+                let objective = route.calculate_objective_delta(Some(order_info.node_index), None);
 
                 Evaluation{
-                    cost: time_diff,
+                    cost: time_diff + objective.cost,
                     time_overflow_delta,
                     capacity_overflow_delta,
+                    distance_delta: objective.distance,
+                    continuous_driving_overflow_delta: rests.continuous_overflow,
+                    weekly_overflow_delta: 0,
+                    day_times: DayTimes::single(order_info.truck_enum, order_info.day_enum, time_diff),
                 }
             })
             .sum();
@@ -135,7 +163,7 @@ impl NeighborMove for RemoveMultipleOrders {
         Evaluation{
             cost: evaluation.cost + get_orders()[self.order_index].penalty,
             ..evaluation
-        }
+        }.with_week(solution)
     }
 
     fn apply(&self, solution: &mut Solution) -> ScoreChange {
@@ -156,4 +184,11 @@ impl NeighborMove for RemoveMultipleOrders {
 
         total_change + get_orders()[self.order_index].penalty
     }
+
+    fn respects_locks(&self, solution: &Solution) -> bool {
+        solution.locks.can_change_days(self.order_index)
+            && self.orders_to_remove.iter().all(|order_info| {
+                !solution.locks.is_frozen(order_info.truck_enum, order_info.day_enum, order_info.time_of_day)
+            })
+    }
 }
@@ -0,0 +1,82 @@
+use rand::Rng;
+use rand::prelude::IndexedRandom;
+use crate::get_sites;
+use crate::datastructures::linked_vectors::{LVNodeIndex, LinkedVector};
+use crate::simulated_annealing::balance::DayTimes;
+use crate::simulated_annealing::day::TimeOfDay;
+use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
+use crate::simulated_annealing::neighbor_move::evaluation_helper::calculate_time_overflow;
+use crate::simulated_annealing::neighbor_move::neighbor_move_trait::{NeighborMove, ScoreChange};
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use crate::simulated_annealing::solution::Solution;
+use crate::simulated_annealing::week::DayEnum;
+
+/// Takes out one of the visits to a dump site in the middle of a route, the truck then keeps its load until the next one.
+#[derive(Debug)]
+pub struct RemoveUnloadStop {
+    truck: TruckEnum,
+    day: DayEnum,
+    time_of_day: TimeOfDay,
+    node_index: LVNodeIndex,
+}
+
+impl RemoveUnloadStop {
+    pub fn new<R: Rng + ?Sized>(solution: &Solution, rng: &mut R) -> Option<Self> {
+        let truck: TruckEnum = rng.random();
+        let day: DayEnum = rng.random();
+        let time_of_day: TimeOfDay = rng.random();
+        let route = solution.get_truck(truck).get(day).get(time_of_day);
+        if route.unload_stops == 0 {
+            return None;
+        }
+
+        let sites = get_sites();
+        let lv = &route.linked_vector;
+        let (head, tail) = (lv.get_head_index()?, lv.get_tail_index()?);
+        let unload_stops: Vec<LVNodeIndex> = lv
+            .iter()
+            .filter(|(node_index, order_index)| *node_index != head && *node_index != tail && sites.is_site(**order_index))
+            .map(|(node_index, _)| node_index)
+            .collect();
+
+        Some(RemoveUnloadStop {
+            truck,
+            day,
+            time_of_day,
+            node_index: *unload_stops.choose(rng)?,
+        })
+    }
+}
+
+impl NeighborMove for RemoveUnloadStop {
+    fn evaluate(&self, solution: &Solution) -> Evaluation {
+        let day = solution.get_truck(self.truck).get(self.day);
+        let route = day.get(self.time_of_day);
+        let rests = route.calculate_rests_delta(Some(self.node_index), None);
+        let time_difference = route.calculate_remove_node(self.node_index) + rests.break_time;
+        let objective = route.calculate_objective_delta(Some(self.node_index), None);
+
+        Evaluation {
+            cost: time_difference + objective.cost,
+            time_overflow_delta: calculate_time_overflow(time_difference, day.get_total_time()),
+            capacity_overflow_delta: route.calculate_capacity_overflow_delta(Some(self.node_index), None),
+            distance_delta: objective.distance,
+            continuous_driving_overflow_delta: rests.continuous_overflow,
+            weekly_overflow_delta: 0,
+            day_times: DayTimes::single(self.truck, self.day, time_difference),
+        }
+        .with_week(solution)
+    }
+
+    fn apply(&self, solution: &mut Solution) -> ScoreChange {
+        solution
+            .get_truck_mut(self.truck)
+            .get_mut(self.day)
+            .get_mut(self.time_of_day)
+            .apply_remove_node(self.node_index)
+    }
+
+    fn respects_locks(&self, solution: &Solution) -> bool {
+        !solution.locks.is_frozen(self.truck, self.day, self.time_of_day)
+    }
+}
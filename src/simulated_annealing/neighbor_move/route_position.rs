@@ -0,0 +1,73 @@
+use crate::datastructures::linked_vectors::{LVNodeIndex, LinkedVector};
+use crate::resource::Time;
+use crate::simulated_annealing::day::TimeOfDay;
+use crate::simulated_annealing::order_day_flags::OrderFlags;
+use crate::simulated_annealing::route::{OrderIndex, Route};
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use crate::simulated_annealing::solution::Solution;
+use crate::simulated_annealing::week::DayEnum;
+
+/// A node in one of the routes. The search picks these at random,
+/// this is for building a move at a place someone chose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoutePosition {
+    pub truck: TruckEnum,
+    pub day: DayEnum,
+    pub time_of_day: TimeOfDay,
+    pub node_index: LVNodeIndex,
+}
+
+impl RoutePosition {
+    pub fn route<'a>(&self, solution: &'a Solution) -> &'a Route {
+        solution.get_truck(self.truck).get(self.day).get(self.time_of_day)
+    }
+
+    /// The order at this node, None if the node isn't in the route.
+    pub fn order(&self, solution: &Solution) -> Option<OrderIndex> {
+        self.route(solution).linked_vector.get_value(self.node_index).copied()
+    }
+
+    pub fn is_head(&self, solution: &Solution) -> bool {
+        self.route(solution).linked_vector.get_head_index() == Some(self.node_index)
+    }
+
+    pub fn is_tail(&self, solution: &Solution) -> bool {
+        self.route(solution).linked_vector.get_tail_index() == Some(self.node_index)
+    }
+
+    /// True if both are in the same route, not necessarily at the same node.
+    pub fn same_route(&self, other: &RoutePosition) -> bool {
+        self.truck == other.truck && self.day == other.day && self.time_of_day == other.time_of_day
+    }
+
+    /// Every place the order is visited, sorted by day.
+    pub fn find_order(solution: &Solution, order_index: OrderIndex) -> Vec<RoutePosition> {
+        let flags = solution.order_flags.get_flag(order_index);
+        let mut positions = Vec::new();
+        for day in DayEnum::ALL.into_iter().filter(|day| flags & OrderFlags::day_to_flags(*day) != 0) {
+            for truck in [TruckEnum::Truck1, TruckEnum::Truck2] {
+                for time_of_day in [TimeOfDay::Morning, TimeOfDay::Afternoon] {
+                    let route = solution.get_truck(truck).get(day).get(time_of_day);
+                    if let Some((node_index, _)) = route.linked_vector.iter().find(|(_, order_i)| **order_i == order_index) {
+                        positions.push(RoutePosition { truck, day, time_of_day, node_index });
+                    }
+                }
+            }
+        }
+        positions
+    }
+
+    /// The place in any route on the day where inserting the order takes the least extra time.
+    /// Overflows are not taken into account. None if there's no route on the day to put it in.
+    pub fn cheapest(solution: &Solution, day: DayEnum, order_index: OrderIndex) -> Option<(RoutePosition, Time)> {
+        [TruckEnum::Truck1, TruckEnum::Truck2]
+            .into_iter()
+            .flat_map(|truck| [TimeOfDay::Morning, TimeOfDay::Afternoon].map(|time_of_day| (truck, time_of_day)))
+            .map(|(truck, time_of_day)| {
+                let route = solution.get_truck(truck).get(day).get(time_of_day);
+                let (node_index, time_diff) = route.get_cheapest_position(order_index);
+                (RoutePosition { truck, day, time_of_day, node_index }, time_diff)
+            })
+            .min_by_key(|(_, time_diff)| *time_diff)
+    }
+}
@@ -1,23 +1,27 @@
 use rand::Rng;
-use DayEnum::{Monday, Tuesday, Wednesday};
 use crate::datastructures::linked_vectors::{LVNodeIndex, LinkedVector};
-use crate::get_orders;
+use crate::{get_orders, get_sites};
+use crate::simulated_annealing::balance::DayTimes;
 use crate::simulated_annealing::day::TimeOfDay;
-use crate::simulated_annealing::neighbor_move::evaluation_helper::{calculate_capacity_overflow, calculate_time_overflow};
+use crate::simulated_annealing::neighbor_move::evaluation_helper::calculate_time_overflow;
 use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
 use crate::simulated_annealing::neighbor_move::neighbor_move_trait::{NeighborMove, ScoreChange};
+use crate::simulated_annealing::neighbor_move::route_position::RoutePosition;
 use crate::simulated_annealing::order_day_flags::OrderFlags;
 use crate::simulated_annealing::route::{OrderIndex, Route};
 use crate::simulated_annealing::simulated_annealing::TruckEnum;
 use crate::simulated_annealing::solution::Solution;
 use crate::simulated_annealing::week::{DayEnum, Week};
-use crate::simulated_annealing::week::DayEnum::{Friday, Thursday};
 
+/// Moves an order to other days. When one visit moves, the other visits that aren't in the new pattern move along.
+#[derive(Debug)]
 pub struct ShiftBetweenDays {
-    shifts: [Option<TruckDayTimeNode>; 2],
-    targets: [Option<TruckDayTimeNode>; 2],
+    /// The visits that move, every one goes to the target with the same index
+    shifts: Vec<TruckDayTimeNode>,
+    targets: Vec<TruckDayTimeNode>,
     order: OrderIndex,
 }
+#[derive(Debug)]
 struct TruckDayTimeNode{
     truck: TruckEnum,
     day: DayEnum,
@@ -26,80 +30,92 @@ struct TruckDayTimeNode{
 }
 
 impl ShiftBetweenDays {
+    /// If granular is true, the order will only be shifted next to one of its neighbours.
     pub fn new<R: Rng + ?Sized>(
         solution: &Solution,
         rng: &mut R,
+        granular: bool,
     ) -> Option<Self> {
         let (first_thingy, shift_order_index) = Self::find_first_random_node(solution, rng)?;
 
         let order = &get_orders()[shift_order_index];
-        // if the order has frequency 3 or 5, we can't shift to another place
-        let frequency = order.frequency as u8;
-        if frequency == 3 ||
-            frequency == 5 {
+        let flags = solution.order_flags.get_flag(shift_order_index);
+
+        // The random visit has to leave its day, so only patterns without that day will do.
+        // If the order has just one allowed pattern, like the defaults for 3 and 5 times a week, it can't be shifted.
+        let first_flag = OrderFlags::day_to_flags(first_thingy.day);
+        let new_patterns: Vec<u8> = order
+            .allowed_patterns
+            .iter()
+            .copied()
+            .filter(|pattern| pattern & first_flag == 0)
+            .collect();
+        if new_patterns.is_empty() {
             return None;
         }
+        let new_flags = new_patterns[rng.random_range(0..new_patterns.len())];
 
+        // The random visit moves to the first new day, the other visits that have to go follow in order of the days
+        let target_days = OrderFlags::flags_to_days(new_flags & !flags);
+        let mut shifts = vec![first_thingy];
+        for day in OrderFlags::flags_to_days(flags & !new_flags & !first_flag) {
+            shifts.push(Self::find_other_day(solution, day, shift_order_index));
+        }
+        let targets = target_days
+            .into_iter()
+            .map(|day| Self::find_random_target(solution, rng, day, shift_order_index, granular))
+            .collect::<Option<Vec<_>>>()?;
+        debug_assert_eq!(shifts.len(), targets.len());
 
+        Some(Self {
+            shifts,
+            targets,
+            order: shift_order_index,
+        })
+    }
+    /// Moves the order at `shift` to right after `target`, on another day.
+    /// When that day doesn't fit the other days of the order, it switches to the allowed pattern that keeps most of them.
+    /// The visits that have to move along go to the place on their new day that takes the least extra time.
+    /// Returns None if the order is already collected on the target day, or if no allowed pattern has the target day without the shift day.
+    pub fn between(solution: &Solution, shift: &RoutePosition, target: &RoutePosition) -> Option<Self> {
+        if shift.day == target.day || shift.is_head(solution) || shift.is_tail(solution) || target.is_tail(solution) {
+            return None;
+        }
+        target.order(solution)?;
+        let order_index = shift.order(solution)?;
+        let flags = solution.order_flags.get_flag(order_index);
+        let (shift_flag, target_flag) = (OrderFlags::day_to_flags(shift.day), OrderFlags::day_to_flags(target.day));
+        if flags & target_flag != 0 {
+            return None;
+        }
 
-        let mut second_shift: Option<TruckDayTimeNode> = None;
-
-        let targets = match frequency {
-            1 => {
-                // this is my attempt at getting a random number between 0 and 4 that is different from the shift day.
-                // random day between 0 and 3.
-                let random_num = rng.random_range(0..4);
-                let target_day = if random_num == first_thingy.day as u8 {
-                    // if we got the same day as the shift day, we use day 4 (0 based indexing)
-                    Friday
-                } else {
-                    // just use the random number we found.
-                    match random_num {
-                        0 => Monday,
-                        1 => Tuesday,
-                        2 => Wednesday,
-                        3 => Thursday,
-                        _ => unreachable!(),
-                    }
-                };
-                [Some(Self::find_random_target(solution, rng, target_day)?), None]},
-            2 => {
-                let other_shift_day = match first_thingy.day {
-                    Monday => Thursday,
-                    Tuesday => Friday,
-                    Wednesday => unreachable!(),
-                    Thursday => Monday,
-                    Friday => Tuesday,
-                };
-                let other_shift_node = Self::find_other_day(solution, other_shift_day, shift_order_index);
-                second_shift = Some(other_shift_node);
-
-                let target_days = match first_thingy.day {
-                    Monday => [Tuesday, Friday],
-                    Tuesday => [Monday, Thursday],
-                    Wednesday => unreachable!(),
-                    Thursday => [Tuesday, Friday],
-                    Friday => [Monday, Thursday],
-                };
-
-                [Some(Self::find_random_target(solution, rng, target_days[0])?),
-                    Some(Self::find_random_target(solution, rng, target_days[1])?)]
-            },
-            3 => return None,
-            4 => {
-                let flags = solution.order_flags.get_flag(shift_order_index);
-                // Flip all bits, then mask to keep only the 5 day bits
-                let available_days_mask = (!flags) & 0b11111;
-                let day = OrderFlags::flag_to_day(available_days_mask)?;
-                [Some(Self::find_random_target(solution, rng, day)?), None]
-            },
-            5 => return None,
-            _ => unreachable!()
+        let new_flags = get_orders()[order_index]
+            .allowed_patterns
+            .iter()
+            .copied()
+            .filter(|pattern| pattern & target_flag != 0 && pattern & shift_flag == 0)
+            .max_by_key(|pattern| (pattern & flags).count_ones())?;
+
+        let node = |position: &RoutePosition| TruckDayTimeNode {
+            truck: position.truck,
+            day: position.day,
+            time_of_day: position.time_of_day,
+            node_index: position.node_index,
         };
+        let mut shifts = vec![node(shift)];
+        let mut targets = vec![node(target)];
+        let other_shift_days = OrderFlags::flags_to_days(flags & !new_flags & !shift_flag);
+        let other_target_days = OrderFlags::flags_to_days(new_flags & !flags & !target_flag);
+        for (shift_day, target_day) in other_shift_days.into_iter().zip(other_target_days) {
+            let (other_target, _) = RoutePosition::cheapest(solution, target_day, order_index)?;
+            shifts.push(Self::find_other_day(solution, shift_day, order_index));
+            targets.push(node(&other_target));
+        }
+
         Some(Self {
-            shifts: [Some(first_thingy), second_shift],
+            shifts,
             targets,
-            order: shift_order_index,
+            order: order_index,
         })
     }
     fn find_first_random_node<R: Rng + ?Sized>(solution: &Solution, rng: &mut R) -> Option<(TruckDayTimeNode, OrderIndex)>{
@@ -112,7 +128,8 @@ impl ShiftBetweenDays {
         let (node_index, order) = route.linked_vector.get_random(rng).unwrap();
 
         if node_index == route.linked_vector.get_head_index().unwrap() ||
-            node_index == route.linked_vector.get_tail_index().unwrap(){
+            node_index == route.linked_vector.get_tail_index().unwrap() ||
+            get_sites().is_site(*order){
             return None;
         }
 
@@ -165,12 +182,22 @@ impl ShiftBetweenDays {
         None
     }
     /// gets a random target_node on the given day. This could be any node in a route besides the tail.
-    fn find_random_target<R: Rng + ?Sized>(solution: &Solution, rng: &mut R, day_enum: DayEnum) -> Option<TruckDayTimeNode>{
+    /// If granular is true, it only returns nodes next to a neighbour of the order.
+    fn find_random_target<R: Rng + ?Sized>(solution: &Solution, rng: &mut R, day_enum: DayEnum, order_index: OrderIndex, granular: bool) -> Option<TruckDayTimeNode>{
         let truck:TruckEnum = rng.random();
         let day = solution.get_truck(truck).get(day_enum);
         let time_of_day = rng.random();
         let route = day.get(time_of_day);
 
+        if granular {
+            return Some(TruckDayTimeNode{
+                truck,
+                day: day_enum,
+                time_of_day,
+                node_index: route.get_random_neighbor_position(order_index, rng)?,
+            });
+        }
+
 
         loop {
             let (node_index, _order_index) = route.linked_vector.get_random(rng)?;
@@ -186,88 +213,75 @@ impl ShiftBetweenDays {
             });
         }
     }
-    /// I call this function once most of the time with i=0. This gets the first element in the shift and target array.
-    /// If there is a frequency 2 order, this function is also called with i=1.
+    /// Evaluates moving the i-th shift to the i-th target.
+    /// The shifts are all on different days, and so are the targets, so they can be evaluated one by one.
     fn evaluation_helper(&self, solution: &Solution, i: usize) -> Evaluation {
-        let shift_info = self.shifts[i].as_ref().unwrap();
+        let shift_info = &self.shifts[i];
         let shift_day = solution.get_truck(shift_info.truck).get(shift_info.day);
         let shift_route = shift_day.get(shift_info.time_of_day);
 
-        let shift_diff = shift_route.calculate_remove_node(shift_info.node_index);
+        let shift_rests = shift_route.calculate_rests_delta(Some(shift_info.node_index), None);
+        let shift_diff = shift_route.calculate_remove_node(shift_info.node_index) + shift_rests.break_time;
 
-        let target_info = self.targets[i].as_ref().unwrap();
+        let target_info = &self.targets[i];
         let target_day = solution.get_truck(target_info.truck).get(target_info.day);
         let target_route = target_day.get(target_info.time_of_day);
 
-        let target_diff = target_route.calculate_add_order(target_info.node_index, self.order);
+        let target_rests = target_route.calculate_rests_delta(None, Some((target_info.node_index, self.order)));
+        let target_diff = target_route.calculate_add_order(target_info.node_index, self.order) + target_rests.break_time;
 
         let shift_t_delta = calculate_time_overflow(shift_diff, shift_day.get_total_time());
         let target_t_delta = calculate_time_overflow(target_diff, target_day.get_total_time());
 
-        let orders = get_orders();
-        let order = &orders[self.order];
-        let shift_c_delta = calculate_capacity_overflow(-(order.total_container_volume as i32), shift_route.capacity as i32);
-        let target_c_delta = calculate_capacity_overflow(order.total_container_volume as i32, target_route.capacity as i32);
+        let shift_c_delta = shift_route.calculate_capacity_overflow_delta(Some(shift_info.node_index), None);
+        let target_c_delta = target_route.calculate_capacity_overflow_delta(None, Some((target_info.node_index, self.order)));
+
+        let shift_objective = shift_route.calculate_objective_delta(Some(shift_info.node_index), None);
+        let target_objective = target_route.calculate_objective_delta(None, Some((target_info.node_index, self.order)));
 
         Evaluation {
-            cost: shift_diff + target_diff,
+            cost: shift_diff + target_diff + shift_objective.cost + target_objective.cost,
             time_overflow_delta: shift_t_delta + target_t_delta,
             capacity_overflow_delta: shift_c_delta + target_c_delta,
+            distance_delta: shift_objective.distance + target_objective.distance,
+            continuous_driving_overflow_delta: shift_rests.continuous_overflow + target_rests.continuous_overflow,
+            weekly_overflow_delta: 0,
+            day_times: DayTimes::single(shift_info.truck, shift_info.day, shift_diff)
+                + DayTimes::single(target_info.truck, target_info.day, target_diff),
         }
     }
 }
 
 impl NeighborMove for ShiftBetweenDays {
     fn evaluate(&self, solution: &Solution) -> Evaluation {
-        let first_shift = self.evaluation_helper(solution, 0);
-        // if we are doing a shift for frequency 2, also calaculate the second stored shift
-        if self.shifts[1].is_some(){
-            return self.evaluation_helper(solution, 1) + first_shift;
-        }
-        first_shift
+        (1..self.shifts.len())
+            .fold(self.evaluation_helper(solution, 0), |evaluation, i| evaluation + self.evaluation_helper(solution, i))
+            .with_week(solution)
     }
 
     fn apply(&self, solution: &mut Solution) -> ScoreChange {
-        // first we clear the order_flags
-        // get all the data and apply the remove and adds
-        let shift_info = self.shifts[0].as_ref().unwrap();
-        solution.order_flags.remove_order(self.order, shift_info.day);
-        let shift_route = solution.get_truck_mut(shift_info.truck)
-            .get_mut(shift_info.day)
-            .get_mut(shift_info.time_of_day);
-        let shift_diff = shift_route.apply_remove_node(shift_info.node_index);
-
-        let target_info = self.targets[0].as_ref().unwrap();
-        let target_route = solution.get_truck_mut(target_info.truck)
-            .get_mut(target_info.day)
-            .get_mut(target_info.time_of_day);
-        let target_diff = target_route.apply_add_order(target_info.node_index, self.order);
-
-        // update the order flags
-        solution.order_flags.add_order(self.order, target_info.day);
-
-        // ugly code that maybe works
-        if self.shifts[1].is_some(){
-            let shift_info = self.shifts[1].as_ref().unwrap();
+        let mut score_change = 0;
+        for (shift_info, target_info) in self.shifts.iter().zip(&self.targets) {
             solution.order_flags.remove_order(self.order, shift_info.day);
             let shift_route = solution.get_truck_mut(shift_info.truck)
                 .get_mut(shift_info.day)
                 .get_mut(shift_info.time_of_day);
-            let shift_diff2 = shift_route.apply_remove_node(shift_info.node_index);
+            score_change += shift_route.apply_remove_node(shift_info.node_index);
 
-            let target_info = self.targets[1].as_ref().unwrap();
             let target_route = solution.get_truck_mut(target_info.truck)
                 .get_mut(target_info.day)
                 .get_mut(target_info.time_of_day);
-            let target_diff2 = target_route.apply_add_order(target_info.node_index, self.order);
-
-            // update the order flags
+            score_change += target_route.apply_add_order(target_info.node_index, self.order);
             solution.order_flags.add_order(self.order, target_info.day);
-
-            return shift_diff + target_diff + shift_diff2 + target_diff2;
         }
+        score_change
+    }
 
-
-        shift_diff + target_diff
+    fn respects_locks(&self, solution: &Solution) -> bool {
+        let locks = &solution.locks;
+        locks.can_change_days(self.order)
+            && self.shifts.iter().chain(self.targets.iter()).all(|position| {
+                !locks.is_frozen(position.truck, position.day, position.time_of_day)
+            })
     }
 }
\ No newline at end of file
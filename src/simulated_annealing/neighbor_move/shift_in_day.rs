@@ -1,21 +1,25 @@
 use rand::Rng;
+use crate::get_sites;
 use crate::datastructures::linked_vectors::{LVNodeIndex, LinkedVector};
-use crate::get_orders;
+use crate::simulated_annealing::balance::DayTimes;
 use crate::simulated_annealing::day::TimeOfDay;
-use crate::simulated_annealing::neighbor_move::evaluation_helper::{calculate_capacity_overflow, calculate_time_overflow};
+use crate::simulated_annealing::neighbor_move::evaluation_helper::calculate_time_overflow;
 use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
 use crate::simulated_annealing::neighbor_move::neighbor_move_trait::{NeighborMove, ScoreChange};
+use crate::simulated_annealing::neighbor_move::route_position::RoutePosition;
 use crate::simulated_annealing::route::OrderIndex;
 use crate::simulated_annealing::simulated_annealing::TruckEnum;
 use crate::simulated_annealing::solution::Solution;
 use crate::simulated_annealing::week::DayEnum;
 
+#[derive(Debug)]
 pub struct ShiftInDay {
     shift: TruckDayTimeNode,
     target: TruckDayTimeNode,
 }
 
 
+#[derive(Debug)]
 struct TruckDayTimeNode {
     truck: TruckEnum,
     day: DayEnum,
@@ -25,15 +29,40 @@ struct TruckDayTimeNode {
 }
 
 impl ShiftInDay {
+    /// If granular is true, the order will only be shifted next to one of its neighbours.
     pub fn new<R: Rng + ?Sized>(
         solution: &Solution,
         rng: &mut R,
+        granular: bool,
     ) -> Option<Self> {
         let shift = Self::get_shift(solution, rng)?;
-        let target = Self::get_target(solution, rng, &shift)?;
+        let target = Self::get_target(solution, rng, &shift, granular)?;
         Some(Self { shift, target })
     }
 
+    /// Moves the order at `shift` to right after `target`, in another route on the same day.
+    /// Returns None if the head or tail would move, or if both are in the same route or on different days.
+    pub fn between(solution: &Solution, shift: &RoutePosition, target: &RoutePosition) -> Option<Self> {
+        if shift.day != target.day
+            || shift.same_route(target)
+            || shift.is_head(solution)
+            || shift.is_tail(solution)
+            || target.is_tail(solution)
+        {
+            return None;
+        }
+        let node = |position: &RoutePosition| {
+            Some(TruckDayTimeNode {
+                truck: position.truck,
+                day: position.day,
+                time_of_day: position.time_of_day,
+                node_index: position.node_index,
+                order: position.order(solution)?,
+            })
+        };
+        Some(Self { shift: node(shift)?, target: node(target)? })
+    }
+
     fn get_shift<R: Rng + ?Sized>(
         solution: &Solution,
         rng: &mut R,
@@ -44,9 +73,10 @@ impl ShiftInDay {
         let route = (if truck == TruckEnum::Truck1 {&solution.truck1} else {&solution.truck2}).get(day).get(time_of_day);
         let (node_index, order) = route.linked_vector.get_random(rng).unwrap();
 
-        // if the random node is a tail or head, we can't shift it.
+        // if the random node is a tail or head, we can't shift it. Unload stops stay in their own route.
         if node_index == route.linked_vector.get_tail_index().unwrap() ||
-            node_index == route.linked_vector.get_head_index().unwrap(){
+            node_index == route.linked_vector.get_head_index().unwrap() ||
+            get_sites().is_site(*order){
             None
         } else {
             Some(TruckDayTimeNode{
@@ -62,7 +92,8 @@ impl ShiftInDay {
     fn get_target<R: Rng + ?Sized>(
         solution: &Solution,
         rng: &mut R,
-        shift: &TruckDayTimeNode
+        shift: &TruckDayTimeNode,
+        granular: bool,
     ) -> Option<TruckDayTimeNode> {
         let random_truck = rng.random_range(0..3);
         // random number between 0 and 2.
@@ -81,6 +112,16 @@ impl ShiftInDay {
 
         let route = (if truck == TruckEnum::Truck1 {&solution.truck1} else {&solution.truck2}).get(day).get(time_of_day);
 
+        if granular {
+            let node_index = route.get_random_neighbor_position(shift.order, rng)?;
+            return Some(TruckDayTimeNode{
+                truck,
+                day,
+                time_of_day,
+                node_index,
+                order: *route.linked_vector.get_value_unsafe(node_index),
+            });
+        }
 
         loop {
             let (node_index, order) = route.linked_vector.get_random(rng).unwrap();
@@ -123,23 +164,32 @@ impl NeighborMove for ShiftInDay {
         let target_day = (if self.target.truck == TruckEnum::Truck1 {&solution.truck1} else {&solution.truck2}).get(self.target.day);
         let target_route = target_day.get(self.target.time_of_day);
 
-        let shift_diff = shift_route.calculate_remove_node(self.shift.node_index);
-        let target_diff = target_route.calculate_add_order(self.target.node_index, self.shift.order);
+        let shift_rests = shift_route.calculate_rests_delta(Some(self.shift.node_index), None);
+        let shift_diff = shift_route.calculate_remove_node(self.shift.node_index) + shift_rests.break_time;
+        let target_rests = target_route.calculate_rests_delta(None, Some((self.target.node_index, self.shift.order)));
+        let target_diff = target_route.calculate_add_order(self.target.node_index, self.shift.order) + target_rests.break_time;
 
         let shift_t_delta = calculate_time_overflow(shift_diff, shift_day.get_total_time());
         let target_t_delta = calculate_time_overflow(target_diff, target_day.get_total_time());
 
 
-        let orders = get_orders();
-        let order = &orders[self.shift.order];
-        let shift_c_delta = calculate_capacity_overflow(-(order.total_container_volume as i32), shift_route.capacity as i32);
-        let target_c_delta = calculate_capacity_overflow(order.total_container_volume as i32, target_route.capacity as i32);
+        let shift_c_delta = shift_route.calculate_capacity_overflow_delta(Some(self.shift.node_index), None);
+        let target_c_delta = target_route.calculate_capacity_overflow_delta(None, Some((self.target.node_index, self.shift.order)));
+
+        let shift_objective = shift_route.calculate_objective_delta(Some(self.shift.node_index), None);
+        let target_objective = target_route.calculate_objective_delta(None, Some((self.target.node_index, self.shift.order)));
 
         Evaluation {
-            cost: shift_diff + target_diff,
+            cost: shift_diff + target_diff + shift_objective.cost + target_objective.cost,
             time_overflow_delta: shift_t_delta + target_t_delta,
             capacity_overflow_delta: shift_c_delta + target_c_delta,
+            distance_delta: shift_objective.distance + target_objective.distance,
+            continuous_driving_overflow_delta: shift_rests.continuous_overflow + target_rests.continuous_overflow,
+            weekly_overflow_delta: 0,
+            day_times: DayTimes::single(self.shift.truck, self.shift.day, shift_diff)
+                + DayTimes::single(self.target.truck, self.target.day, target_diff),
         }
+        .with_week(solution)
     }
 
     fn apply(&self, solution: &mut Solution) -> ScoreChange {
@@ -153,4 +203,11 @@ impl NeighborMove for ShiftInDay {
         let target_diff = target_route.apply_add_order(self.target.node_index, self.shift.order);
         shift_diff + target_diff
     }
+
+    fn respects_locks(&self, solution: &Solution) -> bool {
+        let locks = &solution.locks;
+        !locks.is_frozen(self.shift.truck, self.shift.day, self.shift.time_of_day)
+            && !locks.is_frozen(self.target.truck, self.target.day, self.target.time_of_day)
+            && locks.can_change_route(self.shift.order, self.shift.truck, self.target.truck)
+    }
 }
\ No newline at end of file
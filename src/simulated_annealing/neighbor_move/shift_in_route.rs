@@ -1,13 +1,19 @@
 use crate::datastructures::linked_vectors::{LVNodeIndex, LinkedVector};
 use crate::resource::{Time};
+use crate::simulated_annealing::balance::DayTimes;
 use crate::simulated_annealing::day::TimeOfDay;
 use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
 use crate::simulated_annealing::neighbor_move::neighbor_move_trait::{NeighborMove};
+use crate::simulated_annealing::neighbor_move::route_position::RoutePosition;
+use crate::simulated_annealing::route::{ObjectiveDelta, RestsDelta};
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
 use crate::simulated_annealing::week::{DayEnum};
 use rand::Rng;
+use rand::prelude::IteratorRandom;
 use crate::simulated_annealing::neighbor_move::evaluation_helper::calculate_time_overflow;
 use crate::simulated_annealing::solution::Solution;
 
+#[derive(Debug)]
 pub struct ShiftInRoute {
     truck1: bool,
     day: DayEnum,
@@ -17,7 +23,8 @@ pub struct ShiftInRoute {
 }
 
 impl ShiftInRoute {
-    pub fn new<R: Rng + ?Sized>(solution: &Solution, rng: &mut R) -> Option<Self> {
+    /// If granular is true, the node will only be shifted next to one of its neighbours.
+    pub fn new<R: Rng + ?Sized>(solution: &Solution, rng: &mut R, granular: bool) -> Option<Self> {
         let truck1: bool = rng.random();
         let truck = if truck1 { &solution.truck1 } else { &solution.truck2 };
 
@@ -45,18 +52,23 @@ impl ShiftInRoute {
 
         let before_shifting_node = lv.get_prev_index(shifting_node).unwrap();
 
-        let target_neighbor1: LVNodeIndex;
-        loop {
-            let (node_index, _) = lv.get_random(rng).unwrap();
-            if node_index == shifting_node
-                || node_index == before_shifting_node
-                || node_index == lv.get_tail_index().unwrap()
-            {
-                continue;
+        let target_neighbor1: LVNodeIndex = if granular {
+            route
+                .get_neighbor_positions(*lv.get_value_unsafe(shifting_node))
+                .filter(|node_index| *node_index != shifting_node && *node_index != before_shifting_node)
+                .choose(rng)?
+        } else {
+            loop {
+                let (node_index, _) = lv.get_random(rng).unwrap();
+                if node_index == shifting_node
+                    || node_index == before_shifting_node
+                    || node_index == lv.get_tail_index().unwrap()
+                {
+                    continue;
+                }
+                break node_index;
             }
-            target_neighbor1 = node_index;
-            break;
-        }
+        };
 
         Some(ShiftInRoute {
             truck1,
@@ -67,6 +79,30 @@ impl ShiftInRoute {
         })
     }
 
+    /// Moves the node at `shifting` to right after `target_neighbor`, in the same route.
+    /// Returns None if the head or tail would move, or if nothing would change.
+    pub fn between(solution: &Solution, shifting: &RoutePosition, target_neighbor: LVNodeIndex) -> Option<Self> {
+        let lv = &shifting.route(solution).linked_vector;
+        lv.get_value(target_neighbor)?;
+        if shifting.is_head(solution)
+            || shifting.is_tail(solution)
+            || target_neighbor == shifting.node_index
+            || Some(target_neighbor) == lv.get_prev_index(shifting.node_index)
+            || Some(target_neighbor) == lv.get_tail_index()
+        {
+            return None;
+        }
+
+        Some(ShiftInRoute {
+            truck1: shifting.truck == TruckEnum::Truck1,
+            day: shifting.day,
+            time_of_day: shifting.time_of_day,
+            shifting_node: shifting.node_index,
+            target_neighbor1: target_neighbor,
+        })
+    }
+
+    /// Without the change in breaks, that's in rests_delta
     pub fn time_difference(&self, solution: &Solution) -> Time {
         let truck = if self.truck1 { &solution.truck1 } else { &solution.truck2 };
         let route = truck.get(self.day).get(self.time_of_day);
@@ -76,11 +112,32 @@ impl ShiftInRoute {
             route.calculate_add_order(self.target_neighbor1, *lv.get_value_unsafe(self.shifting_node))
 
     }
+
+    pub fn driving_delta(&self, solution: &Solution) -> Time {
+        let truck = if self.truck1 { &solution.truck1 } else { &solution.truck2 };
+        let route = truck.get(self.day).get(self.time_of_day);
+        let shifting_value = *route.linked_vector.get_value_unsafe(self.shifting_node);
+        route.calculate_driving_delta(Some(self.shifting_node), Some((self.target_neighbor1, shifting_value)))
+    }
+
+    pub fn rests_delta(&self, solution: &Solution) -> RestsDelta {
+        let truck = if self.truck1 { &solution.truck1 } else { &solution.truck2 };
+        let route = truck.get(self.day).get(self.time_of_day);
+        let shifting_value = *route.linked_vector.get_value_unsafe(self.shifting_node);
+        route.calculate_rests_delta(Some(self.shifting_node), Some((self.target_neighbor1, shifting_value)))
+    }
+
+    pub fn objective_delta(&self, solution: &Solution) -> ObjectiveDelta {
+        let truck = if self.truck1 { &solution.truck1 } else { &solution.truck2 };
+        let route = truck.get(self.day).get(self.time_of_day);
+        let shifting_value = *route.linked_vector.get_value_unsafe(self.shifting_node);
+        route.calculate_objective_delta(Some(self.shifting_node), Some((self.target_neighbor1, shifting_value)))
+    }
 }
 impl NeighborMove for ShiftInRoute {
     fn evaluate(&self, solution: &Solution) -> Evaluation {
-        let truck = if self.truck1 { &solution.truck1 } else { &solution.truck2 };
-        let day = truck.get(self.day);
+        let truck_enum = if self.truck1 { TruckEnum::Truck1 } else { TruckEnum::Truck2 };
+        let day = solution.get_truck(truck_enum).get(self.day);
         let route = day.get(self.time_of_day);
         let lv = &route.linked_vector;
 
@@ -88,29 +145,47 @@ impl NeighborMove for ShiftInRoute {
         // The calculate_remove_node function would remove 30 minutes from time_difference. This is because the route would be empty after removing
         // Calculate add order would not add 30 minutes to the time difference, because the route is currently not empty
         // This would thus result in a time difference of 30 minutes while nothing changed to the route.
+        let rests = self.rests_delta(solution);
         let time_difference = route.calculate_remove_node(self.shifting_node) +
-            route.calculate_add_order(self.target_neighbor1, *lv.get_value_unsafe(self.shifting_node));
+            route.calculate_add_order(self.target_neighbor1, *lv.get_value_unsafe(self.shifting_node)) +
+            rests.break_time;
 
         let time_overflow_delta = calculate_time_overflow(time_difference, day.get_total_time());
 
 
-        // We don't calculate anything in regard to the capacity,
-        // because the capacity of this route does not change.
+        // The route picks up the same containers, but with unload stops in it, they can end up in another load.
+        let capacity_overflow_delta = route.calculate_capacity_overflow_delta(
+            Some(self.shifting_node),
+            Some((self.target_neighbor1, *lv.get_value_unsafe(self.shifting_node))),
+        );
+        let objective = self.objective_delta(solution);
+
         Evaluation {
-            cost: time_difference,
+            cost: time_difference + objective.cost,
             time_overflow_delta,
-            capacity_overflow_delta: 0,
+            capacity_overflow_delta,
+            distance_delta: objective.distance,
+            continuous_driving_overflow_delta: rests.continuous_overflow,
+            weekly_overflow_delta: 0,
+            day_times: DayTimes::single(truck_enum, self.day, time_difference),
         }
+        .with_week(solution)
     }
 
     fn apply(&self, solution: &mut Solution) -> Time {
         // calculate the change in time after this operation
         let time_difference = self.time_difference(solution);
+        let objective = self.objective_delta(solution);
+        let driving_difference = self.driving_delta(solution);
 
         let truck = if self.truck1 { &mut solution.truck1 } else { &mut solution.truck2 };
         let route = truck.get_mut(self.day).get_mut(self.time_of_day);
 
         route.time += time_difference;
+        route.driving += driving_difference;
+        // the same customers stay in the route, so it isn't empty before or after and all of the distance counts
+        route.distance = (route.distance as i32 + objective.distance) as u32;
+        route.cost += objective.cost;
 
         // move the shifting_node in the lv
         let lv = &mut route.linked_vector;
@@ -119,9 +194,14 @@ impl NeighborMove for ShiftInRoute {
         lv.insert_after(self.target_neighbor1, shifting_value);
         // don't need to compact, because the lv has the same length as before the operations.
 
-        #[cfg(debug_assertions)]
-        route.check_correctness_time();
+        time_difference + objective.cost + route.update_rests()
+    }
 
-        time_difference
+    fn respects_locks(&self, solution: &Solution) -> bool {
+        let truck = if self.truck1 { TruckEnum::Truck1 } else { TruckEnum::Truck2 };
+        let route = solution.get_truck(truck).get(self.day).get(self.time_of_day);
+        let locks = &solution.locks;
+        !locks.is_frozen(truck, self.day, self.time_of_day)
+            && locks.can_change_position(*route.linked_vector.get_value_unsafe(self.shifting_node))
     }
 }
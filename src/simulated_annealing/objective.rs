@@ -0,0 +1,191 @@
+use std::ops::AddAssign;
+
+use crate::resource::{MINUTE, TRUCK_CAPACITY, Time};
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+
+/// How much a truck burns. Everything in between empty and full is estimated linearly from the load.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TruckType {
+    pub name: String,
+    pub liters_per_km_empty: f64,
+    pub liters_per_km_full: f64,
+    pub kg_co2_per_liter: f64,
+}
+
+/// How unbalanced a set of times is, in hours or hours squared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceMeasure {
+    /// Every hour away from the average counts, and big differences count a lot more
+    Variance,
+    /// Only the longest one counts
+    Max,
+}
+
+impl BalanceMeasure {
+    pub fn measure(self, times: &[Time]) -> f64 {
+        let hours: Vec<f64> = times.iter().map(|time| *time as f64 / (60 * MINUTE) as f64).collect();
+        match self {
+            BalanceMeasure::Variance => {
+                let mean = hours.iter().sum::<f64>() / hours.len() as f64;
+                hours.iter().map(|hour| (hour - mean) * (hour - mean)).sum::<f64>() / hours.len() as f64
+            }
+            BalanceMeasure::Max => hours.into_iter().fold(0.0, f64::max),
+        }
+    }
+}
+
+/// What the search minimises on top of the time: driven kilometres, fuel, CO2 and how balanced the workload is.
+/// The weights are in minutes, so they say how many minutes of driving one kilometre, litre or kilo is worth.
+/// With all weights at 0 the score is only time, like it always was.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Objective {
+    pub minutes_per_km: f64,
+    pub minutes_per_liter: f64,
+    pub minutes_per_kg_co2: f64,
+    /// Indexed by truck
+    pub truck_types: [TruckType; 2],
+    pub balance_measure: BalanceMeasure,
+    /// Minutes per hour (squared, for the variance) of imbalance between the 10 days of the trucks
+    pub minutes_per_day_balance: f64,
+    /// The same, between the week totals of the two trucks
+    pub minutes_per_truck_balance: f64,
+}
+
+/// The parts of the objective of one or more routes, in the units of the sustainability report.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Emissions {
+    pub km: f64,
+    pub liters: f64,
+    pub kg_co2: f64,
+}
+
+impl AddAssign for Emissions {
+    fn add_assign(&mut self, other: Self) {
+        self.km += other.km;
+        self.liters += other.liters;
+        self.kg_co2 += other.kg_co2;
+    }
+}
+
+impl Objective {
+    /// Nothing but time, with two of the same garbage trucks. For tests that don't care about the data files.
+    pub fn time_only() -> Self {
+        let truck_type = TruckType {
+            name: "Vuilniswagen".to_string(),
+            liters_per_km_empty: 0.45,
+            liters_per_km_full: 0.6,
+            kg_co2_per_liter: 2.64,
+        };
+        Objective {
+            minutes_per_km: 0.0,
+            minutes_per_liter: 0.0,
+            minutes_per_kg_co2: 0.0,
+            truck_types: [truck_type.clone(), truck_type],
+            balance_measure: BalanceMeasure::Variance,
+            minutes_per_day_balance: 0.0,
+            minutes_per_truck_balance: 0.0,
+        }
+    }
+
+    /// Negative weights reward driving or imbalance, then the score of a plan can be less than its time.
+    pub fn has_negative_weights(&self) -> bool {
+        [
+            self.minutes_per_km,
+            self.minutes_per_liter,
+            self.minutes_per_kg_co2,
+            self.minutes_per_day_balance,
+            self.minutes_per_truck_balance,
+        ]
+        .iter()
+        .any(|weight| *weight < 0.0)
+    }
+
+    /// False when the balance isn't in the score, then the moves can skip it.
+    pub fn balances(&self) -> bool {
+        self.minutes_per_day_balance != 0.0 || self.minutes_per_truck_balance != 0.0
+    }
+
+    /// What the balance of these day and truck times costs in the score, in centiseconds.
+    pub fn balance_cost(&self, day_times: &[Time], truck_times: &[Time]) -> Time {
+        let minutes = self.balance_measure.measure(day_times) * self.minutes_per_day_balance
+            + self.balance_measure.measure(truck_times) * self.minutes_per_truck_balance;
+        (minutes * MINUTE as f64).round() as Time
+    }
+
+    /// The fuel depends on how full the truck is on every kilometre. Walking the route for that is slow,
+    /// so the routes only do it when fuel or CO2 count for the score.
+    pub fn needs_load(&self) -> bool {
+        self.minutes_per_liter != 0.0 || self.minutes_per_kg_co2 != 0.0
+    }
+
+    /// `distance` is in metres, `load_distance` is the volume in the truck times the metres it's driven around, in litre metres.
+    pub fn emissions(&self, truck: TruckEnum, distance: u32, load_distance: u64) -> Emissions {
+        let truck_type = &self.truck_types[truck as usize];
+        let km = distance as f64 / 1000.0;
+        // the kilometres a full truck would have driven to carry the same load around
+        let full_km = load_distance as f64 / 1000.0 / TRUCK_CAPACITY as f64;
+        let liters = km * truck_type.liters_per_km_empty + full_km * (truck_type.liters_per_km_full - truck_type.liters_per_km_empty);
+        Emissions {
+            km,
+            liters,
+            kg_co2: liters * truck_type.kg_co2_per_liter,
+        }
+    }
+
+    /// What a route with this distance and load costs in the score, in centiseconds.
+    /// This is rounded per route, so a move only has to look at the routes it changes.
+    pub fn route_cost(&self, truck: TruckEnum, distance: u32, load_distance: u64) -> Time {
+        let emissions = self.emissions(truck, distance, load_distance);
+        let minutes = emissions.km * self.minutes_per_km
+            + emissions.liters * self.minutes_per_liter
+            + emissions.kg_co2 * self.minutes_per_kg_co2;
+        (minutes * MINUTE as f64).round() as Time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BalanceMeasure, Objective};
+    use crate::resource::{MINUTE, TRUCK_CAPACITY};
+    use crate::simulated_annealing::simulated_annealing::TruckEnum;
+
+    #[test]
+    fn time_only_costs_nothing() {
+        let objective = Objective::time_only();
+        assert!(!objective.needs_load());
+        assert_eq!(objective.route_cost(TruckEnum::Truck1, 50_000, 1_000_000_000), 0);
+    }
+
+    #[test]
+    fn a_full_truck_burns_more() {
+        let mut objective = Objective::time_only();
+        objective.minutes_per_liter = 1.0;
+        let empty = objective.emissions(TruckEnum::Truck2, 10_000, 0);
+        let full = objective.emissions(TruckEnum::Truck2, 10_000, 10_000 * TRUCK_CAPACITY as u64);
+        assert!((empty.liters - 4.5).abs() < 1e-9);
+        assert!((full.liters - 6.0).abs() < 1e-9);
+        assert!((full.kg_co2 - 6.0 * 2.64).abs() < 1e-9);
+        assert_eq!(objective.route_cost(TruckEnum::Truck2, 10_000, 0), (4.5 * MINUTE as f64) as i32);
+    }
+
+    #[test]
+    fn kilometres_are_weighted_in_minutes() {
+        let mut objective = Objective::time_only();
+        objective.minutes_per_km = 2.0;
+        assert_eq!(objective.route_cost(TruckEnum::Truck1, 1_500, 0), 3 * MINUTE);
+    }
+
+    #[test]
+    fn balance_is_measured_in_hours() {
+        let hour = 60 * MINUTE;
+        assert_eq!(BalanceMeasure::Max.measure(&[hour, 3 * hour, 2 * hour]), 3.0);
+        assert_eq!(BalanceMeasure::Variance.measure(&[hour, 3 * hour]), 1.0);
+        assert_eq!(BalanceMeasure::Variance.measure(&[2 * hour, 2 * hour]), 0.0);
+
+        let mut objective = Objective::time_only();
+        assert!(!objective.balances());
+        objective.minutes_per_truck_balance = 10.0;
+        assert!(objective.balances());
+        assert_eq!(objective.balance_cost(&[hour, 3 * hour], &[4 * hour, 0]), 40 * MINUTE);
+    }
+}
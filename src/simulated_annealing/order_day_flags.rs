@@ -1,8 +1,9 @@
 use super::week::DayEnum;
 use crate::get_orders;
-use crate::resource::{Frequency};
+use crate::resource::DayPattern;
 use crate::simulated_annealing::route::OrderIndex;
 use rand::Rng;
+
 #[derive(Clone)]
 pub struct OrderFlags {
     orders: Vec<u8>,
@@ -52,7 +53,7 @@ impl OrderFlags {
     ) -> Option<DayEnum> {
         let order = &get_orders()[order_index];
         let flags = self.orders[order_index] & 0b1_1111;
-        OrderFlags::_get_random_allowed_day(flags, order.frequency, rng)
+        OrderFlags::_get_random_allowed_day(flags, &order.allowed_patterns, rng)
     }
 
     pub fn get_random_day_to_shift_to<R: Rng + ?Sized>(
@@ -66,111 +67,45 @@ impl OrderFlags {
 
         debug_assert!(self.orders[order_index] & 0b1_1111 > flags); // assert that a one has been flipped to 0
 
-        OrderFlags::_get_random_allowed_day(flags, order.frequency, rng)
+        OrderFlags::_get_random_allowed_day(flags, &order.allowed_patterns, rng)
     }
 
+    /// Picks a random day that the order can get next to the days in `flags`,
+    /// so it's still on its way to one of the allowed patterns.
+    /// Returns None if every pattern with these days is already complete.
     pub fn _get_random_allowed_day<R: Rng + ?Sized>(
         flags: u8,
-        frequency: Frequency,
+        allowed_patterns: &[DayPattern],
         rng: &mut R,
     ) -> Option<DayEnum> {
-        match frequency {
-            Frequency::None => panic!(
-                "Tried to add something with frequency 0 to a route. \
-            Frequency 0 is preserved for the dropoff locations"
-            ),
-            Frequency::Once => {
-                if flags == 0 {
-                    Some(rng.random())
-                } else {
-                    None
-                }
-            }
-            Frequency::Twice => match flags {
-                0b10000 => Some(DayEnum::Thursday),
-                0b01000 => Some(DayEnum::Friday),
-                0b00010 => Some(DayEnum::Monday),
-                0b00001 => Some(DayEnum::Tuesday),
-                0b00100 => panic!("An order with frequency 2 has been put on Wednesday"),
-                0b00000 => match rng.random_range(0..4) {
-                    0 => Some(DayEnum::Monday),
-                    1 => Some(DayEnum::Tuesday),
-                    2 => Some(DayEnum::Thursday),
-                    3 => Some(DayEnum::Friday),
-                    _ => None,
-                },
-                _ => unreachable!(),
-            },
-            Frequency::Thrice => {
-                let mask = 0b10000 | 0b00100 | 0b00001;
-                let available: u8 = mask & !flags;
-                if available == 0 {
-                    return None;
-                }
-
-                // find how many are available
-                let mut count = 0;
-                for bit in &[0b10000, 0b00100, 0b00001] {
-                    if available & bit != 0 {
-                        count += 1;
-                    }
-                }
-
-                // get a random number
-                let choice_idx = rng.random_range(0..count);
-                let mut idx = choice_idx;
-                for (bit, day) in &[
-                    (0b10000, DayEnum::Monday),
-                    (0b00100, DayEnum::Wednesday),
-                    (0b00001, DayEnum::Friday),
-                ] {
-                    // iterate to the random number
-                    if available & bit != 0 {
-                        if idx == 0 {
-                            return Some(*day);
-                        }
-                        idx -= 1;
-                    }
-                }
-
-                unreachable!()
-            }
-            Frequency::FourTimes => {
-                let mask = 0b10000 | 0b01000 | 0b00100 | 0b00010 | 0b00001;
-                let available = mask & !flags;
-                if available == 0 {
-                    return None;
-                }
-
-                // count available days
-                let mut count = 0;
-                for bit in &[0b10000, 0b01000, 0b00100, 0b00010, 0b00001] {
-                    if available & bit != 0 {
-                        count += 1;
-                    }
-                }
+        let available = allowed_patterns
+            .iter()
+            .filter(|pattern| *pattern & flags == flags)
+            .fold(0, |available, pattern| available | (pattern & !flags));
+        if available == 0 {
+            return None;
+        }
 
-                // get a random day
-                let choice_idx = rng.random_range(0..count);
-                let mut idx = choice_idx;
-                for (bit, day) in &[
-                    (0b10000, DayEnum::Monday),
-                    (0b01000, DayEnum::Tuesday),
-                    (0b00100, DayEnum::Wednesday),
-                    (0b00010, DayEnum::Thursday),
-                    (0b00001, DayEnum::Friday),
-                ] {
-                    if available & bit != 0 {
-                        if idx == 0 {
-                            return Some(*day);
-                        }
-                        idx -= 1;
-                    }
+        // get a random one of the available days
+        let mut idx = rng.random_range(0..available.count_ones());
+        for day in DayEnum::ALL {
+            if available & Self::day_to_flags(day) != 0 {
+                if idx == 0 {
+                    return Some(day);
                 }
-
-                unreachable!()
+                idx -= 1;
             }
         }
+
+        unreachable!()
+    }
+    /// Checks if a complete set of days is one of the allowed patterns of an order.
+    pub fn is_allowed_pattern(flags: u8, allowed_patterns: &[DayPattern]) -> bool {
+        allowed_patterns.contains(&flags)
+    }
+    /// The days in the flags, from Monday to Friday.
+    pub fn flags_to_days(flags: u8) -> Vec<DayEnum> {
+        DayEnum::ALL.into_iter().filter(|day| flags & Self::day_to_flags(*day) != 0).collect()
     }
     pub fn day_to_flags(day: DayEnum) -> u8 {
         match day {
@@ -234,3 +169,37 @@ impl Default for OrderFlags{
         Self::new(get_orders().len())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::OrderFlags;
+    use crate::resource::Frequency;
+    use crate::simulated_annealing::week::DayEnum;
+    use rand::SeedableRng;
+    use rand::rngs::SmallRng;
+
+    #[test]
+    fn random_days_complete_an_allowed_pattern() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        // Twice a week, but the customer is closed on Monday
+        let patterns = [0b01001, 0b00101];
+        for _ in 0..100 {
+            let mut flags = 0;
+            while let Some(day) = OrderFlags::_get_random_allowed_day(flags, &patterns, &mut rng) {
+                assert_eq!(flags & OrderFlags::day_to_flags(day), 0);
+                flags |= OrderFlags::day_to_flags(day);
+            }
+            assert!(OrderFlags::is_allowed_pattern(flags, &patterns));
+        }
+    }
+
+    #[test]
+    fn days_that_fit_no_pattern_are_never_picked() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let patterns = Frequency::Twice.default_patterns();
+        assert_eq!(OrderFlags::_get_random_allowed_day(0b10000, patterns, &mut rng), Some(DayEnum::Thursday));
+        assert_eq!(OrderFlags::_get_random_allowed_day(0b00100, patterns, &mut rng), None);
+        assert_eq!(OrderFlags::_get_random_allowed_day(0b10010, patterns, &mut rng), None);
+        assert_eq!(OrderFlags::flags_to_days(0b10101), vec![DayEnum::Monday, DayEnum::Wednesday, DayEnum::Friday]);
+    }
+}
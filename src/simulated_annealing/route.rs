@@ -1,69 +1,483 @@
 use crate::datastructures::compact_linked_vector::CompactLinkedVector;
 use crate::datastructures::linked_vectors::{LVNodeIndex, LinkedVector};
-use crate::{get_orders, EXTREME_TEST_FLAG};
-use crate::resource::{HALF_HOUR, Time};
-use crate::simulated_annealing::neighbor_move::evaluation_helper::{time_between_three_nodes, time_between_two_nodes};
+use crate::{get_drivers, get_neighbor_lists, get_objective, get_orders, get_sites, get_travel_table};
+use crate::resource::{HALF_HOUR, MatrixID, TRUCK_CAPACITY, Time};
+use crate::simulated_annealing::driver::{Driver, Rests};
+use crate::simulated_annealing::neighbor_move::evaluation_helper::{calculate_capacity_overflow, distance_between_three_nodes, distance_between_two_nodes, time_between_three_nodes, time_between_two_nodes};
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use rand::Rng;
+use rand::prelude::IteratorRandom;
 
 #[derive(Debug, Clone)]
 pub struct Route {
     pub linked_vector: CompactLinkedVector<OrderIndex>,
     pub capacity: u32,
     pub time: Time,
+    /// How often the truck unloads at a dump site before the end of the route
+    pub unload_stops: u32,
+    /// The truck that drives the route, for its fuel use
+    pub truck: TruckEnum,
+    /// The metres driven, including the way back to the depot. Like the time, empty routes still have the empty trip in here.
+    pub distance: u32,
+    /// What the kilometres, fuel and CO2 of the route cost in the score, see Objective. Always 0 for an empty route.
+    pub cost: Time,
+    /// The time spent driving, including the way back to the depot. The driving time rules only look at this.
+    pub driving: Time,
+    /// The breaks the driver takes on this route, their time is in `time` too. Empty routes don't have any.
+    pub rests: Rests,
+}
+// it used to live here, most of the solver still imports it from here
+pub use crate::resource::OrderIndex;
+
+/// A route of the first truck, for the tests that were written when there was only one depot.
+#[cfg(test)]
+impl Default for Route {
+    fn default() -> Self {
+        Route::for_truck(TruckEnum::Truck1)
+    }
+}
+
+/// What an edit of a route does to the parts of the objective that aren't time.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ObjectiveDelta {
+    /// In metres, as counted for the score, so emptying a route takes away the empty trip too
+    pub distance: i32,
+    pub cost: Time,
+}
+
+/// What an edit of a route does to the breaks and the continuous driving, see Driver.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RestsDelta {
+    pub break_time: Time,
+    pub continuous_overflow: Time,
 }
-pub type OrderIndex = usize;
 
 impl Route {
-    /// Construct an empty route
-    /// We should maybe add the dropoff location as the first and last element of this list?
-    pub fn new() -> Self {
+    /// Construct an empty route of the truck, that starts at the depot and unloads at the dump site.
+    pub fn new(truck: TruckEnum, depot: OrderIndex, dump_site: OrderIndex) -> Self {
         let mut route: CompactLinkedVector<OrderIndex> = CompactLinkedVector::new();
-        route.push_back(get_orders().len() - 1);
-        route.push_back(get_orders().len() - 1);
-        Route {
+        route.push_back(depot);
+        route.push_back(dump_site);
+        let mut route = Route {
             linked_vector: route,
             capacity: 0,
-            time: HALF_HOUR,
+            time: 0,
+            unload_stops: 0,
+            truck,
+            distance: 0,
+            cost: 0,
+            driving: 0,
+            rests: Rests::default(),
+        };
+        route.time = route.empty_time();
+        route.distance = route.calculate_distance();
+        route.driving = route.calculate_driving();
+        route
+    }
+
+    /// An empty route of the truck, from its home depot to the dump site closest to it.
+    pub fn for_truck(truck: TruckEnum) -> Self {
+        let sites = get_sites();
+        Self::new(truck, sites.home_depot(truck), sites.default_dump_site(truck))
+    }
+
+    /// The rules for the driver of the truck of this route
+    pub fn driver(&self) -> &'static Driver {
+        &get_drivers()[self.truck as usize]
+    }
+
+    pub fn depot(&self) -> OrderIndex {
+        *self.linked_vector.get_value_unsafe(self.linked_vector.get_head_index().unwrap())
+    }
+
+    pub fn dump_site(&self) -> OrderIndex {
+        *self.linked_vector.get_value_unsafe(self.linked_vector.get_tail_index().unwrap())
+    }
+
+    /// What the end of a trip takes: the 30 minutes at the dump site, and driving from there back to the depot.
+    /// This is the same for every route that unloads at the same dump site, no matter what's in it.
+    pub fn dropoff_time(&self) -> Time {
+        let orders = get_orders();
+        HALF_HOUR + time_between_two_nodes(orders[self.dump_site()].matrix_id, orders[self.depot()].matrix_id)
+    }
+
+    /// The time of the route without any customers, so straight from the depot to the dump site and back.
+    /// Empty routes don't count for the score, so the first customer in a route adds this on top of its detour.
+    fn empty_time(&self) -> Time {
+        let orders = get_orders();
+        time_between_two_nodes(orders[self.depot()].matrix_id, orders[self.dump_site()].matrix_id) + self.dropoff_time()
+    }
+
+    /// How much longer the route gets when it unloads at another dump site.
+    /// An empty route doesn't count for the score, so that's always 0 for them.
+    pub fn calculate_change_dump_site(&self, dump_site: OrderIndex) -> Time {
+        if self.is_empty() {
+            return 0;
         }
+        self.dump_site_time_diff(dump_site)
     }
 
-    pub fn check_correctness_trash(&self) {
+    pub fn apply_change_dump_site(&mut self, dump_site: OrderIndex) -> Time {
+        let time_diff = self.dump_site_time_diff(dump_site);
+        self.time += time_diff;
+        self.driving += time_diff;
+        self.distance = (self.distance as i32 + self.dump_site_distance_diff(dump_site)) as u32;
+        let tail = self.linked_vector.get_tail_index().unwrap();
+        self.linked_vector.set_value_at_index(tail, dump_site);
+        let cost_change = self.update_cost();
+        let break_change = self.update_rests();
+        if self.is_empty() { cost_change } else { time_diff + cost_change + break_change }
+    }
+
+    /// The change in distance and cost when the route unloads at another dump site.
+    pub fn calculate_objective_change_dump_site(&self, dump_site: OrderIndex) -> ObjectiveDelta {
+        self.objective_delta(self.dump_site_distance_diff(dump_site), self.is_empty(), || {
+            self.load_distance_of(&self.edited_orders(None, None, dump_site))
+        })
+    }
+
+    fn dump_site_distance_diff(&self, dump_site: OrderIndex) -> i32 {
         let orders = get_orders();
-        assert_eq!(
-            self.linked_vector
-                .iter()
-                .map(|(_, matrix_id)| orders[*matrix_id].container_volume as u64)
-                .sum::<u64>(),
-            0u64,
-            "The currently stored trash volume is incorrect"
-        )
+        let lv = &self.linked_vector;
+        let last = orders[*lv.get_prev_value_unsafe(lv.get_tail_index().unwrap())].matrix_id;
+        let depot = orders[self.depot()].matrix_id;
+        let (old, new) = (orders[self.dump_site()].matrix_id, orders[dump_site].matrix_id);
+
+        distance_between_three_nodes(last, new, depot) - distance_between_three_nodes(last, old, depot)
+    }
+
+    /// The change in distance and cost when `remove` is taken out of the route and the order is put in after the node of `insert`,
+    /// like calculate_capacity_overflow_delta.
+    pub fn calculate_objective_delta(&self, remove: Option<LVNodeIndex>, insert: Option<(LVNodeIndex, OrderIndex)>) -> ObjectiveDelta {
+        let distance_diff = self.leg_diff(remove, insert, distance_between_two_nodes);
+        self.objective_delta(distance_diff, self.empty_after(remove, insert), || {
+            self.load_distance_of(&self.edited_orders(remove, insert, self.dump_site()))
+        })
+    }
+
+    /// How much the sum of `between` over the legs of the route changes with the edit, like calculate_objective_delta.
+    /// `remove` can't be right after the node of `insert`, the changes of both are counted as if the other doesn't happen.
+    fn leg_diff(&self, remove: Option<LVNodeIndex>, insert: Option<(LVNodeIndex, OrderIndex)>, between: impl Fn(MatrixID, MatrixID) -> i32) -> i32 {
+        let orders = get_orders();
+        let lv = &self.linked_vector;
+        let mut diff = 0;
+        if let Some(node) = remove {
+            let prev = orders[*lv.get_prev_value_unsafe(node)].matrix_id;
+            let middle = orders[*lv.get_value_unsafe(node)].matrix_id;
+            let next = orders[*lv.get_next_value_unsafe(node)].matrix_id;
+            diff += between(prev, next) - between(prev, middle) - between(middle, next);
+        }
+        if let Some((after, order_index)) = insert {
+            let prev = orders[*lv.get_value_unsafe(after)].matrix_id;
+            let middle = orders[order_index].matrix_id;
+            let next = orders[*lv.get_next_value_unsafe(after)].matrix_id;
+            diff += between(prev, middle) + between(middle, next) - between(prev, next);
+        }
+        diff
+    }
+
+    fn empty_after(&self, remove: Option<LVNodeIndex>, insert: Option<(LVNodeIndex, OrderIndex)>) -> bool {
+        self.linked_vector.len() + insert.is_some() as usize - remove.is_some() as usize == 2
+    }
+
+    /// How much the driving changes with the edit, like calculate_objective_delta.
+    pub fn calculate_driving_delta(&self, remove: Option<LVNodeIndex>, insert: Option<(LVNodeIndex, OrderIndex)>) -> Time {
+        self.leg_diff(remove, insert, time_between_two_nodes)
+    }
+
+    /// The change in break time and continuous driving overflow when `remove` is taken out of the route
+    /// and the order is put in after the node of `insert`, like calculate_objective_delta.
+    pub fn calculate_rests_delta(&self, remove: Option<LVNodeIndex>, insert: Option<(LVNodeIndex, OrderIndex)>) -> RestsDelta {
+        let driving_after = self.driving + self.calculate_driving_delta(remove, insert);
+        self.rests_delta(driving_after, self.empty_after(remove, insert), || {
+            self.edited_orders(remove, insert, self.dump_site())
+        })
+    }
+
+    /// The change in break time and continuous driving overflow when the route unloads at another dump site.
+    pub fn calculate_rests_change_dump_site(&self, dump_site: OrderIndex) -> RestsDelta {
+        let driving_after = self.driving + self.dump_site_time_diff(dump_site);
+        self.rests_delta(driving_after, self.is_empty(), || self.edited_orders(None, None, dump_site))
+    }
+
+    /// `orders_after` is only called when the route drives long enough to need a break, because it walks the whole route.
+    fn rests_delta(&self, driving_after: Time, empty_after: bool, orders_after: impl FnOnce() -> Vec<OrderIndex>) -> RestsDelta {
+        let driver = self.driver();
+        let rests = if empty_after { Rests::default() } else { self.rests_of(driving_after, orders_after) };
+        RestsDelta {
+            break_time: driver.break_time(rests) - driver.break_time(self.rests),
+            continuous_overflow: rests.continuous_overflow - self.rests.continuous_overflow,
+        }
+    }
+
+    /// Most routes don't drive long enough for any of the rules, those don't have to be walked.
+    fn rests_of(&self, driving: Time, orders: impl FnOnce() -> Vec<OrderIndex>) -> Rests {
+        let driver = self.driver();
+        if driving <= driver.driving_without_rests() {
+            return Rests::default();
+        }
+        driver.rests(&orders())
+    }
+
+    /// Sets the rests to what the stored driving and the current orders need, and returns how much longer the breaks got.
+    /// The time changes along with them.
+    pub fn update_rests(&mut self) -> Time {
+        let rests = if self.is_empty() {
+            Rests::default()
+        } else {
+            self.rests_of(self.driving, || self.edited_orders(None, None, self.dump_site()))
+        };
+        let driver = self.driver();
+        let change = driver.break_time(rests) - driver.break_time(self.rests);
+        self.rests = rests;
+        self.time += change;
+        change
+    }
+
+    /// The breaks and continuous driving of the route, walked from scratch.
+    pub fn calculate_rests(&self) -> Rests {
+        if self.is_empty() {
+            return Rests::default();
+        }
+        self.driver().rests(&self.edited_orders(None, None, self.dump_site()))
+    }
+
+    /// The time spent driving, from the depot past every order and back from the dump site.
+    pub fn calculate_driving(&self) -> Time {
+        let orders = get_orders();
+        let lv = &self.linked_vector;
+        let driven: Time = lv
+            .iter()
+            .map_windows(|[(_, from), (_, to)]| time_between_two_nodes(orders[**from].matrix_id, orders[**to].matrix_id))
+            .sum();
+        driven + time_between_two_nodes(orders[self.dump_site()].matrix_id, orders[self.depot()].matrix_id)
+    }
+
+    /// `load_distance_after` is only called when the objective needs it, because it walks the whole route.
+    fn objective_delta(&self, distance_diff: i32, empty_after: bool, load_distance_after: impl FnOnce() -> u64) -> ObjectiveDelta {
+        let objective = get_objective();
+        let counted_before = if self.is_empty() { 0 } else { self.distance as i32 };
+        if empty_after {
+            return ObjectiveDelta { distance: -counted_before, cost: -self.cost };
+        }
+        let distance_after = self.distance as i32 + distance_diff;
+        let load_distance = if objective.needs_load() { load_distance_after() } else { 0 };
+        ObjectiveDelta {
+            distance: distance_after - counted_before,
+            cost: objective.route_cost(self.truck, distance_after as u32, load_distance) - self.cost,
+        }
+    }
+
+    /// Sets the cost to what the stored distance and the current orders cost, and returns how much it changed.
+    fn update_cost(&mut self) -> Time {
+        let cost = self.calculate_cost();
+        let change = cost - self.cost;
+        self.cost = cost;
+        change
+    }
+
+    /// The orders of the route after an edit, with the given dump site at the end.
+    fn edited_orders(&self, remove: Option<LVNodeIndex>, insert: Option<(LVNodeIndex, OrderIndex)>, dump_site: OrderIndex) -> Vec<OrderIndex> {
+        let lv = &self.linked_vector;
+        let mut edited = Vec::with_capacity(lv.len() + 1);
+        for (node_i, order_i) in lv.iter() {
+            if lv.get_tail_index() == Some(node_i) {
+                edited.push(dump_site);
+            } else if remove != Some(node_i) {
+                edited.push(*order_i);
+            }
+            if let Some((after, order_index)) = insert
+                && after == node_i
+            {
+                edited.push(order_index);
+            }
+        }
+        edited
+    }
+
+    /// The volume in the truck times the metres it's carried, summed over the route.
+    /// The truck is empty after every dump site, so the way back to the depot doesn't count.
+    fn load_distance_of(&self, route: &[OrderIndex]) -> u64 {
+        let orders = get_orders();
+        let sites = get_sites();
+        let travel_table = get_travel_table();
+
+        let mut load: u64 = 0;
+        let mut load_distance = 0;
+        for pair in route.windows(2) {
+            let (from, to) = (&orders[pair[0]], &orders[pair[1]]);
+            load_distance += load * travel_table.distance(from.matrix_id, to.matrix_id) as u64;
+            load = if sites.is_site(pair[1]) { 0 } else { load + to.total_container_volume as u64 };
+        }
+        load_distance
     }
 
+    pub fn calculate_load_distance(&self) -> u64 {
+        self.load_distance_of(&self.edited_orders(None, None, self.dump_site()))
+    }
+
+    /// The metres driven, from the depot past every order and back from the dump site.
+    pub fn calculate_distance(&self) -> u32 {
+        let orders = get_orders();
+        let lv = &self.linked_vector;
+        let driven: u32 = lv
+            .iter()
+            .map_windows(|[(_, from), (_, to)]| distance_between_two_nodes(orders[**from].matrix_id, orders[**to].matrix_id) as u32)
+            .sum();
+        driven + distance_between_two_nodes(orders[self.dump_site()].matrix_id, orders[self.depot()].matrix_id) as u32
+    }
+
+    /// What the route costs for the objective, from the stored distance.
+    pub fn calculate_cost(&self) -> Time {
+        if self.is_empty() {
+            return 0;
+        }
+        let objective = get_objective();
+        let load_distance = if objective.needs_load() { self.calculate_load_distance() } else { 0 };
+        objective.route_cost(self.truck, self.distance, load_distance)
+    }
+
+    /// Checks if the stored distance and cost are what the route drives and costs.
+    pub fn check_correctness_distance(&self) -> Result<(), String> {
+        let calculated_distance = self.calculate_distance();
+        if self.distance != calculated_distance {
+            return Err(format!("stored distance {}m but the route drives {}m", self.distance, calculated_distance));
+        }
+        let calculated_cost = self.calculate_cost();
+        if self.cost != calculated_cost {
+            return Err(format!("stored cost {} but the distance and load cost {}", self.cost, calculated_cost));
+        }
+        Ok(())
+    }
+
+    fn dump_site_time_diff(&self, dump_site: OrderIndex) -> Time {
+        let orders = get_orders();
+        let lv = &self.linked_vector;
+        let last = orders[*lv.get_prev_value_unsafe(lv.get_tail_index().unwrap())].matrix_id;
+        let depot = orders[self.depot()].matrix_id;
+        let (old, new) = (orders[self.dump_site()].matrix_id, orders[dump_site].matrix_id);
+
+        time_between_three_nodes(last, new, depot) - time_between_three_nodes(last, old, depot)
+    }
+
+    /// Checks if the stored capacity is the same as the volume of all orders in the route,
+    /// and if the unload stops are counted right.
+    pub fn check_correctness_trash(&self) -> Result<(), String> {
+        let calculated_capacity = self.calculate_capacity();
+        if self.capacity != calculated_capacity {
+            return Err(format!(
+                "stored capacity {} but the route holds {}",
+                self.capacity, calculated_capacity
+            ));
+        }
+        let calculated_unload_stops = self.calculate_unload_stops();
+        if self.unload_stops != calculated_unload_stops {
+            return Err(format!(
+                "stored {} unload stops but the route has {}",
+                self.unload_stops, calculated_unload_stops
+            ));
+        }
+        Ok(())
+    }
+
+    /// Counts the dump sites between the head and the tail.
+    pub fn calculate_unload_stops(&self) -> u32 {
+        let sites = get_sites();
+        let lv = &self.linked_vector;
+        lv.iter()
+            .filter(|(node_i, order_i)| {
+                sites.is_site(**order_i) && lv.get_head_index() != Some(*node_i) && lv.get_tail_index() != Some(*node_i)
+            })
+            .count() as u32
+    }
+
+    /// How much more than a truckload gets collected between two visits to a dump site, summed over the route.
+    pub fn capacity_overflow(&self) -> u32 {
+        self.capacity_overflow_after(None, None)
+    }
+
+    /// How much the capacity overflow changes when `remove` is taken out of the route
+    /// and the order is put in after the node of `insert`. Both are optional, so this works for adding, removing and shifting.
+    ///
+    /// Without unload stops the whole route is one load, which is quick to check.
+    /// Otherwise the loads between the dump sites are counted again.
+    pub fn calculate_capacity_overflow_delta(&self, remove: Option<LVNodeIndex>, insert: Option<(LVNodeIndex, OrderIndex)>) -> i32 {
+        let orders = get_orders();
+        let sites = get_sites();
+        let lv = &self.linked_vector;
+        let removed = remove.map(|node| *lv.get_value_unsafe(node));
+        let inserted = insert.map(|(_, order_index)| order_index);
+
+        if self.unload_stops == 0 && !removed.into_iter().chain(inserted).any(|order_index| sites.is_site(order_index)) {
+            let volume = |order_index: Option<OrderIndex>| order_index.map_or(0, |order_index| orders[order_index].total_container_volume as i32);
+            return calculate_capacity_overflow(volume(inserted) - volume(removed), self.capacity as i32);
+        }
+        self.capacity_overflow_after(remove, insert) as i32 - self.capacity_overflow() as i32
+    }
+
+    fn capacity_overflow_after(&self, remove: Option<LVNodeIndex>, insert: Option<(LVNodeIndex, OrderIndex)>) -> u32 {
+        let orders = get_orders();
+        let sites = get_sites();
+        let lv = &self.linked_vector;
+
+        let mut overflow = 0;
+        let mut load: u32 = 0;
+        let mut visit = |order_index: OrderIndex| {
+            if sites.is_site(order_index) {
+                overflow += load.saturating_sub(TRUCK_CAPACITY);
+                load = 0;
+            } else {
+                load += orders[order_index].total_container_volume;
+            }
+        };
+        for (node_i, order_i) in lv.iter() {
+            // the truck starts empty, even when the depot is a dump site too
+            if lv.get_head_index() != Some(node_i) && remove != Some(node_i) {
+                visit(*order_i);
+            }
+            if let Some((after, order_index)) = insert
+                && after == node_i
+            {
+                visit(order_index);
+            }
+        }
+        overflow
+    }
+
+    /// Also recalculates the distance, cost, driving and rests, which go along with the time.
     pub fn recalculate_total_time(&mut self) {
+        self.driving = self.calculate_driving();
+        self.rests = self.calculate_rests();
         self.time = self.calculate_time();
+        self.distance = self.calculate_distance();
+        self.cost = self.calculate_cost();
     }
-    pub fn check_correctness_time(&self) -> bool {
-        if !EXTREME_TEST_FLAG{
-            return true
-        }
+    /// Checks if the stored time is the same as the time it actually takes to drive the route.
+    pub fn check_correctness_time(&self) -> Result<(), String> {
         let calculated_time = self.calculate_time();
-        let difference = self.time - calculated_time;
-        if difference > 1 {
-            if self.linked_vector.len() == 2 && calculated_time == HALF_HOUR {
-                return true;
-            }
-            println!("found inconsistency");
-            println!("route length: {}", self.linked_vector.len());
-            println!("stored time: {}", self.time);
-            println!("actual time: {}", calculated_time);
-            return false;
+        if self.time != calculated_time {
+            return Err(format!(
+                "stored time {} but the route takes {} (route length: {})",
+                self.time,
+                calculated_time,
+                self.linked_vector.len()
+            ));
+        }
+        let calculated_driving = self.calculate_driving();
+        if self.driving != calculated_driving {
+            return Err(format!("stored driving {} but the route drives {}", self.driving, calculated_driving));
         }
-        true
+        let calculated_rests = self.calculate_rests();
+        if self.rests != calculated_rests {
+            return Err(format!("stored rests {:?} but the route needs {:?}", self.rests, calculated_rests));
+        }
+        Ok(())
     }
 
-    /// This function calculates how much time the route takes.
-    /// It always adds the 30 minutes dropoff time at the end of the route, even if it doesn't have to.
+    /// This function calculates how much time the route takes, breaks included.
+    /// It always adds the dropoff time at the end of the route, even if it doesn't have to.
     /// This is to stay consistent with how we store the Time value in the route.
-    fn calculate_time(&self) -> Time {
+    pub fn calculate_time(&self) -> Time {
         let orders = get_orders();
         let mut time_travel = 0;
         let lv = &self.linked_vector;
@@ -75,7 +489,9 @@ impl Route {
             let next_matrix_i = orders[*lv.get_next_value(node_i).unwrap()].matrix_id;
 
             time_travel += time_between_two_nodes(matrix_i, next_matrix_i);
-            time_travel += orders[*order_i].emptying_time;
+            if lv.get_head_index() != Some(node_i) {
+                time_travel += orders[*order_i].service_time();
+            }
 
             // let prev_order_i = lv.get_prev_value(node_i).unwrap();
             //
@@ -86,9 +502,18 @@ impl Route {
             // time_travel += orders[*order_i].emptying_time;
         }
 
-        // Add the 30 minutes for the dropoff
-        time_travel += HALF_HOUR;
-        time_travel
+        // Add the 30 minutes for the dropoff, and the way back to the depot
+        time_travel += self.dropoff_time();
+        time_travel + self.driver().break_time(self.calculate_rests())
+    }
+
+    /// Sums the container volume of every order in the route, which is what `capacity` should be.
+    pub fn calculate_capacity(&self) -> u32 {
+        let orders = get_orders();
+        self.linked_vector
+            .iter()
+            .map(|(_, order_i)| orders[*order_i].total_container_volume)
+            .sum()
     }
     pub fn calculate_remove_node(&self, node: LVNodeIndex) -> Time{
         let orders = get_orders();
@@ -102,10 +527,10 @@ impl Route {
         let time_diff =
             - time_between_three_nodes(prev, middle, next)
             + time_between_two_nodes(prev, next)
-            - order.emptying_time;
+            - order.service_time();
 
         if lv.len() == 3 {
-            time_diff - HALF_HOUR
+            time_diff - self.empty_time()
         } else {
             time_diff
         }
@@ -122,17 +547,22 @@ impl Route {
         let time_diff =
             - time_between_three_nodes(prev, middle, next)
             + time_between_two_nodes(prev, next)
-            - order.emptying_time;
+            - order.service_time();
 
         self.time += time_diff;
+        self.driving += time_between_two_nodes(prev, next) - time_between_three_nodes(prev, middle, next);
+        self.distance = (self.distance as i32 + distance_between_two_nodes(prev, next) - distance_between_three_nodes(prev, middle, next)) as u32;
         self.capacity -= order.total_container_volume;
+        self.unload_stops -= get_sites().is_site(*lv.get_value_unsafe(node)) as u32;
         lv.remove(node);
         lv.compact();
 
-        if lv.len() == 2 {
-            time_diff - HALF_HOUR
+        let cost_change = self.update_cost();
+        let break_change = self.update_rests();
+        if self.linked_vector.len() == 2 {
+            time_diff - self.empty_time() + cost_change + break_change
         } else {
-            time_diff
+            time_diff + cost_change + break_change
         }
     }
     /// a special function for the FIXPLZPLZPLZPLZPLZPLZPLZ function.
@@ -148,16 +578,19 @@ impl Route {
         let time_diff =
             - time_between_three_nodes(prev, middle, next)
             + time_between_two_nodes(prev, next)
-            - order.emptying_time;
+            - order.service_time();
 
         self.time += time_diff;
+        self.driving += time_between_two_nodes(prev, next) - time_between_three_nodes(prev, middle, next);
+        self.distance = (self.distance as i32 + distance_between_two_nodes(prev, next) - distance_between_three_nodes(prev, middle, next)) as u32;
         self.capacity -= order.total_container_volume;
+        self.unload_stops -= get_sites().is_site(*lv.get_value_unsafe(node)) as u32;
         lv.remove(node);
-        time_diff
+        time_diff + self.update_cost() + self.update_rests()
     }
     /// Calculates the time it takes to add an order after the given node.
     ///
-    /// Adds half an hour to the time if the route is empty
+    /// Adds the time of the empty trip if the route is empty
     ///
     /// Does not add emptying
     pub fn calculate_add_order(&self, insert_after_this: LVNodeIndex, order_to_insert: OrderIndex) -> Time {
@@ -174,9 +607,9 @@ impl Route {
             - time_between_two_nodes(prev, next);
 
         if lv.len() == 2 {
-            time_diff + order.emptying_time + HALF_HOUR
+            time_diff + order.service_time() + self.empty_time()
         } else {
-            time_diff + order.emptying_time
+            time_diff + order.service_time()
         }
     }
     pub fn apply_add_order(&mut self, insert_after_this: LVNodeIndex, order_index: OrderIndex) -> Time {
@@ -192,40 +625,77 @@ impl Route {
         let time_diff =
               time_between_three_nodes(prev, middle, next)
             - time_between_two_nodes(prev, next)
-            + order.emptying_time;
+            + order.service_time();
 
         self.time += time_diff;
+        self.driving += time_between_three_nodes(prev, middle, next) - time_between_two_nodes(prev, next);
+        self.distance = (self.distance as i32 + distance_between_three_nodes(prev, middle, next) - distance_between_two_nodes(prev, next)) as u32;
         self.capacity += order.total_container_volume;
+        self.unload_stops += get_sites().is_site(order_index) as u32;
         lv.insert_after(insert_after_this, order_index);
 
-        if lv.len() == 3 {
-            time_diff + HALF_HOUR
+        let cost_change = self.update_cost();
+        let break_change = self.update_rests();
+        if self.linked_vector.len() == 3 {
+            time_diff + self.empty_time() + cost_change + break_change
         } else {
-            time_diff
+            time_diff + cost_change + break_change
         }
     }
     pub fn is_empty(&self) -> bool {
         self.linked_vector.len() == 2
     }
-}
 
-impl Default for Route {
-    fn default() -> Self {
-        Self::new()
+    /// Returns all nodes after which the given order can be inserted,
+    /// so that it ends up right next to one of its neighbours (see NeighborLists).
+    ///
+    /// The tail is never returned, because we can't add anything behind it.
+    /// This is called for every granular move, so the positions are found lazily instead of collected.
+    pub fn get_neighbor_positions(&self, order_to_insert: OrderIndex) -> impl Iterator<Item = LVNodeIndex> + '_ {
+        let orders = get_orders();
+        let neighbor_lists = get_neighbor_lists();
+        let lv = &self.linked_vector;
+        let matrix_id = orders[order_to_insert].matrix_id;
+
+        lv.iter()
+            .filter(move |(node_i, _)| lv.get_tail_index() != Some(*node_i))
+            .filter(move |(node_i, order_i)| {
+                neighbor_lists.is_neighbor(matrix_id, orders[**order_i].matrix_id)
+                    || neighbor_lists.is_neighbor(matrix_id, orders[*lv.get_next_value_unsafe(*node_i)].matrix_id)
+            })
+            .map(|(node_i, _)| node_i)
+    }
+
+    /// Returns the node after which inserting the order takes the least extra time, and that extra time.
+    pub fn get_cheapest_position(&self, order_to_insert: OrderIndex) -> (LVNodeIndex, Time) {
+        let lv = &self.linked_vector;
+        lv.iter()
+            .filter(|(node_i, _)| lv.get_tail_index() != Some(*node_i))
+            .map(|(node_i, _)| (node_i, self.calculate_add_order(node_i, order_to_insert)))
+            .min_by_key(|(_, time_diff)| *time_diff)
+            .expect("a route always has a head to insert after")
+    }
+
+    /// Gets a random node after which the given order can be inserted next to one of its neighbours.
+    pub fn get_random_neighbor_position<R: Rng + ?Sized>(&self, order_to_insert: OrderIndex, rng: &mut R) -> Option<LVNodeIndex> {
+        self.get_neighbor_positions(order_to_insert).choose(rng)
     }
 }
 
 
+
 #[cfg(test)]
 use test_env_helpers::*;
 
 #[before_all]
 #[cfg(test)]
 mod tests {
-    use crate::{get_distance_matrix, get_orders, DISTANCE_MATRIX, ORDERS};
+    use crate::{get_distance_matrix, get_orders, get_sites, get_travel_table, DISTANCE_MATRIX, ORDERS, SITES, TRAVEL_TABLE};
+    use crate::datastructures::linked_vectors::LinkedVector;
     use crate::parser::{parse_distance_matrix, parse_orderfile};
-    use crate::resource::{Company, Frequency};
+    use crate::resource::{Company, Frequency, Sites, TRUCK_CAPACITY, TravelTable};
     use crate::simulated_annealing::route::Route;
+    use crate::simulated_annealing::simulated_annealing::TruckEnum;
 
     fn before_all(){
         // We make most of the frequencies 0 to make the penalty score a lot lower.
@@ -234,11 +704,14 @@ mod tests {
         for (i, order) in order_vec.iter_mut().enumerate() {
             if i > 3 {
                 order.frequency = Frequency::None;
+                order.allowed_patterns.clear();
             }
         }
         ORDERS.set(order_vec.into()).ok();
         let distance_matrix = parse_distance_matrix().unwrap();
         DISTANCE_MATRIX.set(distance_matrix).ok();
+        TRAVEL_TABLE.set(TravelTable::new(get_distance_matrix(), get_orders())).ok();
+        SITES.set(Sites::new(get_orders(), get_travel_table()).unwrap()).ok();
     }
 
     #[test]
@@ -275,4 +748,92 @@ mod tests {
         route.recalculate_total_time();
         assert_eq!(before_time, route.time);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn changing_the_dump_site_keeps_the_time_right(){
+        // Any location will do for the bookkeeping, it doesn't have to be a real dump site
+        let dropoff = get_orders().len() - 1;
+        let route = &mut Route::new(TruckEnum::Truck1, dropoff, 2);
+        route.apply_add_order(0, 0);
+        assert_eq!(route.calculate_time(), route.time);
+
+        let expected = route.calculate_change_dump_site(dropoff);
+        assert_eq!(route.apply_change_dump_site(dropoff), expected);
+        assert_eq!(route.dump_site(), dropoff);
+        assert_eq!(route.calculate_time(), route.time);
+
+        // Empty routes don't count for the score
+        let empty = &mut Route::new(TruckEnum::Truck1, dropoff, 2);
+        assert_eq!(empty.calculate_change_dump_site(dropoff), 0);
+        assert_eq!(empty.apply_change_dump_site(dropoff), 0);
+        assert_eq!(empty.calculate_time(), empty.time);
+    }
+
+    #[test]
+    fn unload_stops_split_the_load(){
+        let dump_site = get_sites().default_dump_site(TruckEnum::Truck1);
+        let route = &mut Route::for_truck(TruckEnum::Truck1);
+        let mut order_index = 0;
+        while route.capacity <= TRUCK_CAPACITY {
+            let before_tail = route.linked_vector.get_prev_index(route.linked_vector.get_tail_index().unwrap()).unwrap();
+            route.apply_add_order(before_tail, order_index);
+            order_index += 1;
+        }
+        let overflow = route.capacity_overflow();
+        assert_eq!(overflow, route.capacity - TRUCK_CAPACITY);
+
+        // unload after the customer where the load goes over
+        let mut load = 0;
+        let (unload_after, _) = route.linked_vector.iter().skip(1).find(|(_, order_i)| {
+            load += get_orders()[**order_i].total_container_volume;
+            load > TRUCK_CAPACITY / 2
+        }).unwrap();
+        // the route is long enough for breaks, unloading halfway can change them
+        let expected_time = route.calculate_add_order(unload_after, dump_site)
+            + route.calculate_rests_delta(None, Some((unload_after, dump_site))).break_time;
+        let expected_overflow = route.calculate_capacity_overflow_delta(None, Some((unload_after, dump_site)));
+        assert_eq!(route.apply_add_order(unload_after, dump_site), expected_time);
+        assert_eq!(route.capacity_overflow() as i32, overflow as i32 + expected_overflow);
+        assert!(route.capacity_overflow() < overflow);
+        assert_eq!(route.unload_stops, 1);
+        assert_eq!(route.calculate_time(), route.time);
+        route.check_correctness_trash().unwrap();
+
+        // and take it out again
+        let stop = route.linked_vector.get_next_index(unload_after).unwrap();
+        let expected_time = route.calculate_remove_node(stop) + route.calculate_rests_delta(Some(stop), None).break_time;
+        let expected_overflow = route.calculate_capacity_overflow_delta(Some(stop), None);
+        let overflow_with_stop = route.capacity_overflow() as i32;
+        assert_eq!(route.apply_remove_node(stop), expected_time);
+        assert_eq!(route.capacity_overflow() as i32, overflow_with_stop + expected_overflow);
+        assert_eq!(route.capacity_overflow(), overflow);
+        assert_eq!(route.unload_stops, 0);
+        assert_eq!(route.calculate_time(), route.time);
+    }
+
+    #[test]
+    fn long_routes_take_breaks(){
+        let route = &mut Route::for_truck(TruckEnum::Truck1);
+        let driver = route.driver().clone();
+        let mut order_index = 0;
+        while route.rests.breaks < 2 {
+            let before_tail = route.linked_vector.get_prev_index(route.linked_vector.get_tail_index().unwrap()).unwrap();
+            let expected = route.calculate_add_order(before_tail, order_index)
+                + route.calculate_rests_delta(None, Some((before_tail, order_index))).break_time;
+            assert_eq!(route.apply_add_order(before_tail, order_index), expected);
+            route.check_correctness_time().unwrap();
+            order_index += 1;
+        }
+        assert!(route.driving > driver.break_after);
+        assert_eq!(route.time, route.calculate_time());
+
+        // and they go away again when the route gets shorter
+        while !route.is_empty() {
+            let first = route.linked_vector.get_next_index(route.linked_vector.get_head_index().unwrap()).unwrap();
+            let expected = route.calculate_remove_node(first) + route.calculate_rests_delta(Some(first), None).break_time;
+            assert_eq!(route.apply_remove_node(first), expected);
+            route.check_correctness_time().unwrap();
+        }
+        assert_eq!(route.rests.breaks, 0);
+    }
+}
@@ -1,51 +1,68 @@
 use crate::datastructures::compact_linked_vector::CompactLinkedVector;
-use crate::get_orders;
-use crate::resource::Time;
+use crate::datastructures::linked_vectors::LinkedVector;
+use crate::{get_objective, get_orders, get_sites};
+use crate::resource::{Company, FULL_DAY, HALF_HOUR, Time};
+use crate::simulated_annealing::balance::DayTimes;
+use crate::simulated_annealing::day::{Day, TimeOfDay};
+use crate::simulated_annealing::neighbor_move::evaluation_helper::time_between_two_nodes;
+use crate::simulated_annealing::locks::Locks;
+use crate::simulated_annealing::objective::Emissions;
 use crate::simulated_annealing::order_day_flags::OrderFlags;
 use crate::simulated_annealing::route::Route;
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
 use crate::simulated_annealing::solution::Solution;
+use crate::simulated_annealing::week::{DayEnum, Week};
 
 pub fn calculate_score(solution: &Solution, order_flags: &OrderFlags) -> Time {
-    let truck1 = &solution.truck1;
-    let truck2 = &solution.truck2;
-    let orders = get_orders();
-    let mut order_count: Vec<usize> = vec![0; orders.len()];
+    let total_time = solution.truck1.get_total_time() + solution.truck2.get_total_time();
+    total_time + calculate_objective_cost(solution) + calculate_balance_cost(solution) + calculate_penalty(solution, order_flags)
+}
 
-    for route in truck1.iter() {
-        add_orders(route, &mut order_count);
-    }
-    for route in truck2.iter() {
-        add_orders(route, &mut order_count);
-    }
+/// What the workload balance costs, from the times stored in the routes. 0 when balancing is off.
+pub fn calculate_balance_cost(solution: &Solution) -> Time {
+    DayTimes::of(&solution.truck1, &solution.truck2).balance_cost()
+}
 
-    order_count.pop(); // remove dropoff
+/// What the kilometres, fuel and CO2 of all routes cost, from the costs stored in them.
+pub fn calculate_objective_cost(solution: &Solution) -> Time {
+    solution.truck1.iter().chain(solution.truck2.iter()).map(|route| route.cost).sum()
+}
+
+/// The penalty for all orders that are not (completely) done.
+/// This is the part of the score that isn't driving time.
+pub fn calculate_penalty(solution: &Solution, order_flags: &OrderFlags) -> Time {
+    let orders = get_orders();
+    let order_count = count_orders(&solution.truck1, &solution.truck2);
 
-    
     #[cfg(debug_assertions)]
     for (counted_orders, saved_order_count) in order_count.iter().zip(order_flags.get_counts()){
         assert_eq!(*counted_orders as u32, saved_order_count);
     }
-    
 
-    let unfinished_orders: Vec<usize> = order_count
+    let penalty: Time = order_count
         .iter()
         .enumerate()
         .filter(|(order_i, count)| orders[*order_i].frequency as usize != **count)
-        .map(|(order_i, _)| order_i)
-        .collect();
-
-    let penalty: Time = unfinished_orders
-        .iter()
-        .map(|order_i| {
-            orders[*order_i].frequency as usize as Time * orders[*order_i].emptying_time * 3 as Time
-        })
+        .map(|(order_i, _)| unfinished_order_penalty(&orders[order_i]))
         .sum();
 
-    let total_time = truck1.get_total_time() + truck2.get_total_time();
-
-    // println!("time: {}", total_time);
     // println!("penalty: {}", penalty);
-    total_time + penalty
+    penalty
+}
+
+/// How often every order is in the routes, without the depots and dump sites.
+fn count_orders(truck1: &Week, truck2: &Week) -> Vec<usize> {
+    let mut order_count: Vec<usize> = vec![0; get_orders().len()];
+    for route in truck1.iter().chain(truck2.iter()) {
+        add_orders(route, &mut order_count);
+    }
+    order_count.truncate(get_sites().first);
+    order_count
+}
+
+/// What it costs when an order isn't done as often as it should be: three times the time it would have taken.
+fn unfinished_order_penalty(order: &Company) -> Time {
+    order.frequency as usize as Time * order.emptying_time * 3 as Time
 }
 
 pub fn add_orders(route: &Route, order_count: &mut [usize]) {
@@ -56,10 +73,245 @@ pub fn add_orders(route: &Route, order_count: &mut [usize]) {
 
 pub fn calculate_starting_score() -> Time {
     calculate_score(&Solution{
-        truck1: Default::default(),
-        truck2: Default::default(),
+        truck1: Week::new(TruckEnum::Truck1),
+        truck2: Week::new(TruckEnum::Truck2),
         score: 0, // this value is not used and does not matter in this function
         unfilled_orders: CompactLinkedVector::new(), // does not matter
         order_flags: OrderFlags::new(0),// does not matter
+        locks: Locks::new(0), // does not matter
     }, &OrderFlags::new(get_orders().iter().count()))
 }
+
+/// What the score is made of, so we can explain why a plan costs what it costs.
+/// All times are in centiseconds, the capacity overflow is in litres.
+///
+/// Everything is calculated from the routes themselves, not from the times stored in them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScoreBreakdown {
+    /// The same as calculate_score, if the stored route times are right
+    pub total: Time,
+    pub driving_time: Time,
+    pub emptying_time: Time,
+    /// The half hour at the dump site at the end of every route that isn't empty
+    pub dropoff_time: Time,
+    /// The breaks the drivers have to take, see Driver
+    pub break_time: Time,
+    pub penalty: Time,
+    /// What the kilometres, fuel and CO2 cost, see Objective
+    pub objective_cost: Time,
+    /// Driven by both trucks, what the sustainability report needs
+    pub emissions: Emissions,
+    /// What it costs that some days or one truck have more work than the others
+    pub balance_cost: Time,
+    /// Every order that isn't done as often as it should be, sorted by order index
+    pub unfinished_orders: Vec<OrderPenalty>,
+    /// How much longer than a full day all days take together. This isn't in the score, the search keeps it at 0.
+    pub time_overflow: Time,
+    /// How much more than the capacity of a truck all routes pick up together. This isn't in the score either.
+    pub capacity_overflow: u32,
+    /// How much longer than allowed the drivers drive without a rest, all routes together. Not in the score.
+    pub continuous_driving_overflow: Time,
+    /// How much longer than allowed the weeks of the drivers are, both together. Not in the score.
+    pub weekly_overflow: Time,
+    /// Per truck, per day
+    pub days: Vec<DaySubtotal>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderPenalty {
+    pub order: u16,
+    /// How often it's planned
+    pub planned: usize,
+    pub frequency: usize,
+    pub penalty: Time,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DaySubtotal {
+    pub truck: TruckEnum,
+    pub day: DayEnum,
+    pub driving_time: Time,
+    pub emptying_time: Time,
+    pub dropoff_time: Time,
+    pub break_time: Time,
+    pub time_overflow: Time,
+    pub capacity_overflow: u32,
+    pub continuous_driving_overflow: Time,
+    pub objective_cost: Time,
+    pub emissions: Emissions,
+}
+
+impl DaySubtotal {
+    /// How long the day takes, without the objective cost
+    pub fn total(&self) -> Time {
+        self.driving_time + self.emptying_time + self.dropoff_time + self.break_time
+    }
+}
+
+impl ScoreBreakdown {
+    pub fn new(solution: &Solution) -> Self {
+        Self::from_trucks(&solution.truck1, &solution.truck2)
+    }
+
+    /// Only needs the routes, so it also works on what the GUI gets from the search threads.
+    pub fn from_trucks(truck1: &Week, truck2: &Week) -> Self {
+        let orders = get_orders();
+        let mut breakdown = ScoreBreakdown::default();
+
+        for (truck_enum, truck) in [(TruckEnum::Truck1, truck1), (TruckEnum::Truck2, truck2)] {
+            for day_enum in DayEnum::ALL {
+                let subtotal = day_subtotal(truck.get(day_enum), truck_enum, day_enum);
+                breakdown.driving_time += subtotal.driving_time;
+                breakdown.emptying_time += subtotal.emptying_time;
+                breakdown.dropoff_time += subtotal.dropoff_time;
+                breakdown.break_time += subtotal.break_time;
+                breakdown.time_overflow += subtotal.time_overflow;
+                breakdown.capacity_overflow += subtotal.capacity_overflow;
+                breakdown.continuous_driving_overflow += subtotal.continuous_driving_overflow;
+                breakdown.objective_cost += subtotal.objective_cost;
+                breakdown.emissions += subtotal.emissions;
+                breakdown.days.push(subtotal);
+            }
+        }
+
+        for (order_i, planned) in count_orders(truck1, truck2).into_iter().enumerate() {
+            let order = &orders[order_i];
+            if order.frequency as usize != planned {
+                breakdown.unfinished_orders.push(OrderPenalty {
+                    order: order.order,
+                    planned,
+                    frequency: order.frequency as usize,
+                    penalty: unfinished_order_penalty(order),
+                });
+            }
+        }
+        breakdown.penalty = breakdown.unfinished_orders.iter().map(|order| order.penalty).sum();
+
+        let mut day_times = DayTimes::default();
+        for day in &breakdown.days {
+            day_times.0[day.truck as usize][day.day as usize] = day.total();
+        }
+        breakdown.balance_cost = day_times.balance_cost();
+        breakdown.weekly_overflow = day_times.weekly_overflow();
+
+        breakdown.total = breakdown.driving_time
+            + breakdown.emptying_time
+            + breakdown.dropoff_time
+            + breakdown.break_time
+            + breakdown.objective_cost
+            + breakdown.balance_cost
+            + breakdown.penalty;
+        breakdown
+    }
+
+    /// The time and objective cost of the truck
+    pub fn truck_total(&self, truck: TruckEnum) -> Time {
+        self.days.iter().filter(|day| day.truck == truck).map(|day| day.total() + day.objective_cost).sum()
+    }
+}
+
+fn day_subtotal(day: &Day, truck: TruckEnum, day_enum: DayEnum) -> DaySubtotal {
+    let orders = get_orders();
+    let sites = get_sites();
+    let objective = get_objective();
+    let mut subtotal = DaySubtotal {
+        truck,
+        day: day_enum,
+        driving_time: 0,
+        emptying_time: 0,
+        dropoff_time: 0,
+        break_time: 0,
+        time_overflow: 0,
+        capacity_overflow: 0,
+        continuous_driving_overflow: 0,
+        objective_cost: 0,
+        emissions: Emissions::default(),
+    };
+
+    for route in [day.get(TimeOfDay::Morning), day.get(TimeOfDay::Afternoon)] {
+        if route.is_empty() {
+            continue;
+        }
+        // the same walk as Route::calculate_time
+        let lv = &route.linked_vector;
+        for (node_i, order_i) in lv.iter() {
+            if lv.get_tail_index() == Some(node_i) {
+                break;
+            }
+            let next_order_i = *lv.get_next_value(node_i).unwrap();
+            subtotal.driving_time += time_between_two_nodes(orders[*order_i].matrix_id, orders[next_order_i].matrix_id);
+            if lv.get_head_index() == Some(node_i) {
+                continue;
+            }
+            if sites.is_site(*order_i) {
+                // unloading halfway the route
+                subtotal.dropoff_time += orders[*order_i].service_time();
+            } else {
+                subtotal.emptying_time += orders[*order_i].emptying_time;
+            }
+        }
+        // driving back to the depot after unloading
+        subtotal.driving_time += time_between_two_nodes(orders[route.dump_site()].matrix_id, orders[route.depot()].matrix_id);
+        subtotal.dropoff_time += HALF_HOUR;
+        subtotal.capacity_overflow += route.capacity_overflow();
+        let rests = route.calculate_rests();
+        subtotal.break_time += route.driver().break_time(rests);
+        subtotal.continuous_driving_overflow += rests.continuous_overflow;
+
+        let (distance, load_distance) = (route.calculate_distance(), route.calculate_load_distance());
+        subtotal.objective_cost += objective.route_cost(truck, distance, load_distance);
+        subtotal.emissions += objective.emissions(truck, distance, load_distance);
+    }
+
+    subtotal.time_overflow = (subtotal.total() - FULL_DAY).max(0);
+    subtotal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScoreBreakdown, calculate_score};
+    use crate::datastructures::linked_vectors::LinkedVector;
+    use crate::resource::HALF_HOUR;
+    use crate::simulated_annealing::day::TimeOfDay;
+    use crate::simulated_annealing::simulated_annealing::TruckEnum;
+    use crate::simulated_annealing::solution::Solution;
+    use crate::simulated_annealing::week::DayEnum;
+    use crate::init_orders_for_tests;
+
+    #[test]
+    fn empty_schedule_is_all_penalty() {
+        init_orders_for_tests();
+        let breakdown = ScoreBreakdown::new(&Solution::new());
+        assert_eq!(breakdown.total, calculate_score(&Solution::new(), &Solution::new().order_flags));
+        assert_eq!(breakdown.total, breakdown.penalty);
+        assert_eq!(breakdown.days.len(), 10);
+        assert!(breakdown.days.iter().all(|day| day.total() == 0));
+    }
+
+    #[test]
+    fn parts_add_up_to_the_score() {
+        init_orders_for_tests();
+        let mut solution = Solution::new();
+        for (order_index, day, truck, trip) in [
+            (0, DayEnum::Monday, TruckEnum::Truck1, TimeOfDay::Morning),
+            (1, DayEnum::Monday, TruckEnum::Truck1, TimeOfDay::Afternoon),
+            (2, DayEnum::Thursday, TruckEnum::Truck2, TimeOfDay::Morning),
+        ] {
+            let route = solution.get_truck_mut(truck).get_mut(day).get_mut(trip);
+            let head = route.linked_vector.get_head_index().unwrap();
+            route.apply_add_order(head, order_index);
+            solution.order_flags.add_order(order_index, day);
+        }
+        let breakdown = ScoreBreakdown::new(&solution);
+
+        assert_eq!(breakdown.total, calculate_score(&solution, &solution.order_flags));
+        assert_eq!(breakdown.dropoff_time, 3 * HALF_HOUR);
+        assert_eq!(
+            breakdown.truck_total(TruckEnum::Truck1) + breakdown.truck_total(TruckEnum::Truck2) + breakdown.penalty,
+            breakdown.total
+        );
+        let monday = &breakdown.days[0];
+        let truck1 = &solution.truck1;
+        assert_eq!(monday.total(), truck1.get(DayEnum::Monday).get_total_time());
+    }
+}
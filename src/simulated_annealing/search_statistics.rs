@@ -0,0 +1,48 @@
+use crate::simulated_annealing::neighbor_chooser::{MOVE_COUNT, deserialize_per_move};
+use serde::{Deserialize, Serialize};
+
+/// Counts how every kind of move does during the annealing. The perturbations between iterations are left out.
+/// The index is the same as in the weights arrays (see neighbor_chooser).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchStatistics {
+    #[serde(deserialize_with = "deserialize_per_move")]
+    pub tried: [u64; MOVE_COUNT],
+    #[serde(deserialize_with = "deserialize_per_move")]
+    pub accepted: [u64; MOVE_COUNT],
+    /// Accepted moves that made the score better
+    #[serde(deserialize_with = "deserialize_per_move")]
+    pub improved: [u64; MOVE_COUNT],
+}
+
+impl SearchStatistics {
+    pub fn record(&mut self, move_kind: usize, accepted: bool, improved: bool) {
+        self.tried[move_kind] += 1;
+        if accepted {
+            self.accepted[move_kind] += 1;
+            if improved {
+                self.improved[move_kind] += 1;
+            }
+        }
+    }
+
+    /// What happened between `earlier` and now.
+    pub fn since(&self, earlier: &SearchStatistics) -> SearchStatistics {
+        let difference = |now: &[u64; MOVE_COUNT], then: &[u64; MOVE_COUNT]| {
+            std::array::from_fn(|i| now[i] - then[i])
+        };
+        SearchStatistics {
+            tried: difference(&self.tried, &earlier.tried),
+            accepted: difference(&self.accepted, &earlier.accepted),
+            improved: difference(&self.improved, &earlier.improved),
+        }
+    }
+
+    /// Accepted moves divided by tried moves, over all kinds of moves.
+    pub fn acceptance_rate(&self) -> f64 {
+        let tried: u64 = self.tried.iter().sum();
+        if tried == 0 {
+            return 0.0;
+        }
+        self.accepted.iter().sum::<u64>() as f64 / tried as f64
+    }
+}
@@ -1,27 +1,86 @@
 use std::cmp::max;
 use super::week::Week;
-use crate::printer::print_solution;
+use crate::printer::checkpoint::{CHECKPOINT_FORMAT_VERSION, Checkpoint, print_checkpoint};
+use crate::printer::json::{RunInfo, SolutionFile, print_solution_json};
+use crate::printer::score::print_score_breakdown;
+use crate::printer::run_log::{RunManifest, TracePoint, TraceWriter, print_run_manifest};
+use crate::printer::{print_inconsistency, print_solution};
 use crate::resource::Time;
+// it used to live here, most of the solver still imports it from here
+pub use crate::resource::TruckEnum;
+use crate::simulated_annealing::constraints::{ADAPT_INTERVAL, ConstraintModes, PenaltyWeights, Repair, SAMPLE_INTERVAL, Violations, repair};
 use crate::simulated_annealing::neighbor_move::evaluation::Evaluation;
+use crate::simulated_annealing::neighbor_move::neighbor_move_trait::NeighborMove;
+use crate::simulated_annealing::neighbor_chooser::{MOVE_COUNT, deserialize_per_move};
 use crate::simulated_annealing::route::OrderIndex;
-use crate::simulated_annealing::score_calculator::calculate_score;
+use crate::simulated_annealing::score_calculator::{calculate_balance_cost, calculate_score};
+use crate::simulated_annealing::search_statistics::SearchStatistics;
 use crate::simulated_annealing::solution::Solution;
 use crate::simulated_annealing::FIXTHISSHITANDWEAREDONE::fixplzplzplzpl;
 use flume::{Receiver, Sender};
-use rand::distr::{Distribution, StandardUniform};
 use rand::prelude::SmallRng;
 use rand::{Rng, SeedableRng};
 use std::f32::consts::E;
-use std::fs::create_dir;
+use std::fs::{create_dir, create_dir_all};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use time::OffsetDateTime;
+use serde::{Deserialize, Serialize};
 use crate::datastructures::linked_vectors::{LVNodeIndex, LinkedVector};
 
 type RouteState = (Arc<Week>, Arc<Week>);
 
+/// The weights for choosing a neighbor while annealing.
+pub const SEARCH_WEIGHTS: [i32; MOVE_COUNT] = [
+    30,  // add new order
+    1,    // remove
+    150, // within a route
+    80, // within a day
+    30, // shift between days
+    30,  // granular add new order
+    150, // granular within a route
+    80, // granular within a day
+    30, // granular shift between days
+    10, // change dump site
+    5,  // add unload stop
+    5,  // remove unload stop
+];
+
+/// The weights for the random steps that perturb the best solution before the next ILS iteration.
+const PERTURBATION_WEIGHTS: [i32; MOVE_COUNT] = [
+    1, // add
+    1, // remove
+    0, // shift in route
+    1, // shift in day
+    1, // shift between days
+    0, // granular add
+    0, // granular shift in route
+    0, // granular shift in day
+    0, // granular shift between days
+    0, // change dump site
+    0, // add unload stop
+    0, // remove unload stop
+];
+
+/// How many perturbation steps we do before the first ILS iteration, and after every improvement.
+const START_PERTURBATIONS: u32 = 10;
+
+/// How many steps there are between two lines in trace.csv, and between two points in the convergence charts
+const TRACE_INTERVAL: u32 = 100_000;
+
+/// How many steps there are between two checkpoints. A checkpoint also gets written when the search is stopped.
+const CHECKPOINT_INTERVAL: u32 = 20_000_000;
+
+// The parameters the GUI starts with, and what a search from the command line uses.
+pub const DEFAULT_TEMP: f32 = 50_000.0;
+pub const DEFAULT_END_TEMP: f32 = 10.0;
+pub const DEFAULT_Q: u32 = 500_000;
+pub const DEFAULT_ALPHA: f32 = 0.99;
+
 pub struct SimulatedAnnealingConfig {
     pub idx: usize,
+    /// How many searches are running next to each other. Only used for the run manifest.
+    pub thread_count: usize,
     pub temp: f32,
     pub end_temp: f32,
     pub q: u32,
@@ -33,10 +92,95 @@ pub struct SimulatedAnnealingConfig {
     pub q_sender: Sender<u32>,
     pub temp_sender: Sender<f32>,
     pub route_sender: Sender<RouteState>,
+    pub progress_sender: Sender<ProgressPoint>,
+    /// What the repair at the end of every iteration did, the GUI shows it when it failed
+    pub repair_sender: Sender<Repair>,
+    /// The solution to start searching from. If this is None, we start with an empty schedule.
+    pub start_solution: Option<Solution>,
+    /// Paranoid mode: every this many steps the whole solution gets checked for inconsistencies.
+    /// If something is wrong, the solution and the last move get written to a file and we panic.
+    /// This is slow, so it's None unless we're hunting for a bug.
+    pub paranoid_interval: Option<u32>,
+    /// The seed for the random number generator of the search. If this is None, a random seed gets picked.
+    /// Either way, the seed that was used ends up next to the solutions, so a run can be repeated.
+    pub seed: Option<u64>,
+    /// Which constraints the search may break for a penalty, and which never
+    pub constraint_modes: ConstraintModes,
+}
+
+/// How the search is doing, sent to the GUI every TRACE_INTERVAL steps for the convergence charts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressPoint {
+    pub step: u32,
+    pub seconds: f32,
+    pub score: Time,
+    pub best_score: Time,
+    /// Whether the plan with the best score keeps the constraints, the gap to the lower bound only holds then
+    pub best_feasible: bool,
+    pub temp: f32,
+    /// Over the steps since the previous point
+    pub acceptance_rate: f32,
+    pub unfilled_orders: usize,
+}
+
+/// Everything that decides how the search behaves, so it can be written down next to the solutions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolverParameters {
+    pub temp: f32,
+    pub end_temp: f32,
+    pub reheating_temp: f32,
+    pub q: u32,
+    pub a: f32,
+    pub max_iterations: u32,
+    pub num_perturbations: u32,
+    #[serde(deserialize_with = "deserialize_per_move")]
+    pub search_weights: [i32; MOVE_COUNT],
+    #[serde(deserialize_with = "deserialize_per_move")]
+    pub perturbation_weights: [i32; MOVE_COUNT],
+    #[serde(default)]
+    pub constraint_modes: ConstraintModes,
+}
+
+impl SimulatedAnnealingConfig {
+    /// A config that is not connected to a GUI, for benchmarks and tests.
+    /// The other ends of all channels are dropped right away, so anything sent to them just gets lost.
+    pub fn headless(temp: f32, end_temp: f32, q: u32, a: f32) -> Self {
+        let (_, pause_rec) = flume::bounded(1);
+        let (_, stop_rec) = flume::bounded(1);
+        let (score_sender, _) = flume::bounded(1);
+        let (q_sender, _) = flume::bounded(1);
+        let (temp_sender, _) = flume::bounded(1);
+        let (route_sender, _) = flume::bounded(1);
+        let (progress_sender, _) = flume::bounded(1);
+        let (repair_sender, _) = flume::bounded(1);
+        SimulatedAnnealingConfig {
+            idx: 0,
+            thread_count: 1,
+            temp,
+            end_temp,
+            q,
+            a,
+            egui_ctx: egui::Context::default(),
+            pause_rec,
+            stop_rec,
+            score_sender,
+            q_sender,
+            temp_sender,
+            route_sender,
+            progress_sender,
+            repair_sender,
+            start_solution: None,
+            paranoid_interval: None,
+            seed: None,
+            constraint_modes: ConstraintModes::default(),
+        }
+    }
 }
 
 pub struct SimulatedAnnealing {
     idx: usize,
+    thread_count: usize,
+    start_temp: f32,
     temp: f32,
     end_temp: f32,
     reheating_temp: f32,
@@ -45,11 +189,40 @@ pub struct SimulatedAnnealing {
     q: u32,
     step_count: u32,
     a: f32,
+    // the ILS iteration we're in
+    iteration: u32,
+    constraint_modes: ConstraintModes,
+    // what breaking the soft constraints costs, changes with how often the search breaks them
+    penalties: PenaltyWeights,
 
     pub best_solution: Solution,
+    /// What the repair did to the best solution. If it failed, the best solution still breaks the constraints.
+    pub best_repair: Repair,
+    // the repair at the end of the last iteration, which becomes best_repair if the iteration was the best
+    last_repair: Repair,
     // We could store variables here which are needed for simulated annealing.
     paused: bool,
 
+    paranoid_interval: Option<u32>,
+    // Every do_step, the perturbations too. step_count only counts the annealing steps.
+    paranoid_steps: u32,
+    seed: u64,
+    // Only kept up to date in paranoid mode, formatting every move is not free
+    last_move: String,
+    // set by choose_neighbor, the index into the weights
+    pub(crate) last_move_kind: usize,
+
+    // Logging
+    pub statistics: SearchStatistics,
+    // the statistics when the last line of the trace was written
+    traced_statistics: SearchStatistics,
+    trace: Option<TraceWriter>,
+    started: Instant,
+    // None until the run has an output folder, checkpoints are only written once it does
+    output_dir: Option<String>,
+    // The rng and solution to carry on with, if this search was resumed from a checkpoint
+    resumed: Option<(SmallRng, Solution)>,
+
     // Channels for communicating with the draw thread
     egui_ctx: egui::Context,
     pause_rec: Receiver<()>,
@@ -58,20 +231,8 @@ pub struct SimulatedAnnealing {
     q_sender: Sender<u32>,
     temp_sender: Sender<f32>,
     route_sender: Sender<RouteState>,
-}
-
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
-pub enum TruckEnum {
-    Truck1,
-    Truck2,
-}
-impl Distribution<TruckEnum> for StandardUniform {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> TruckEnum {
-        match rng.random_range(0..2) {
-            0 => TruckEnum::Truck1,
-            _ => TruckEnum::Truck2,
-        }
-    }
+    progress_sender: Sender<ProgressPoint>,
+    repair_sender: Sender<Repair>,
 }
 
 impl SimulatedAnnealing {
@@ -79,18 +240,35 @@ impl SimulatedAnnealing {
         // intializationthings
         SimulatedAnnealing {
             idx: config.idx,
+            thread_count: config.thread_count,
+            start_temp: config.temp,
             temp: config.temp, // initialized as starting temperature, decreases to end_temp
             end_temp: config.end_temp,
             reheating_temp: 4000f32,
             max_iterations: 100,
-            num_perturbations: 10,
+            num_perturbations: START_PERTURBATIONS,
             q: config.q,
             step_count: 0,
             a: config.a, // keep around 0.95 or 0.99. It's better to change Q or temp
+            iteration: 0,
+            constraint_modes: config.constraint_modes,
+            penalties: PenaltyWeights::default(),
 
-            // best_solution: Solution::default(),
-            best_solution: Solution::from_file("output/0 5421.txt"),
+            best_solution: config.start_solution.unwrap_or_default(),
+            best_repair: Repair::default(),
+            last_repair: Repair::default(),
             paused: false,
+            paranoid_interval: config.paranoid_interval,
+            paranoid_steps: 0,
+            seed: config.seed.unwrap_or_else(rand::random),
+            last_move: String::new(),
+            last_move_kind: 0,
+            statistics: SearchStatistics::default(),
+            traced_statistics: SearchStatistics::default(),
+            trace: None,
+            started: Instant::now(),
+            output_dir: None,
+            resumed: None,
             egui_ctx: config.egui_ctx,
             pause_rec: config.pause_rec,
             stop_rec: config.stop_rec,
@@ -98,64 +276,80 @@ impl SimulatedAnnealing {
             q_sender: config.q_sender,
             temp_sender: config.temp_sender,
             route_sender: config.route_sender,
+            progress_sender: config.progress_sender,
+            repair_sender: config.repair_sender,
         }
     }
 
     // Iterated Local Search (ILS)
     pub fn insanely_large_stuffloop(&mut self) {
-        let mut rng = SmallRng::from_os_rng();
-        // let mut rng = SmallRng::seed_from_u64(0);
-
-        let now = OffsetDateTime::now_local().unwrap();
-        let output_dir = format!("output/{now}").replace(":", "_");
+        // A resumed search is halfway through an iteration, which carries on without perturbing first
+        let (mut rng, mut resumed_solution) = match self.resumed.take() {
+            Some((rng, solution)) => {
+                self.trace = TraceWriter::append(self.output_dir.as_ref().unwrap())
+                    .inspect_err(|error| println!("Could not continue the trace: {error}"))
+                    .ok();
+                (rng, Some(solution))
+            }
+            None => {
+                let now = OffsetDateTime::now_local().unwrap();
+                let output_dir = format!("output/{now}").replace(":", "_");
+                create_dir(&output_dir).expect("Could not create an output folder");
+                self.start_run_log(&output_dir, now.to_string());
+                self.output_dir = Some(output_dir);
+                (SmallRng::seed_from_u64(self.seed), None)
+            }
+        };
+        let output_dir = self.output_dir.clone().unwrap();
 
-        let start_solution = self.biiiiiig_loop(&mut rng, self.best_solution.clone());
-        if start_solution.is_none(){
+        // Every step would be skipped, the best solution is the one we started with
+        let start_locks = resumed_solution.as_ref().map_or(&self.best_solution.locks, |solution| &solution.locks);
+        if start_locks.all_routes_frozen() {
+            println!("Thread {} has nothing to search, every route is frozen", self.idx);
             return;
         }
-        let start_solution = start_solution.unwrap();
-        create_dir(&output_dir).expect("Could not create an output folder");
-        print_solution(&start_solution, &output_dir, 0).expect("failed to print the solution");
-        if start_solution.score <= self.best_solution.score {self.best_solution = start_solution}
-
-        for i in 1..=self.max_iterations {
-            let mut next_iteration = self.best_solution.clone();
-            self.temp = f32::MAX;
-            for _ in 0..self.num_perturbations {
-                self.do_step(&mut rng, [
-                    1, // add
-                    1, // remove
-                    0, // shift in route
-                    1, // shift in day
-                    1, // shift between days
-                    ],
-                    &mut next_iteration,
-                );
-            }
 
-            self.temp = self.reheating_temp;
-            let next_iteration = self.biiiiiig_loop(&mut rng, next_iteration);
+        for i in self.iteration..=self.max_iterations {
+            self.iteration = i;
+            let next_iteration = match resumed_solution.take() {
+                Some(solution) => solution,
+                None if i == 0 => self.best_solution.clone(),
+                None => {
+                    let mut next_iteration = self.best_solution.clone();
+                    self.perturb(&mut rng, &mut next_iteration);
+                    next_iteration
+                }
+            };
 
-            if next_iteration.is_none(){
+            let Some(next_iteration) = self.biiiiiig_loop(&mut rng, next_iteration) else {
                 return;
-            }
-            let next_iteration = next_iteration.unwrap();
+            };
 
             print_solution(&next_iteration, &output_dir, i)
                 .expect("failed to print the solution");
+            print_solution_json(&next_iteration, &output_dir, i, self.run_info())
+                .expect("failed to print the solution");
+            print_score_breakdown(&next_iteration, &output_dir, i)
+                .expect("failed to print the score breakdown");
 
-            self.num_perturbations += 2;
+            // the first iteration starts from the start solution, so there's nothing to adapt yet
+            if i > 0 {
+                self.num_perturbations += 2;
+            }
 
             if next_iteration.score <= self.best_solution.score {
-                self.num_perturbations = 10;
+                if i > 0 {
+                    self.num_perturbations = START_PERTURBATIONS;
+                }
                 self.best_solution = next_iteration;
+                self.best_repair = std::mem::take(&mut self.last_repair);
             }
         }
     }
 
-    pub fn biiiiiig_loop<R: Rng + ?Sized>(
+    pub fn biiiiiig_loop(
         &mut self,
-        rng: &mut R,
+        rng: &mut SmallRng,
         mut solution: Solution,
     ) -> Option<Solution> {
         // let now = Instant::now();
@@ -165,6 +359,9 @@ impl SimulatedAnnealing {
         // main loop: gui stuff and do_step and thermostat
         loop {
             if self.stop_rec.try_recv().is_ok() {
+                if let Err(error) = self.write_checkpoint(&solution) {
+                    println!("Could not write the checkpoint: {error}");
+                }
                 return None;
             }
             if self.pause_rec.try_recv().is_ok() {
@@ -181,21 +378,16 @@ impl SimulatedAnnealing {
                 self.egui_ctx.request_repaint();
                 continue;
             }
-            self.do_step(
-                rng,
-                [
-                    30,  // add new order
-                    1,    // remove
-                    150, // within a route
-                    80, // within a day
-                    30, // shift between days
-                ],
-                &mut solution,
-            );
-
-            self.step_count += 1;
-            if self.step_count.is_multiple_of(self.q) {
-                self.temp *= self.a;
+            self.do_step(rng, SEARCH_WEIGHTS, &mut solution, true);
+            self.cool_down();
+            self.follow_constraints(&solution);
+            if self.step_count.is_multiple_of(TRACE_INTERVAL) {
+                self.record_progress(&solution);
+            }
+            if self.step_count.is_multiple_of(CHECKPOINT_INTERVAL)
+                && let Err(error) = self.write_checkpoint(&solution)
+            {
+                println!("Could not write the checkpoint: {error}");
             }
             // Yes... it uses a clone, I really tried to avoid it, but there's simply no way to ensure no data races or heavy slowdown through locking
             // Future: It should only send a new route when it's faster, not just accepted
@@ -245,45 +437,274 @@ impl SimulatedAnnealing {
         Some(solution)
     }
 
+    /// The parameters this search was started with.
+    pub fn parameters(&self) -> SolverParameters {
+        SolverParameters {
+            temp: self.start_temp,
+            end_temp: self.end_temp,
+            reheating_temp: self.reheating_temp,
+            q: self.q,
+            a: self.a,
+            max_iterations: self.max_iterations,
+            num_perturbations: START_PERTURBATIONS,
+            search_weights: SEARCH_WEIGHTS,
+            perturbation_weights: PERTURBATION_WEIGHTS,
+            constraint_modes: self.constraint_modes,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn run_info(&self) -> RunInfo {
+        RunInfo {
+            seed: Some(self.seed),
+            parameters: Some(self.parameters()),
+        }
+    }
+
+    /// Writes run.json and starts trace.csv in the output folder.
+    /// If that doesn't work we just search without logging, that's better than losing the run.
+    fn start_run_log(&mut self, output_dir: &str, started: String) {
+        let manifest = RunManifest::new(started, self.idx, self.thread_count, self.seed, self.parameters());
+        if let Err(error) = print_run_manifest(&manifest, output_dir) {
+            println!("Could not write the run manifest: {error}");
+        }
+        self.trace = TraceWriter::new(output_dir)
+            .inspect_err(|error| println!("Could not create the trace: {error}"))
+            .ok();
+        self.started = Instant::now();
+    }
+
+    /// Writes a line to trace.csv, and sends the same numbers to the GUI for the charts.
+    fn record_progress(&mut self, solution: &Solution) {
+        let point = TracePoint {
+            step: self.step_count,
+            iteration: self.iteration,
+            elapsed_seconds: self.started.elapsed().as_secs_f64(),
+            temp: self.temp,
+            score: solution.score,
+            best_score: self.best_solution.score.min(solution.score),
+            statistics: self.statistics.since(&self.traced_statistics),
+        };
+        self.traced_statistics = self.statistics.clone();
+        let best = if solution.score < self.best_solution.score { solution } else { &self.best_solution };
+
+        self.progress_sender
+            .try_send(ProgressPoint {
+                step: point.step,
+                seconds: point.elapsed_seconds as f32,
+                score: point.score,
+                best_score: point.best_score,
+                best_feasible: Violations::of(best).is_feasible(),
+                temp: point.temp,
+                acceptance_rate: point.statistics.acceptance_rate() as f32,
+                unfilled_orders: solution.unfilled_orders.len(),
+            })
+            .ok();
+
+        if let Some(trace) = &mut self.trace
+            && let Err(error) = trace.write(&point)
+        {
+            println!("Could not write to the trace, stopping the trace: {error}");
+            self.trace = None;
+        }
+    }
+
+    /// Saves the state of the search, without touching it.
+    ///
+    /// A resumed search does not make the same moves as this one would have. The rng can't be saved,
+    /// and the moves pick orders by where they are stored in the linked vectors, which the solution files don't keep.
+    /// Resuming the same checkpoint twice does make the same moves.
+    pub fn checkpoint(&self, solution: &Solution) -> Checkpoint {
+        Checkpoint {
+            version: CHECKPOINT_FORMAT_VERSION,
+            output_dir: self.output_dir.clone().unwrap_or_default(),
+            seed: self.seed,
+            parameters: self.parameters(),
+            temp: self.temp,
+            step_count: self.step_count,
+            num_perturbations: self.num_perturbations,
+            iteration: self.iteration,
+            elapsed_seconds: self.started.elapsed().as_secs_f64(),
+            statistics: self.statistics.clone(),
+            penalty_weights: self.penalties.clone(),
+            current: SolutionFile::new(solution, self.run_info()),
+            best: SolutionFile::new(&self.best_solution, self.run_info()),
+            best_repair: self.best_repair.clone(),
+        }
+    }
+
+    /// Does nothing until the run has an output folder.
+    fn write_checkpoint(&self, solution: &Solution) -> std::io::Result<()> {
+        if self.output_dir.is_none() {
+            return Ok(());
+        }
+        let checkpoint = self.checkpoint(solution);
+        print_checkpoint(&checkpoint, &checkpoint.output_dir)
+    }
+
+    /// Puts the search in the state of the checkpoint, and returns the rng and solution to carry on with.
+    /// The parameters of the checkpoint win over the ones in the config.
+    pub fn restore(&mut self, checkpoint: &Checkpoint) -> Result<(SmallRng, Solution), String> {
+        let current = checkpoint.current.to_solution()?;
+        self.best_solution = checkpoint.best.to_solution()?;
+        self.best_repair = checkpoint.best_repair.clone();
+
+        let parameters = &checkpoint.parameters;
+        self.start_temp = parameters.temp;
+        self.end_temp = parameters.end_temp;
+        self.reheating_temp = parameters.reheating_temp;
+        self.q = parameters.q;
+        self.a = parameters.a;
+        self.max_iterations = parameters.max_iterations;
+        self.constraint_modes = parameters.constraint_modes;
+        self.penalties = checkpoint.penalty_weights.clone();
+
+        self.seed = checkpoint.seed;
+        self.temp = checkpoint.temp;
+        self.step_count = checkpoint.step_count;
+        self.num_perturbations = checkpoint.num_perturbations;
+        self.iteration = checkpoint.iteration;
+        self.statistics = checkpoint.statistics.clone();
+        self.traced_statistics = checkpoint.statistics.clone();
+        self.started = Instant::now()
+            .checked_sub(Duration::from_secs_f64(checkpoint.elapsed_seconds))
+            .unwrap_or_else(Instant::now);
+
+        // a new rng for every checkpoint, but the same one every time this checkpoint is resumed
+        let rng_seed = checkpoint.seed.wrapping_add(checkpoint.step_count as u64);
+        Ok((SmallRng::seed_from_u64(rng_seed), current))
+    }
+
+    /// Makes insanely_large_stuffloop carry on from the checkpoint, in the same output folder, instead of starting a new run.
+    pub fn resume_from(&mut self, checkpoint: &Checkpoint) -> Result<(), String> {
+        self.resumed = Some(self.restore(checkpoint)?);
+        self.output_dir = Some(checkpoint.output_dir.clone());
+        Ok(())
+    }
+
+    fn cool_down(&mut self) {
+        self.step_count += 1;
+        if self.step_count.is_multiple_of(self.q) {
+            self.temp *= self.a;
+        }
+    }
+
+    /// Does a fixed number of annealing steps on the solution without talking to the GUI.
+    /// This does not stop at end_temp, so runs with the same seed always do the same amount of work.
+    pub fn run_steps<R: Rng + ?Sized>(&mut self, rng: &mut R, solution: &mut Solution, steps: u32) {
+        for _ in 0..steps {
+            self.do_step(rng, SEARCH_WEIGHTS, solution, true);
+            self.cool_down();
+            self.follow_constraints(solution);
+        }
+    }
+
+    /// Changes the solution at random with PERTURBATION_WEIGHTS, accepting every move, to start the next iteration from.
+    /// These aren't annealing steps, so the step count and the statistics leave them out.
+    pub fn perturb<R: Rng + ?Sized>(&mut self, rng: &mut R, solution: &mut Solution) {
+        self.temp = f32::MAX;
+        for _ in 0..self.num_perturbations {
+            self.do_step(rng, PERTURBATION_WEIGHTS, solution, false);
+        }
+        self.temp = self.reheating_temp;
+    }
+
+    /// Keeps track of how often the solution breaks the constraints, and adapts the penalties to it.
+    /// Not done in do_step, the perturbations break whatever they like.
+    fn follow_constraints(&mut self, solution: &Solution) {
+        if self.step_count.is_multiple_of(SAMPLE_INTERVAL) {
+            self.penalties.record(&Violations::of(solution));
+        }
+        if self.step_count.is_multiple_of(ADAPT_INTERVAL) {
+            self.penalties.adapt();
+        }
+    }
+
+    /// What breaking the soft constraints costs right now
+    pub fn penalty_weights(&self) -> &PenaltyWeights {
+        &self.penalties
+    }
+
+    /// Skips the step when choose_neighbor found no move to do, which happens now and then in a heavily locked plan.
+    /// Only the annealing steps go in the statistics, `annealing` is false for the perturbations.
     fn do_step<R: Rng + ?Sized>(
         &mut self,
         rng: &mut R,
-        weights: [i32; 5],
+        weights: [i32; MOVE_COUNT],
         solution: &mut Solution,
+        annealing: bool,
     ) {
-        let (neighborhood, order_to_add_after_apply) = self.choose_neighbor(rng, weights, solution);
+        let Some((neighborhood, order_to_add_after_apply)) = self.choose_neighbor(rng, weights, solution) else {
+            return;
+        };
 
         // get the change in capacity/time
         let cost = neighborhood.evaluate(solution);
+        let improves = cost.cost < 0;
 
         // if we want to go through with this thing
-        if self.accept(cost, rng) {
+        let accepted = self.accept(cost, rng);
+        if annealing {
+            self.statistics.record(self.last_move_kind, accepted, improves);
+        }
+        if accepted {
             // change the route
+            Self::apply_move(solution, neighborhood.as_ref(), order_to_add_after_apply);
 
-            solution.score += neighborhood.apply(solution);
-
-            if let EndOfStepInfo::Add(order_to_add) = order_to_add_after_apply {
-                solution.unfilled_orders.push_back(order_to_add);
-            }
-            if let EndOfStepInfo::Remove(node_index) = order_to_add_after_apply {
-                solution.unfilled_orders.remove(node_index);
-                solution.unfilled_orders.compact();
+            if self.paranoid_interval.is_some() {
+                self.last_move = format!("{neighborhood:#?}");
             }
+        }
 
-            return;
+        if let Some(interval) = self.paranoid_interval {
+            self.paranoid_steps += 1;
+            if self.paranoid_steps.is_multiple_of(interval) {
+                self.check_invariants(solution);
+            }
         }
     }
 
-    fn accept<R: Rng + ?Sized>(&self, evaluation: Evaluation, rng: &mut R) -> bool {
+    /// Checks the whole solution for inconsistencies.
+    /// If it finds one, it writes the solution and the last applied move to a folder in output, and panics.
+    fn check_invariants(&self, solution: &Solution) {
+        let Err(inconsistency) = solution.check_consistency() else {
+            return;
+        };
+
+        let now = OffsetDateTime::now_local().unwrap();
+        let dump_dir = format!("output/paranoid {now}").replace(":", "_");
+        create_dir_all(&dump_dir).expect("Could not create a folder for the paranoid dump");
+        print_inconsistency(solution, &dump_dir, self.step_count, &inconsistency, &self.last_move)
+            .expect("failed to write the paranoid dump");
 
-        let time_delta_multiplier = 6;
-        let capacity_delta_multiplier = 2500;
+        panic!("Thread {} found an inconsistency at step {}: {inconsistency}. See \"{dump_dir}\"", self.idx, self.step_count);
+    }
+
+    /// Applies a move that came out of choose_neighbor, and updates the score and unfilled orders to match.
+    pub fn apply_move(solution: &mut Solution, neighborhood: &dyn NeighborMove, order_to_add_after_apply: EndOfStepInfo) {
+        // The moves apply their own routes, the balance depends on all days together
+        let balance_before = calculate_balance_cost(solution);
+        solution.score += neighborhood.apply(solution);
+        solution.score += calculate_balance_cost(solution) - balance_before;
 
-        // Calculate total adjusted cost using all factors
-        let mut total_cost = evaluation.cost as i64;
+        if let EndOfStepInfo::Add(order_to_add) = order_to_add_after_apply {
+            solution.unfilled_orders.push_back(order_to_add);
+        }
+        if let EndOfStepInfo::Remove(node_index) = order_to_add_after_apply {
+            solution.unfilled_orders.remove(node_index);
+            solution.unfilled_orders.compact();
+        }
+    }
 
-        total_cost += (evaluation.time_overflow_delta as i64 * time_delta_multiplier) / 100;
-        total_cost += (evaluation.capacity_overflow_delta as i64 * capacity_delta_multiplier) / 100;
+    pub fn accept<R: Rng + ?Sized>(&self, evaluation: Evaluation, rng: &mut R) -> bool {
+        // Breaking a hard constraint more is never accepted, the soft ones cost their penalty
+        let Some(penalty) = self.penalties.penalty(&self.constraint_modes, &Violations::of_evaluation(&evaluation)) else {
+            return false;
+        };
+        let total_cost = evaluation.cost as i64 + penalty as i64;
 
         // If it's an improvement or neutral, always accept
         if total_cost <= 0 {
@@ -310,6 +731,10 @@ impl SimulatedAnnealing {
 
         solution.truck1.recalculate_total_time();
         solution.truck2.recalculate_total_time();
+        if self.paranoid_interval.is_some() {
+            self.check_invariants(solution);
+        }
+
         let after_recalc = calculate_score(solution, &solution.order_flags);
         if after_recalc != before_recalc {
             println!("Incorrect score was stored");
@@ -320,7 +745,16 @@ impl SimulatedAnnealing {
             );
         }
 
-        after_recalc
+        // The soft constraints may still be broken, the plan we hand out may not
+        self.last_repair = repair(solution, &self.penalties);
+        self.repair_sender.try_send(self.last_repair.clone()).ok();
+        if self.last_repair.removed.is_empty() {
+            return after_recalc;
+        }
+        if self.paranoid_interval.is_some() {
+            self.check_invariants(solution);
+        }
+        calculate_score(solution, &solution.order_flags)
     }
 }
 
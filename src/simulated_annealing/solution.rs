@@ -1,9 +1,10 @@
 use std::collections::{HashMap};
 use std::fs::{read_to_string};
-use crate::{get_orders};
+use crate::{get_orders, get_sites};
 use crate::datastructures::compact_linked_vector::CompactLinkedVector;
 use crate::datastructures::linked_vectors::LinkedVector;
 use crate::simulated_annealing::day::{TimeOfDay};
+use crate::simulated_annealing::locks::Locks;
 use crate::simulated_annealing::order_day_flags::OrderFlags;
 use crate::simulated_annealing::route::{OrderIndex};
 use crate::simulated_annealing::score_calculator::{calculate_score, calculate_starting_score};
@@ -17,24 +18,25 @@ pub struct Solution{
     pub score: i32,
     pub unfilled_orders: CompactLinkedVector<OrderIndex>,
     pub order_flags: OrderFlags,
+    pub locks: Locks,
 }
 
 
 impl Solution {
     pub fn new() -> Self {
         Solution {
-            truck1: Default::default(),
-            truck2: Default::default(),
+            truck1: Week::new(TruckEnum::Truck1),
+            truck2: Week::new(TruckEnum::Truck2),
             score: calculate_starting_score(),
             unfilled_orders: Self::fill_unfilled_orders_list(),
             order_flags: Default::default(),
+            locks: Default::default(),
         }
     }
 
     fn fill_unfilled_orders_list() -> CompactLinkedVector<OrderIndex> {
         let mut deliveries = CompactLinkedVector::new();
-        let orders = get_orders();
-        for i in 0..orders.len() - 1 {
+        for i in 0..get_sites().first {
             deliveries.push_back(i);
         }
         deliveries
@@ -46,10 +48,13 @@ impl Solution {
             Self::_fulfilled_order_count(&self.truck2)
     }
     fn _fulfilled_order_count(truck: &Week) -> usize {
-        truck.iter().map(|route| route.linked_vector.len() - 2)
+        truck.iter().map(|route| route.linked_vector.len() - 2 - route.unload_stops as usize)
             .sum()
     }
 
+    /// Reads a solution in the text format that print_solution writes.
+    /// Every 0 in there is taken as the end of the morning trip, so unload stops in the middle of a trip
+    /// end up as the end of a trip too. Only the JSON files keep those.
     pub fn from_file(path: &str) -> Solution{
          let solution_file = read_to_string(path)
              .expect("Could not read the solution file");
@@ -107,9 +112,10 @@ impl Solution {
                 .map(|(amount_done, order)| order.frequency as u32 - *amount_done)
                 .collect();
 
+        // The add move always adds all days of an order at once, so every unfilled order is in here once.
         let mut new_unfilled_orders = Vec::new();
         for (order_index, count) in unfilled_order_counts.iter().enumerate() {
-            for _ in 0..*count{
+            if *count > 0 {
                 new_unfilled_orders.push(order_index as OrderIndex);
             }
         }
@@ -132,16 +138,131 @@ impl Solution {
         solution
     }
 
-    fn order_id_to_index_hash_map() -> HashMap<u16, OrderIndex> {
+    /// Builds a solution around routes that only came with their times and capacities, like the ones the search sends to the GUI.
+    /// Orders that aren't in any route are unfilled.
+    pub fn from_trucks(truck1: Week, truck2: Week) -> Solution {
+        let mut solution = Self::new();
+        solution.truck1 = truck1;
+        solution.truck2 = truck2;
+
+        let sites = get_sites();
+        for truck_enum in [TruckEnum::Truck1, TruckEnum::Truck2] {
+            for day_enum in DayEnum::ALL {
+                let day = solution.get_truck(truck_enum).get(day_enum);
+                let visited: Vec<OrderIndex> = [TimeOfDay::Morning, TimeOfDay::Afternoon]
+                    .into_iter()
+                    .flat_map(|time_of_day| day.get(time_of_day).linked_vector.iter().map(|(_, order_index)| *order_index))
+                    .filter(|order_index| !sites.is_site(*order_index))
+                    .collect();
+                for order_index in visited {
+                    solution.order_flags.add_order(order_index, day_enum);
+                }
+            }
+        }
+
+        solution.unfilled_orders = CompactLinkedVector::new();
+        for order_index in 0..sites.first {
+            if solution.order_flags.get_flag(order_index) == 0 {
+                solution.unfilled_orders.push_back(order_index);
+            }
+        }
+        solution.score = calculate_score(&solution, &solution.order_flags);
+        solution
+    }
+
+    pub(crate) fn order_id_to_index_hash_map() -> HashMap<u16, OrderIndex> {
         let mut map: HashMap<u16, OrderIndex> = HashMap::new();
         let orders = get_orders();
-        for (order_index, order) in orders.iter().enumerate().rev().skip(1).rev() {
+        for (order_index, order) in orders.iter().enumerate().take(get_sites().first) {
             map.insert(order.order, order_index as OrderIndex);
             assert_eq!(orders[order_index].order, order.order);
         }
         map
     }
 
+    /// Recomputes everything we keep track of incrementally and compares it to what is stored.
+    /// Returns a description of the first thing that doesn't match.
+    ///
+    /// This is slow, it walks over the whole solution. Only use it for testing and debugging.
+    pub fn check_consistency(&self) -> Result<(), String> {
+        let orders = get_orders();
+        let sites = get_sites();
+
+        // the days on which each order is actually driven, in the same format as the OrderFlags
+        let mut found_flags = vec![0u8; orders.len()];
+
+        for truck_enum in [TruckEnum::Truck1, TruckEnum::Truck2] {
+            for day_enum in DayEnum::ALL {
+                for time_of_day in [TimeOfDay::Morning, TimeOfDay::Afternoon] {
+                    let route = self.get_truck(truck_enum).get(day_enum).get(time_of_day);
+                    let place = format!("{truck_enum:?} {day_enum:?} {time_of_day}");
+                    let lv = &route.linked_vector;
+
+                    let head = lv.get_head_index().and_then(|head| lv.get_value(head));
+                    let tail = lv.get_tail_index().and_then(|tail| lv.get_value(tail));
+                    if head != Some(&sites.home_depot(truck_enum)) {
+                        return Err(format!("{place}: the route does not start at the home depot of the truck"));
+                    }
+                    if !tail.is_some_and(|tail| sites.dump_sites.contains(tail)) {
+                        return Err(format!("{place}: the route does not end at a dump site"));
+                    }
+                    route.check_correctness_time().map_err(|error| format!("{place}: {error}"))?;
+                    route.check_correctness_trash().map_err(|error| format!("{place}: {error}"))?;
+                    route.check_correctness_distance().map_err(|error| format!("{place}: {error}"))?;
+
+                    for (node_i, order_i) in lv.iter() {
+                        if Some(node_i) == lv.get_head_index() || Some(node_i) == lv.get_tail_index() {
+                            continue;
+                        }
+                        if sites.is_site(*order_i) {
+                            if !sites.dump_sites.contains(order_i) {
+                                return Err(format!("{place}: a depot that isn't a dump site is in the middle of the route"));
+                            }
+                            continue;
+                        }
+                        let day_flag = OrderFlags::day_to_flags(day_enum);
+                        if found_flags[*order_i] & day_flag != 0 {
+                            return Err(format!("{place}: order {order_i} is driven twice on the same day"));
+                        }
+                        found_flags[*order_i] |= day_flag;
+                    }
+                }
+            }
+        }
+
+        let mut unfilled_counts = vec![0u32; orders.len()];
+        for (_, order_i) in self.unfilled_orders.iter() {
+            unfilled_counts[*order_i] += 1;
+        }
+
+        for (order_i, order) in orders.iter().enumerate().take(sites.first) {
+            let stored_flags = self.order_flags.get_flag(order_i);
+            if stored_flags != found_flags[order_i] {
+                return Err(format!("order {order_i}: the order flags say {stored_flags:05b} but the routes say {:05b}", found_flags[order_i]));
+            }
+            let filled = found_flags[order_i].count_ones();
+            if filled != 0 && filled != order.frequency as u32 {
+                return Err(format!("order {order_i}: driven {filled} times, but it has a frequency of {}", order.frequency as u32));
+            }
+            if filled != 0 && !OrderFlags::is_allowed_pattern(found_flags[order_i], &order.allowed_patterns) {
+                return Err(format!("order {order_i}: driven on {:05b}, which is not one of its allowed day patterns", found_flags[order_i]));
+            }
+            let expected_unfilled = if filled == 0 { 1 } else { 0 };
+            if unfilled_counts[order_i] != expected_unfilled {
+                return Err(format!("order {order_i}: in the unfilled orders {} times, but it should be in there {expected_unfilled} times", unfilled_counts[order_i]));
+            }
+        }
+        if unfilled_counts[sites.first..].iter().any(|count| *count != 0) {
+            return Err("a depot or dump site is in the unfilled orders".to_string());
+        }
+
+        let calculated_score = calculate_score(self, &self.order_flags);
+        if self.score != calculated_score {
+            return Err(format!("stored score {} but the solution scores {calculated_score}", self.score));
+        }
+        Ok(())
+    }
+
     pub fn get_truck(&self, truck_enum: TruckEnum) -> &Week {
         match truck_enum {
             TruckEnum::Truck1 => {&self.truck1}
@@ -1,6 +1,8 @@
 use super::day::Day;
 use crate::resource::Time;
 use crate::simulated_annealing::route::Route;
+use crate::simulated_annealing::simulated_annealing::TruckEnum;
+use serde::{Deserialize, Serialize};
 use rand::{
     Rng,
     distr::{Distribution, StandardUniform},
@@ -15,7 +17,7 @@ pub struct Week {
     friday: Day,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, PartialOrd, Ord)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum DayEnum {
     Monday,
     Tuesday,
@@ -23,6 +25,16 @@ pub enum DayEnum {
     Thursday,
     Friday,
 }
+impl DayEnum {
+    /// Every day we plan, from Monday to Friday
+    pub const ALL: [DayEnum; 5] = [
+        DayEnum::Monday,
+        DayEnum::Tuesday,
+        DayEnum::Wednesday,
+        DayEnum::Thursday,
+        DayEnum::Friday,
+    ];
+}
 // This makes it easier to get a random day
 impl Distribution<DayEnum> for StandardUniform {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> DayEnum {
@@ -36,13 +48,14 @@ impl Distribution<DayEnum> for StandardUniform {
     }
 }
 impl Week {
-    pub fn new() -> Self {
+    /// An empty week for the truck, every route goes from its home depot to the closest dump site.
+    pub fn new(truck: TruckEnum) -> Self {
         Week {
-            monday: Day::new(),
-            tuesday: Day::new(),
-            wednesday: Day::new(),
-            thursday: Day::new(),
-            friday: Day::new(),
+            monday: Day::new(truck),
+            tuesday: Day::new(truck),
+            wednesday: Day::new(truck),
+            thursday: Day::new(truck),
+            friday: Day::new(truck),
         }
     }
     pub fn get_random<R>(&self, rng: &mut R) -> (&Day, DayEnum)
@@ -118,9 +131,3 @@ impl Week {
         ]
     }
 }
-
-impl Default for Week {
-    fn default() -> Self {
-        Self::new()
-    }
-}
@@ -0,0 +1,40 @@
+//! Searches with the workload balance in the score. The data files leave it out,
+//! so the objective is set before anything reads it, which needs its own test binary.
+//! The objective test does the same with the max instead of the variance.
+
+mod common;
+
+use common::{config, init, run_and_check, set_orders};
+use route_finder::OBJECTIVE;
+use route_finder::parser::parse_orderfile;
+use route_finder::simulated_annealing::balance::DayTimes;
+use route_finder::simulated_annealing::objective::{BalanceMeasure, Objective};
+use route_finder::simulated_annealing::score_calculator::{ScoreBreakdown, calculate_balance_cost, calculate_score};
+use route_finder::simulated_annealing::simulated_annealing::SEARCH_WEIGHTS;
+
+fn setup() {
+    set_orders(parse_orderfile().unwrap());
+    let mut objective = Objective::time_only();
+    objective.balance_measure = BalanceMeasure::Variance;
+    objective.minutes_per_day_balance = 20.0;
+    objective.minutes_per_truck_balance = 5.0;
+    OBJECTIVE.set(objective).ok();
+}
+
+#[test]
+fn every_move_computes_the_balance() {
+    init(setup);
+    let solution = run_and_check(0, config(50_000.0), SEARCH_WEIGHTS, |step| {
+        assert_eq!(
+            DayTimes::of(&step.before.truck1, &step.before.truck2) + step.evaluation.day_times,
+            DayTimes::of(&step.after.truck1, &step.after.truck2),
+            "{step}: evaluate did not match the real change in day times"
+        );
+        assert_eq!(step.after.score, calculate_score(step.after, &step.after.order_flags), "{step}");
+    });
+
+    assert!(calculate_balance_cost(&solution) > 0);
+    let breakdown = ScoreBreakdown::new(&solution);
+    assert_eq!(breakdown.balance_cost, calculate_balance_cost(&solution));
+    assert_eq!(breakdown.total, solution.score);
+}
@@ -0,0 +1,102 @@
+//! Resuming from a checkpoint. Just like move_consistency this needs the real frequencies,
+//! so it gets its own test binary with the normal order file.
+
+mod common;
+
+use common::init_from_files;
+use rand::SeedableRng;
+use rand::prelude::SmallRng;
+use route_finder::printer::checkpoint::Checkpoint;
+use route_finder::printer::json::SolutionFile;
+use route_finder::simulated_annealing::neighbor_chooser::MOVE_COUNT;
+use route_finder::simulated_annealing::simulated_annealing::{SimulatedAnnealing, SimulatedAnnealingConfig};
+use route_finder::simulated_annealing::solution::Solution;
+
+fn search(seed: u64, temp: f32) -> SimulatedAnnealing {
+    init_from_files();
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let config = SimulatedAnnealingConfig {
+        seed: Some(seed),
+        ..SimulatedAnnealingConfig::headless(temp, 10.0, 5_000, 0.99)
+    };
+    SimulatedAnnealing::new(&mut rng, config)
+}
+
+#[test]
+fn checkpoints_leave_the_search_alone() {
+    let mut checkpointed = search(7, 50_000.0);
+    let mut rng = SmallRng::seed_from_u64(7);
+    let mut solution = Solution::new();
+    checkpointed.run_steps(&mut rng, &mut solution, 20_000);
+    checkpointed.checkpoint(&solution);
+    checkpointed.run_steps(&mut rng, &mut solution, 20_000);
+
+    let mut uninterrupted = search(7, 50_000.0);
+    let mut uninterrupted_rng = SmallRng::seed_from_u64(7);
+    let mut uninterrupted_solution = Solution::new();
+    uninterrupted.run_steps(&mut uninterrupted_rng, &mut uninterrupted_solution, 40_000);
+
+    assert_eq!(solution.score, uninterrupted_solution.score);
+    assert_eq!(
+        SolutionFile::new(&solution, Default::default()),
+        SolutionFile::new(&uninterrupted_solution, Default::default())
+    );
+    assert_eq!(checkpointed.statistics, uninterrupted.statistics);
+}
+
+#[test]
+fn resuming_a_checkpoint_twice_makes_the_same_moves() {
+    let mut original = search(7, 50_000.0);
+    let mut rng = SmallRng::seed_from_u64(7);
+    let mut solution = Solution::new();
+    original.run_steps(&mut rng, &mut solution, 20_000);
+
+    // through JSON, like it would be on disk
+    let checkpoint = original.checkpoint(&solution);
+    let checkpoint: Checkpoint = serde_json::from_str(&serde_json::to_string(&checkpoint).unwrap()).unwrap();
+
+    // different seeds and temperatures, which the checkpoint has to override
+    let resume = |seed| {
+        let mut resumed = search(seed, 1.0);
+        let (mut resumed_rng, mut resumed_solution) = resumed.restore(&checkpoint).unwrap();
+        assert_eq!(resumed.parameters(), original.parameters());
+        assert_eq!(resumed.seed(), original.seed());
+        assert_eq!(resumed_solution.score, solution.score);
+        resumed.run_steps(&mut resumed_rng, &mut resumed_solution, 20_000);
+        resumed_solution.check_consistency().unwrap();
+        (resumed.checkpoint(&resumed_solution), resumed_solution)
+    };
+    let (first_checkpoint, first) = resume(8);
+    let (second_checkpoint, second) = resume(9);
+
+    assert_eq!(first.score, second.score);
+    assert_eq!(first_checkpoint.current, second_checkpoint.current);
+    assert_eq!(first_checkpoint.statistics, second_checkpoint.statistics);
+    assert_eq!(first_checkpoint.temp, second_checkpoint.temp);
+    assert_eq!(first_checkpoint.step_count, checkpoint.step_count + 20_000);
+}
+
+#[test]
+fn checkpoints_from_before_the_last_moves_can_be_resumed() {
+    let mut original = search(9, 50_000.0);
+    let mut rng = SmallRng::seed_from_u64(9);
+    let mut solution = Solution::new();
+    original.run_steps(&mut rng, &mut solution, 5_000);
+
+    let mut json = serde_json::to_value(original.checkpoint(&solution)).unwrap();
+    // back when there were 9 moves, before changing dump sites and unload stops
+    for weights in ["search_weights", "perturbation_weights"] {
+        json["parameters"][weights].as_array_mut().unwrap().truncate(9);
+    }
+    for counts in ["tried", "accepted", "improved"] {
+        json["statistics"][counts].as_array_mut().unwrap().truncate(9);
+    }
+
+    let checkpoint: Checkpoint = serde_json::from_value(json).unwrap();
+    let mut resumed = search(10, 1.0);
+    let (mut resumed_rng, mut resumed_solution) = resumed.restore(&checkpoint).unwrap();
+    assert_eq!(resumed.statistics.tried[..9], original.statistics.tried[..9]);
+    assert_eq!(resumed.statistics.tried[9..], [0; MOVE_COUNT - 9]);
+    resumed.run_steps(&mut resumed_rng, &mut resumed_solution, 5_000);
+    resumed_solution.check_consistency().unwrap();
+}
@@ -0,0 +1,105 @@
+//! What the integration tests share. The global statics can only be set once per process,
+//! so every test binary sets up its own data with `init`, and then searches with `run_and_check`.
+// not every test binary uses everything in here
+#![allow(dead_code)]
+
+use std::fmt::{Display, Formatter};
+use std::sync::Once;
+
+use rand::SeedableRng;
+use rand::prelude::SmallRng;
+use route_finder::parser::parse_distance_matrix;
+use route_finder::resource::{Company, Sites, TravelTable};
+use route_finder::simulated_annealing::neighbor_chooser::MOVE_COUNT;
+use route_finder::simulated_annealing::neighbor_lists::{NEIGHBOR_COUNT, NeighborLists};
+use route_finder::simulated_annealing::neighbor_move::evaluation::Evaluation;
+use route_finder::simulated_annealing::score_calculator::calculate_score;
+use route_finder::simulated_annealing::simulated_annealing::{SimulatedAnnealing, SimulatedAnnealingConfig};
+use route_finder::simulated_annealing::solution::Solution;
+use route_finder::{DISTANCE_MATRIX, NEIGHBOR_LISTS, ORDERS, SITES, TRAVEL_TABLE, get_distance_matrix, get_orders, get_travel_table, initialize};
+
+pub const STEPS: u32 = 20_000;
+
+static INIT: Once = Once::new();
+
+/// Runs `setup` the first time it's called in this test binary, and does nothing after that.
+pub fn init(setup: impl FnOnce()) {
+    INIT.call_once(setup);
+}
+
+/// Everything from the data files.
+pub fn init_from_files() {
+    init(|| initialize().unwrap());
+}
+
+/// Sets `orders` instead of the order file, with the distances and neighbour lists that go with them.
+/// The objective and the drivers are read from the data files when they are first used, if nothing else set them.
+pub fn set_orders(orders: Vec<Company>) {
+    ORDERS.set(orders.into()).ok();
+    DISTANCE_MATRIX.set(parse_distance_matrix().unwrap()).ok();
+    TRAVEL_TABLE.set(TravelTable::new(get_distance_matrix(), get_orders())).ok();
+    SITES.set(Sites::new(get_orders(), get_travel_table()).unwrap()).ok();
+    NEIGHBOR_LISTS.set(NeighborLists::new(get_travel_table(), get_orders(), NEIGHBOR_COUNT)).ok();
+}
+
+/// Anneals from `temp`, with a new temperature every STEPS steps.
+pub fn config(temp: f32) -> SimulatedAnnealingConfig {
+    SimulatedAnnealingConfig::headless(temp, 10.0, STEPS, 0.99)
+}
+
+/// An applied move, for the checks of `run_and_check`.
+pub struct Step<'a> {
+    pub seed: u64,
+    pub step: u32,
+    pub evaluation: &'a Evaluation,
+    pub before: &'a Solution,
+    pub after: &'a Solution,
+}
+
+/// To start the message of a failed check with
+impl Display for Step<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "seed {}, step {}", self.seed, self.step)
+    }
+}
+
+/// Does STEPS annealing steps with the given weights, and panics as soon as something doesn't add up.
+/// After every applied move, the evaluation has to match the real change in score and the solution has to be consistent.
+/// `check` is for what else the test wants to compare.
+pub fn run_and_check(
+    seed: u64,
+    config: SimulatedAnnealingConfig,
+    weights: [i32; MOVE_COUNT],
+    mut check: impl FnMut(&Step),
+) -> Solution {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut annealing = SimulatedAnnealing::new(&mut rng, config);
+    let mut solution = Solution::new();
+    solution.check_consistency().unwrap();
+
+    for step in 0..STEPS {
+        // skipped, like the search does
+        let Some((neighbor_move, end_of_step_info)) = annealing.choose_neighbor(&mut rng, weights, &mut solution) else {
+            continue;
+        };
+        let evaluation = neighbor_move.evaluate(&solution);
+        if !annealing.accept(evaluation.clone(), &mut rng) {
+            continue;
+        }
+
+        let before = solution.clone();
+        SimulatedAnnealing::apply_move(&mut solution, neighbor_move.as_ref(), end_of_step_info);
+        let step = Step { seed, step, evaluation: &evaluation, before: &before, after: &solution };
+
+        assert_eq!(
+            evaluation.cost,
+            calculate_score(&solution, &solution.order_flags) - calculate_score(&before, &before.order_flags),
+            "{step}: evaluate did not match the real change in score"
+        );
+        if let Err(inconsistency) = solution.check_consistency() {
+            panic!("{step}: {inconsistency}");
+        }
+        check(&step);
+    }
+    solution
+}
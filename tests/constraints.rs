@@ -0,0 +1,92 @@
+//! Searches with strict drivers, so the soft constraints get broken and the repair has something to do.
+//! The drivers are set before anything reads them, which needs its own test binary.
+
+mod common;
+
+use common::{config, init, run_and_check, set_orders};
+use route_finder::DRIVERS;
+use route_finder::parser::parse_orderfile;
+use route_finder::resource::MINUTE;
+use route_finder::simulated_annealing::constraints::{Constraint, ConstraintMode, ConstraintModes, PenaltyWeights, Violations, repair};
+use route_finder::simulated_annealing::day::TimeOfDay;
+use route_finder::simulated_annealing::driver::Driver;
+use route_finder::simulated_annealing::score_calculator::calculate_score;
+use route_finder::simulated_annealing::simulated_annealing::{SEARCH_WEIGHTS, SimulatedAnnealingConfig, TruckEnum};
+use route_finder::simulated_annealing::solution::Solution;
+use route_finder::simulated_annealing::week::DayEnum;
+
+fn setup() {
+    set_orders(parse_orderfile().unwrap());
+    let strict = Driver {
+        name: "Streng".to_string(),
+        break_after: 40 * MINUTE,
+        break_length: 15 * MINUTE,
+        max_continuous_driving: 30 * MINUTE,
+        max_weekly_time: 20 * 60 * MINUTE,
+    };
+    DRIVERS.set([strict.clone(), strict]).ok();
+}
+
+/// Searches hot, so plenty of moves break the soft constraints.
+fn search(constraint_modes: ConstraintModes, mut check: impl FnMut(&Violations)) -> Solution {
+    init(setup);
+    let config = SimulatedAnnealingConfig { constraint_modes, ..config(500_000.0) };
+    run_and_check(3, config, SEARCH_WEIGHTS, |step| check(&Violations::of_evaluation(step.evaluation)))
+}
+
+#[test]
+fn hard_constraints_never_get_worse() {
+    let mut constraint_modes = ConstraintModes::default();
+    constraint_modes.set(Constraint::ContinuousDriving, ConstraintMode::Hard);
+    constraint_modes.set(Constraint::WeeklyTime, ConstraintMode::Hard);
+
+    let solution = search(constraint_modes, |changes| {
+        assert!(changes.get(Constraint::ContinuousDriving) <= 0);
+        assert!(changes.get(Constraint::WeeklyTime) <= 0);
+    });
+    let violations = Violations::of(&solution);
+    assert_eq!(violations.get(Constraint::ContinuousDriving), 0);
+    assert_eq!(violations.get(Constraint::WeeklyTime), 0);
+}
+
+#[test]
+fn repair_makes_the_plan_feasible() {
+    let mut solution = search(ConstraintModes::default(), |_| {});
+    assert!(!Violations::of(&solution).is_feasible(), "nothing to repair, the drivers should be stricter");
+
+    let repair = repair(&mut solution, &PenaltyWeights::default());
+
+    assert_eq!(repair.failure, None);
+    assert!(!repair.removed.is_empty());
+    assert!(Violations::of(&solution).is_feasible());
+    if let Err(inconsistency) = solution.check_consistency() {
+        panic!("{inconsistency}");
+    }
+    assert_eq!(solution.score, calculate_score(&solution, &solution.order_flags));
+    for order_index in repair.removed {
+        assert!(solution.unfilled_orders.iter().any(|(_, unfilled)| *unfilled == order_index));
+    }
+}
+
+#[test]
+fn frozen_routes_cannot_be_repaired() {
+    let mut solution = search(ConstraintModes::default(), |_| {});
+    let violations = Violations::of(&solution);
+    assert!(!violations.is_feasible(), "nothing to repair, the drivers should be stricter");
+    for truck in [TruckEnum::Truck1, TruckEnum::Truck2] {
+        for day in DayEnum::ALL {
+            for time_of_day in [TimeOfDay::Morning, TimeOfDay::Afternoon] {
+                solution.locks.set_frozen(truck, day, time_of_day, true);
+            }
+        }
+    }
+    let score = solution.score;
+
+    let repair = repair(&mut solution, &PenaltyWeights::default());
+
+    assert!(repair.removed.is_empty());
+    let failure = repair.failure.expect("nothing can be taken out, so the plan stays broken");
+    assert!(failure.contains(&violations.broken_names()), "{failure}");
+    assert_eq!(solution.score, score);
+    assert_eq!(Violations::of(&solution), violations);
+}
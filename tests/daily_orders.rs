@@ -0,0 +1,76 @@
+//! Searches with some customers that are collected every weekday. The order file has none of them,
+//! so a few orders are made daily before they go into the global statics, which needs its own test binary.
+
+mod common;
+
+use common::{init, set_orders};
+use rand::SeedableRng;
+use rand::prelude::SmallRng;
+use route_finder::get_orders;
+use route_finder::parser::parse_orderfile;
+use route_finder::printer::json::{RunInfo, SolutionFile};
+use route_finder::resource::{Frequency, Time};
+use route_finder::simulated_annealing::manual_edit::ManualEdit;
+use route_finder::simulated_annealing::route::OrderIndex;
+use route_finder::simulated_annealing::score_calculator::calculate_score;
+use route_finder::simulated_annealing::simulated_annealing::{SimulatedAnnealing, SimulatedAnnealingConfig};
+use route_finder::simulated_annealing::solution::Solution;
+
+fn setup() {
+    let mut order_vec = parse_orderfile().unwrap();
+    let dropoff = order_vec.len() - 1;
+    for order in order_vec.iter_mut().take(dropoff).step_by(40) {
+        order.frequency = Frequency::FiveTimes;
+        order.allowed_patterns = Frequency::FiveTimes.default_patterns().to_vec();
+        order.penalty = 3 * Frequency::FiveTimes as Time * order.emptying_time;
+    }
+    set_orders(order_vec);
+}
+
+fn daily_orders() -> Vec<OrderIndex> {
+    let orders = get_orders();
+    (0..orders.len() - 1).filter(|order_index| orders[*order_index].frequency as u8 == 5).collect()
+}
+
+fn annealed_solution() -> Solution {
+    init(setup);
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut annealing = SimulatedAnnealing::new(&mut rng, SimulatedAnnealingConfig::headless(50_000.0, 10.0, 10_000, 0.99));
+    let mut solution = Solution::new();
+    annealing.run_steps(&mut rng, &mut solution, 40_000);
+    solution
+}
+
+#[test]
+fn daily_orders_are_planned_on_every_day_or_not_at_all() {
+    let solution = annealed_solution();
+    solution.check_consistency().unwrap();
+    let planned: Vec<u8> = daily_orders()
+        .into_iter()
+        .map(|order_index| solution.order_flags.get_flag(order_index))
+        .filter(|flags| *flags != 0)
+        .collect();
+    assert!(!planned.is_empty());
+    assert!(planned.iter().all(|flags| *flags == 0b11111));
+}
+
+#[test]
+fn daily_orders_can_be_removed_inserted_and_saved() {
+    let mut solution = annealed_solution();
+    let order = daily_orders()
+        .into_iter()
+        .find(|order_index| solution.order_flags.get_flag(*order_index) != 0)
+        .unwrap();
+
+    for edit in [ManualEdit::Remove { order }, ManualEdit::Insert { order, after: None }] {
+        let score_before = calculate_score(&solution, &solution.order_flags);
+        let evaluation = edit.apply(&mut solution).unwrap();
+        solution.check_consistency().unwrap();
+        assert_eq!(evaluation.cost, calculate_score(&solution, &solution.order_flags) - score_before);
+    }
+    assert_eq!(solution.order_flags.get_flag(order), 0b11111);
+
+    let json = serde_json::to_string(&SolutionFile::new(&solution, RunInfo::default())).unwrap();
+    let loaded = serde_json::from_str::<SolutionFile>(&json).unwrap().to_solution().unwrap();
+    assert_eq!(loaded.order_flags.get_flag(order), 0b11111);
+}
@@ -0,0 +1,62 @@
+//! Searches with customer-specific day patterns instead of the defaults of their frequency.
+//! The orders are changed before they go into the global statics, so this needs its own test binary.
+
+mod common;
+
+use common::{config, init, run_and_check, set_orders};
+use route_finder::get_orders;
+use route_finder::parser::parse_orderfile;
+use route_finder::resource::Frequency;
+use route_finder::simulated_annealing::neighbor_chooser::MOVE_COUNT;
+use route_finder::simulated_annealing::simulated_annealing::SEARCH_WEIGHTS;
+use route_finder::simulated_annealing::solution::Solution;
+
+/// Twice a week, but closed on Monday
+const NOT_ON_MONDAY: [u8; 2] = [0b01001, 0b00101];
+/// Three times a week, with a second pattern so these orders can be shifted too
+const THRICE: [u8; 2] = [0b10101, 0b01011];
+
+fn setup() {
+    let mut order_vec = parse_orderfile().unwrap();
+    for order in &mut order_vec {
+        match order.frequency {
+            Frequency::Twice => order.allowed_patterns = NOT_ON_MONDAY.to_vec(),
+            Frequency::Thrice => order.allowed_patterns = THRICE.to_vec(),
+            _ => {}
+        }
+    }
+    set_orders(order_vec);
+}
+
+fn run(seed: u64, weights: [i32; MOVE_COUNT]) -> Solution {
+    init(setup);
+    run_and_check(seed, config(50_000.0), weights, |_| {})
+}
+
+#[test]
+fn orders_are_only_planned_on_their_own_patterns() {
+    let solution = run(0, SEARCH_WEIGHTS);
+    let orders = get_orders();
+    let planned_on = |frequency: Frequency| {
+        (0..orders.len() - 1)
+            .filter(move |order_index| orders[*order_index].frequency as u8 == frequency as u8)
+            .map(|order_index| solution.order_flags.get_flag(order_index))
+            .filter(|flags| *flags != 0)
+            .collect::<Vec<u8>>()
+    };
+    let twice = planned_on(Frequency::Twice);
+    assert!(!twice.is_empty());
+    assert!(twice.iter().all(|flags| NOT_ON_MONDAY.contains(flags)));
+    assert!(planned_on(Frequency::Thrice).iter().all(|flags| THRICE.contains(flags)));
+}
+
+#[test]
+fn shifting_between_days_follows_the_patterns() {
+    // Mostly day shifts, with some adds and removes to give them something to work with
+    let mut weights = [0; MOVE_COUNT];
+    weights[0] = 1;
+    weights[1] = 1;
+    weights[4] = 4;
+    weights[8] = 4;
+    run(1, weights);
+}
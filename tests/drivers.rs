@@ -0,0 +1,64 @@
+//! Searches with strict driving time rules, so routes need breaks and go over the limits.
+//! The data file has the legal rules, which the short routes of the search hardly ever run into,
+//! so the drivers are set before anything reads them, which needs its own test binary.
+
+mod common;
+
+use common::{config, init, run_and_check, set_orders};
+use route_finder::DRIVERS;
+use route_finder::parser::parse_orderfile;
+use route_finder::printer::manifest::build_manifest;
+use route_finder::resource::MINUTE;
+use route_finder::simulated_annealing::balance::DayTimes;
+use route_finder::simulated_annealing::driver::Driver;
+use route_finder::simulated_annealing::score_calculator::ScoreBreakdown;
+use route_finder::simulated_annealing::simulated_annealing::{SEARCH_WEIGHTS, TruckEnum};
+use route_finder::simulated_annealing::solution::Solution;
+
+fn setup() {
+    set_orders(parse_orderfile().unwrap());
+    let strict = Driver {
+        name: "Streng".to_string(),
+        break_after: 40 * MINUTE,
+        break_length: 15 * MINUTE,
+        max_continuous_driving: 30 * MINUTE,
+        max_weekly_time: 30 * 60 * MINUTE,
+    };
+    let legal = Driver::legal();
+    DRIVERS.set([strict, legal]).ok();
+}
+
+fn continuous_overflow(solution: &Solution) -> i32 {
+    solution.truck1.iter().chain(solution.truck2.iter()).map(|route| route.rests.continuous_overflow).sum()
+}
+
+fn weekly_overflow(solution: &Solution) -> i32 {
+    DayTimes::of(&solution.truck1, &solution.truck2).weekly_overflow()
+}
+
+#[test]
+fn every_move_computes_the_breaks_and_overflows() {
+    init(setup);
+    let solution = run_and_check(0, config(50_000.0), SEARCH_WEIGHTS, |step| {
+        assert_eq!(
+            step.evaluation.continuous_driving_overflow_delta,
+            continuous_overflow(step.after) - continuous_overflow(step.before),
+            "{step}: evaluate did not match the real change in continuous driving"
+        );
+        assert_eq!(
+            step.evaluation.weekly_overflow_delta,
+            weekly_overflow(step.after) - weekly_overflow(step.before),
+            "{step}: evaluate did not match the real change in weekly overflow"
+        );
+    });
+
+    let breakdown = ScoreBreakdown::new(&solution);
+    assert_eq!(breakdown.total, solution.score);
+    assert!(breakdown.break_time > 0);
+    assert_eq!(breakdown.continuous_driving_overflow, continuous_overflow(&solution));
+
+    // The manifest has the same breaks as the routes
+    let mut stops = build_manifest(&solution.truck1, TruckEnum::Truck1);
+    stops.extend(build_manifest(&solution.truck2, TruckEnum::Truck2));
+    assert_eq!(stops.iter().map(|stop| stop.break_time).sum::<i32>(), breakdown.break_time);
+}
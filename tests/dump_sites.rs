@@ -0,0 +1,139 @@
+//! Searches with a second depot and a second dump site. The site file only has Maarheeze,
+//! so the extra sites are added before they go into the global statics, which needs its own test binary.
+
+mod common;
+
+use common::{config, init, run_and_check, set_orders};
+use route_finder::get_sites;
+use route_finder::parser::parse_orderfile;
+use route_finder::printer::json::{RunInfo, SolutionFile};
+use route_finder::resource::{Company, Site};
+use route_finder::simulated_annealing::neighbor_chooser::MOVE_COUNT;
+use route_finder::simulated_annealing::simulated_annealing::{SEARCH_WEIGHTS, TruckEnum};
+use route_finder::simulated_annealing::solution::Solution;
+
+/// Hot enough that the first customer of a trip gets accepted, which costs a drive from the second depot
+const START_TEMPERATURE: f32 = 500_000.0;
+
+/// A site at the same place as one of the customers
+fn site_at(order: &Company, place: &str, site: Site) -> Company {
+    Company {
+        order: 0,
+        place: place.to_string(),
+        container_count: 0,
+        container_volume: 0,
+        emptying_time: 0,
+        total_container_volume: 0,
+        penalty: 0,
+        allowed_patterns: vec![],
+        site: Some(site),
+        ..order.clone()
+    }
+}
+
+fn setup() {
+    let mut order_vec = parse_orderfile().unwrap();
+    let maarheeze = order_vec.last_mut().unwrap();
+    maarheeze.site.as_mut().unwrap().home_of = vec![TruckEnum::Truck1];
+
+    let depot = site_at(&order_vec[100], "Tweede depot", Site { is_dump: false, home_of: vec![TruckEnum::Truck2] });
+    let dump_site = site_at(&order_vec[300], "Tweede stort", Site { is_dump: true, home_of: vec![] });
+    order_vec.push(depot);
+    order_vec.push(dump_site);
+    set_orders(order_vec);
+}
+
+fn capacity_overflow(solution: &Solution) -> i32 {
+    solution.truck1.iter().chain(solution.truck2.iter()).map(|route| route.capacity_overflow() as i32).sum()
+}
+
+fn run(seed: u64, weights: [i32; MOVE_COUNT]) -> Solution {
+    init(setup);
+    run_and_check(seed, config(START_TEMPERATURE), weights, |step| {
+        assert_eq!(
+            step.evaluation.capacity_overflow_delta,
+            capacity_overflow(step.after) - capacity_overflow(step.before),
+            "{step}: evaluate did not match the real change in capacity overflow"
+        );
+    })
+}
+
+#[test]
+fn trucks_start_at_their_own_depot() {
+    init(setup);
+    let sites = get_sites();
+    assert_eq!(sites.dump_sites.len(), 2);
+    assert_ne!(sites.home_depot(TruckEnum::Truck1), sites.home_depot(TruckEnum::Truck2));
+
+    let solution = run(0, SEARCH_WEIGHTS);
+    for truck in [TruckEnum::Truck1, TruckEnum::Truck2] {
+        let route = solution.get_truck(truck).iter().next().unwrap();
+        assert_eq!(route.depot(), sites.home_depot(truck));
+    }
+}
+
+#[test]
+fn trips_change_dump_site_and_keep_it_when_saved() {
+    // Mostly dump site changes, with adds and removes to give them something to work with
+    let mut weights = [0; MOVE_COUNT];
+    weights[0] = 2;
+    weights[1] = 1;
+    weights[9] = 4;
+    let mut solution = run(1, weights);
+
+    // Whether the search ends up using the other dump site depends on the distances, so make sure one trip does
+    let sites = get_sites();
+    let truck = TruckEnum::Truck1;
+    let other = *sites.dump_sites.iter().find(|dump_site| **dump_site != sites.default_dump_site(truck)).unwrap();
+    let route = solution
+        .get_truck_mut(truck)
+        .get_all_as_mut()
+        .into_iter()
+        .find(|route| !route.is_empty() && route.dump_site() != other)
+        .unwrap();
+    let score_change = route.apply_change_dump_site(other);
+    solution.get_truck_mut(truck).recalculate_total_time();
+    solution.score += score_change;
+    solution.check_consistency().unwrap();
+
+    let json = serde_json::to_string(&SolutionFile::new(&solution, RunInfo::default())).unwrap();
+    let loaded = serde_json::from_str::<SolutionFile>(&json).unwrap().to_solution().unwrap();
+    for truck in [TruckEnum::Truck1, TruckEnum::Truck2] {
+        let dump_sites = |solution: &Solution| {
+            solution
+                .get_truck(truck)
+                .iter()
+                .map(|route| route.dump_site())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(dump_sites(&loaded), dump_sites(&solution));
+    }
+}
+
+#[test]
+fn unload_stops_split_the_load_and_are_saved() {
+    // Adds without removes, so the routes fill up until unloading halfway starts to pay off
+    let mut weights = [0; MOVE_COUNT];
+    weights[0] = 4;
+    weights[2] = 4;
+    weights[3] = 2;
+    weights[10] = 2;
+    weights[11] = 1;
+    let solution = run(2, weights);
+
+    let with_unload_stops = solution.truck1.iter().chain(solution.truck2.iter()).filter(|route| route.unload_stops > 0).count();
+    assert!(with_unload_stops > 0);
+
+    let json = serde_json::to_string(&SolutionFile::new(&solution, RunInfo::default())).unwrap();
+    let loaded = serde_json::from_str::<SolutionFile>(&json).unwrap().to_solution().unwrap();
+    let sequences = |solution: &Solution| {
+        solution
+            .truck1
+            .iter()
+            .chain(solution.truck2.iter())
+            .map(|route| route.linked_vector.iter().map(|(_, order_index)| *order_index).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sequences(&loaded), sequences(&solution));
+    assert_eq!(capacity_overflow(&loaded), capacity_overflow(&solution));
+}
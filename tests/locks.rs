@@ -0,0 +1,114 @@
+//! Searches on a solution with locked orders and frozen routes. These need the real frequencies,
+//! so just like move_consistency they get their own test binary with the normal order file.
+
+mod common;
+
+use common::init_from_files;
+use rand::SeedableRng;
+use rand::prelude::SmallRng;
+use route_finder::get_orders;
+use route_finder::printer::json::{RunInfo, SolutionFile};
+use route_finder::simulated_annealing::day::TimeOfDay;
+use route_finder::simulated_annealing::locks::OrderLock;
+use route_finder::simulated_annealing::manual_edit::ManualEdit;
+use route_finder::simulated_annealing::neighbor_move::route_position::RoutePosition;
+use route_finder::simulated_annealing::route::OrderIndex;
+use route_finder::simulated_annealing::simulated_annealing::{SimulatedAnnealing, SimulatedAnnealingConfig, TruckEnum};
+use route_finder::simulated_annealing::solution::Solution;
+use route_finder::simulated_annealing::week::DayEnum;
+
+fn annealing(seed: u64) -> (SimulatedAnnealing, SmallRng) {
+    init_from_files();
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let annealing = SimulatedAnnealing::new(&mut rng, SimulatedAnnealingConfig::headless(50_000.0, 10.0, 10_000, 0.99));
+    (annealing, rng)
+}
+
+fn annealed_solution() -> Solution {
+    let (mut annealing, mut rng) = annealing(3);
+    let mut solution = Solution::new();
+    annealing.run_steps(&mut rng, &mut solution, 30_000);
+    solution
+}
+
+/// Where the order is visited: the truck, day and trip of every visit.
+fn visits(solution: &Solution, order_index: OrderIndex) -> Vec<(TruckEnum, DayEnum, TimeOfDay)> {
+    RoutePosition::find_order(solution, order_index)
+        .into_iter()
+        .map(|position| (position.truck, position.day, position.time_of_day))
+        .collect()
+}
+
+fn customers(solution: &Solution, truck: TruckEnum, day: DayEnum, time_of_day: TimeOfDay) -> Vec<OrderIndex> {
+    let route = solution.get_truck(truck).get(day).get(time_of_day);
+    route.linked_vector.iter().map(|(_, order_index)| *order_index).collect()
+}
+
+/// The first planned orders, one for every lock level but Free.
+fn lock_some_orders(solution: &mut Solution) -> Vec<(OrderIndex, OrderLock)> {
+    let planned = (0..get_orders().len() - 1).filter(|order_index| solution.order_flags.get_flag(*order_index) != 0);
+    let locked: Vec<(OrderIndex, OrderLock)> = planned.zip([OrderLock::Day, OrderLock::Truck, OrderLock::Position]).collect();
+    for (order_index, lock) in &locked {
+        solution.locks.set_order(*order_index, *lock);
+    }
+    locked
+}
+
+#[test]
+fn search_leaves_locked_orders_and_frozen_routes_alone() {
+    let mut solution = annealed_solution();
+    let locked = lock_some_orders(&mut solution);
+    let frozen = (TruckEnum::Truck1, DayEnum::Tuesday, TimeOfDay::Morning);
+    solution.locks.set_frozen(frozen.0, frozen.1, frozen.2, true);
+
+    let before = solution.clone();
+    let (mut annealing, mut rng) = annealing(4);
+    annealing.run_steps(&mut rng, &mut solution, 50_000);
+    solution.check_consistency().unwrap();
+
+    assert_eq!(customers(&solution, frozen.0, frozen.1, frozen.2), customers(&before, frozen.0, frozen.1, frozen.2));
+    for (order_index, lock) in locked {
+        let (visits_before, visits_after) = (visits(&before, order_index), visits(&solution, order_index));
+        let days = |visits: &[(TruckEnum, DayEnum, TimeOfDay)]| visits.iter().map(|visit| visit.1).collect::<Vec<_>>();
+        let trucks = |visits: &[(TruckEnum, DayEnum, TimeOfDay)]| visits.iter().map(|visit| (visit.0, visit.1)).collect::<Vec<_>>();
+        match lock {
+            OrderLock::Day => assert_eq!(days(&visits_after), days(&visits_before)),
+            OrderLock::Truck => assert_eq!(trucks(&visits_after), trucks(&visits_before)),
+            OrderLock::Position => assert_eq!(visits_after, visits_before),
+            OrderLock::Free => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn manual_edits_leave_locked_orders_alone() {
+    let mut solution = annealed_solution();
+    let locked = lock_some_orders(&mut solution);
+    let (order, _) = locked[0];
+    assert!(ManualEdit::Remove { order }.apply(&mut solution).is_err());
+
+    solution.locks.set_order(order, OrderLock::Free);
+    let stop = RoutePosition::find_order(&solution, order)[0];
+    solution.locks.set_frozen(stop.truck, stop.day, stop.time_of_day, true);
+    assert!(ManualEdit::Remove { order }.apply(&mut solution).is_err());
+
+    solution.locks.set_frozen(stop.truck, stop.day, stop.time_of_day, false);
+    ManualEdit::Remove { order }.apply(&mut solution).unwrap();
+    solution.check_consistency().unwrap();
+}
+
+#[test]
+fn locks_are_saved_with_the_solution() {
+    let mut solution = annealed_solution();
+    assert!(SolutionFile::new(&solution, RunInfo::default()).locks.is_empty());
+
+    lock_some_orders(&mut solution);
+    solution.locks.set_frozen(TruckEnum::Truck2, DayEnum::Friday, TimeOfDay::Afternoon, true);
+    let saved = SolutionFile::new(&solution, RunInfo::default());
+    assert_eq!(saved.locks.orders.len(), 3);
+    assert_eq!(saved.locks.frozen_routes.len(), 1);
+
+    let json = serde_json::to_string(&saved).unwrap();
+    let loaded = serde_json::from_str::<SolutionFile>(&json).unwrap().to_solution().unwrap();
+    assert_eq!(loaded.locks, solution.locks);
+}
@@ -0,0 +1,152 @@
+//! Edits made by hand on an annealed solution. These need the real frequencies,
+//! so just like move_consistency they get their own test binary with the normal order file.
+
+mod common;
+
+use common::init_from_files;
+use rand::SeedableRng;
+use rand::prelude::SmallRng;
+use route_finder::datastructures::linked_vectors::LinkedVector;
+use route_finder::get_orders;
+use route_finder::resource::Frequency;
+use route_finder::simulated_annealing::manual_edit::ManualEdit;
+use route_finder::simulated_annealing::neighbor_move::route_position::RoutePosition;
+use route_finder::simulated_annealing::route::OrderIndex;
+use route_finder::simulated_annealing::score_calculator::calculate_score;
+use route_finder::simulated_annealing::simulated_annealing::{SimulatedAnnealing, SimulatedAnnealingConfig, TruckEnum};
+use route_finder::simulated_annealing::solution::Solution;
+use route_finder::simulated_annealing::week::DayEnum;
+
+fn annealed_solution() -> Solution {
+    init_from_files();
+    let mut rng = SmallRng::seed_from_u64(11);
+    let mut annealing = SimulatedAnnealing::new(&mut rng, SimulatedAnnealingConfig::headless(50_000.0, 10.0, 10_000, 0.99));
+    let mut solution = Solution::new();
+    // stop early, so there are both filled and unfilled orders
+    annealing.run_steps(&mut rng, &mut solution, 30_000);
+    solution
+}
+
+/// Applies the edit, and checks that the evaluation was right and nothing got out of sync.
+fn apply_and_check(solution: &mut Solution, edit: ManualEdit) {
+    let score_before = solution.score;
+    let evaluation = edit.apply(solution).unwrap_or_else(|error| panic!("{edit:?}: {error}"));
+    assert_eq!(solution.score - score_before, evaluation.cost, "{edit:?}");
+    assert_eq!(solution.score, calculate_score(solution, &solution.order_flags), "{edit:?}");
+    solution.check_consistency().unwrap_or_else(|error| panic!("{edit:?}: {error}"));
+}
+
+fn planned_order_with(solution: &Solution, frequency: Frequency) -> OrderIndex {
+    (0..get_orders().len() - 1)
+        .find(|order_index| {
+            get_orders()[*order_index].frequency as u8 == frequency as u8 && solution.order_flags.get_flag(*order_index) != 0
+        })
+        .expect("the annealed solution has an order with this frequency")
+}
+
+fn head(solution: &Solution, position: RoutePosition) -> RoutePosition {
+    RoutePosition {
+        node_index: position.route(solution).linked_vector.get_head_index().unwrap(),
+        ..position
+    }
+}
+
+#[test]
+fn remove_and_insert_again() {
+    let mut solution = annealed_solution();
+    let order = planned_order_with(&solution, Frequency::Twice);
+
+    apply_and_check(&mut solution, ManualEdit::Remove { order });
+    assert_eq!(solution.order_flags.get_flag(order), 0);
+    assert!(ManualEdit::Remove { order }.apply(&mut solution).is_err());
+
+    apply_and_check(&mut solution, ManualEdit::Insert { order, after: None });
+    assert_eq!(RoutePosition::find_order(&solution, order).len(), 2);
+    assert!(ManualEdit::Insert { order, after: None }.apply(&mut solution).is_err());
+}
+
+#[test]
+fn insert_after_a_chosen_stop() {
+    let mut solution = annealed_solution();
+    let order = planned_order_with(&solution, Frequency::Thrice);
+    apply_and_check(&mut solution, ManualEdit::Remove { order });
+
+    let other = planned_order_with(&solution, Frequency::Once);
+    let stop = RoutePosition::find_order(&solution, other)[0];
+    let tuesday = head(&solution, RoutePosition { day: DayEnum::Tuesday, ..stop });
+    assert!(ManualEdit::Insert { order, after: Some(tuesday) }.apply(&mut solution).is_err());
+
+    let friday = head(&solution, RoutePosition { day: DayEnum::Friday, ..stop });
+    apply_and_check(&mut solution, ManualEdit::Insert { order, after: Some(friday) });
+    let visits = RoutePosition::find_order(&solution, order);
+    assert_eq!(visits.len(), 3);
+    assert!(visits.iter().any(|visit| visit.same_route(&friday)));
+}
+
+#[test]
+fn move_within_and_between_routes() {
+    let mut solution = annealed_solution();
+    let order = planned_order_with(&solution, Frequency::Once);
+    let stop = RoutePosition::find_order(&solution, order)[0];
+
+    // to the start of its own route, then to the start of another truck's route on the same day
+    let start = head(&solution, stop);
+    if stop.route(&solution).linked_vector.get_prev_index(stop.node_index) != Some(start.node_index) {
+        apply_and_check(&mut solution, ManualEdit::Move { stop, after: start });
+    }
+    let stop = RoutePosition::find_order(&solution, order)[0];
+    assert!(ManualEdit::Move { stop, after: start }.apply(&mut solution).is_err());
+
+    let other_truck = head(&solution, RoutePosition { truck: other(stop.truck), ..stop });
+    apply_and_check(&mut solution, ManualEdit::Move { stop, after: other_truck });
+    let stop = RoutePosition::find_order(&solution, order)[0];
+    assert!(stop.same_route(&other_truck));
+}
+
+#[test]
+fn move_to_other_days() {
+    let mut solution = annealed_solution();
+
+    let once = planned_order_with(&solution, Frequency::Once);
+    let stop = RoutePosition::find_order(&solution, once)[0];
+    let day = if stop.day == DayEnum::Monday { DayEnum::Friday } else { DayEnum::Monday };
+    let after = head(&solution, RoutePosition { day, ..stop });
+    apply_and_check(&mut solution, ManualEdit::Move { stop, after });
+    assert_eq!(RoutePosition::find_order(&solution, once)[0].day, day);
+
+    // the other visit of a twice a week order moves along to the matching day
+    let twice = planned_order_with(&solution, Frequency::Twice);
+    let stop = RoutePosition::find_order(&solution, twice)[0];
+    let (day, other_day) = if stop.day == DayEnum::Monday {
+        (DayEnum::Tuesday, DayEnum::Friday)
+    } else {
+        (DayEnum::Monday, DayEnum::Thursday)
+    };
+    let after = head(&solution, RoutePosition { day, ..stop });
+    apply_and_check(&mut solution, ManualEdit::Move { stop, after });
+    let days: Vec<DayEnum> = RoutePosition::find_order(&solution, twice).iter().map(|visit| visit.day).collect();
+    assert_eq!(days, [day, other_day]);
+
+    let stop = RoutePosition::find_order(&solution, twice)[0];
+    let wednesday = head(&solution, RoutePosition { day: DayEnum::Wednesday, ..stop });
+    let score = solution.score;
+    assert!(ManualEdit::Move { stop, after: wednesday }.apply(&mut solution).is_err());
+    assert_eq!(solution.score, score);
+    solution.check_consistency().unwrap();
+}
+
+#[test]
+fn solution_from_the_trucks_alone() {
+    let solution = annealed_solution();
+    let rebuilt = Solution::from_trucks(solution.truck1.clone(), solution.truck2.clone());
+    rebuilt.check_consistency().unwrap();
+    assert_eq!(rebuilt.score, solution.score);
+    assert_eq!(rebuilt.unfilled_orders.len(), solution.unfilled_orders.len());
+}
+
+fn other(truck: TruckEnum) -> TruckEnum {
+    match truck {
+        TruckEnum::Truck1 => TruckEnum::Truck2,
+        TruckEnum::Truck2 => TruckEnum::Truck1,
+    }
+}
@@ -0,0 +1,58 @@
+//! Applies lots of random moves and checks after every single one that the incremental bookkeeping
+//! still matches a full recalculation.
+//!
+//! This lives in its own test binary, because the unit tests in route.rs fill the global orders
+//! with different frequencies, and the statics can only be set once per process.
+
+mod common;
+
+use common::{STEPS, config, init_from_files, run_and_check};
+use rand::SeedableRng;
+use rand::prelude::SmallRng;
+use route_finder::simulated_annealing::neighbor_chooser::MOVE_COUNT;
+use route_finder::simulated_annealing::simulated_annealing::{
+    SEARCH_WEIGHTS, SimulatedAnnealing, SimulatedAnnealingConfig,
+};
+use route_finder::simulated_annealing::solution::Solution;
+
+#[test]
+fn search_moves_stay_consistent() {
+    init_from_files();
+    for seed in 0..3 {
+        run_and_check(seed, config(50_000.0), SEARCH_WEIGHTS, |_| {});
+    }
+}
+
+#[test]
+fn search_moves_stay_consistent_when_cold() {
+    init_from_files();
+    // At a low temperature almost only improvements get through, which gives us fuller routes
+    run_and_check(3, config(100.0), SEARCH_WEIGHTS, |_| {});
+}
+
+#[test]
+fn every_move_stays_consistent() {
+    init_from_files();
+    for neighbor_move in 0..MOVE_COUNT {
+        // we always need some adds and removes, otherwise most moves have nothing to work with
+        let mut weights = [0; MOVE_COUNT];
+        weights[0] += 1;
+        weights[1] += 1;
+        weights[neighbor_move] += 4;
+        run_and_check(neighbor_move as u64, config(50_000.0), weights, |_| {});
+    }
+}
+
+#[test]
+fn paranoid_mode_passes_on_a_normal_run() {
+    init_from_files();
+    let mut rng = SmallRng::seed_from_u64(0);
+    let config = SimulatedAnnealingConfig {
+        paranoid_interval: Some(100),
+        ..config(50_000.0)
+    };
+    let mut annealing = SimulatedAnnealing::new(&mut rng, config);
+    let mut solution = Solution::new();
+    // this panics if any of the checks fail
+    annealing.run_steps(&mut rng, &mut solution, STEPS);
+}
@@ -0,0 +1,102 @@
+//! Searches with kilometres, fuel, CO2 and the longest day in the score. The data files weigh them at 0,
+//! so the objective is set before anything reads it, which needs its own test binary.
+
+mod common;
+
+use common::{config, init, run_and_check, set_orders};
+use route_finder::OBJECTIVE;
+use route_finder::parser::parse_orderfile;
+use route_finder::printer::json::{RunInfo, SolutionFile};
+use route_finder::resource::{Company, Site};
+use route_finder::simulated_annealing::neighbor_chooser::MOVE_COUNT;
+use route_finder::simulated_annealing::objective::{BalanceMeasure, Objective, TruckType};
+use route_finder::simulated_annealing::score_calculator::{ScoreBreakdown, calculate_balance_cost, calculate_objective_cost};
+use route_finder::simulated_annealing::simulated_annealing::SEARCH_WEIGHTS;
+use route_finder::simulated_annealing::solution::Solution;
+
+fn setup() {
+    let mut order_vec = parse_orderfile().unwrap();
+    // a second dump site, so changing the dump site changes the distance too
+    let dump_site = Company {
+        order: 0,
+        place: "Tweede stort".to_string(),
+        container_count: 0,
+        container_volume: 0,
+        emptying_time: 0,
+        total_container_volume: 0,
+        penalty: 0,
+        allowed_patterns: vec![],
+        site: Some(Site { is_dump: true, home_of: vec![] }),
+        ..order_vec[300].clone()
+    };
+    order_vec.push(dump_site);
+    set_orders(order_vec);
+
+    // Truck 2 is an old one that burns a lot more when it's full
+    let mut objective = Objective::time_only();
+    objective.minutes_per_km = 0.5;
+    objective.minutes_per_liter = 1.5;
+    objective.minutes_per_kg_co2 = 0.25;
+    objective.truck_types[1] = TruckType {
+        name: "Oude vuilniswagen".to_string(),
+        liters_per_km_empty: 0.5,
+        liters_per_km_full: 0.9,
+        kg_co2_per_liter: 2.64,
+    };
+    objective.balance_measure = BalanceMeasure::Max;
+    objective.minutes_per_day_balance = 3.0;
+    objective.minutes_per_truck_balance = 0.5;
+    OBJECTIVE.set(objective).ok();
+}
+
+fn driven_distance(solution: &Solution) -> i32 {
+    solution
+        .truck1
+        .iter()
+        .chain(solution.truck2.iter())
+        .filter(|route| !route.is_empty())
+        .map(|route| route.distance as i32)
+        .sum()
+}
+
+fn run(seed: u64, weights: [i32; MOVE_COUNT]) -> Solution {
+    init(setup);
+    run_and_check(seed, config(50_000.0), weights, |step| {
+        assert_eq!(
+            step.evaluation.distance_delta,
+            driven_distance(step.after) - driven_distance(step.before),
+            "{step}: evaluate did not match the real change in distance"
+        );
+    })
+}
+
+#[test]
+fn every_move_computes_the_objective() {
+    let mut weights = SEARCH_WEIGHTS;
+    weights[9] = 10;
+    weights[10] = 10;
+    weights[11] = 5;
+    let solution = run(0, weights);
+
+    assert!(calculate_objective_cost(&solution) > 0);
+    assert!(calculate_balance_cost(&solution) > 0);
+    let breakdown = ScoreBreakdown::new(&solution);
+    assert_eq!(breakdown.total, solution.score);
+    assert_eq!(breakdown.objective_cost, calculate_objective_cost(&solution));
+    assert_eq!(breakdown.balance_cost, calculate_balance_cost(&solution));
+    assert!(breakdown.emissions.km > 0.0);
+    assert!(breakdown.emissions.kg_co2 > breakdown.emissions.liters);
+}
+
+#[test]
+fn the_objective_is_kept_when_saved() {
+    let solution = run(1, SEARCH_WEIGHTS);
+    let saved = SolutionFile::new(&solution, RunInfo::default());
+    assert_eq!(saved.score.objective_cost, calculate_objective_cost(&solution));
+    assert_eq!(saved.score.balance_cost, calculate_balance_cost(&solution));
+
+    let json = serde_json::to_string(&saved).unwrap();
+    let loaded = serde_json::from_str::<SolutionFile>(&json).unwrap().to_solution().unwrap();
+    assert_eq!(loaded.score, solution.score);
+    assert_eq!(calculate_objective_cost(&loaded), calculate_objective_cost(&solution));
+}
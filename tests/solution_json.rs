@@ -0,0 +1,103 @@
+//! Round trips of the JSON solution format. These need real, annealed solutions,
+//! so just like move_consistency they get their own test binary with the normal order file.
+
+mod common;
+
+use common::init_from_files;
+use rand::SeedableRng;
+use rand::prelude::SmallRng;
+use route_finder::printer::json::{RunInfo, SOLUTION_FORMAT_VERSION, SolutionFile};
+use route_finder::simulated_annealing::neighbor_chooser::MOVE_COUNT;
+use route_finder::simulated_annealing::score_calculator::ScoreBreakdown;
+use route_finder::simulated_annealing::simulated_annealing::{SEARCH_WEIGHTS, SimulatedAnnealing, SimulatedAnnealingConfig};
+use route_finder::simulated_annealing::solution::Solution;
+
+fn annealed_solution() -> (Solution, RunInfo) {
+    init_from_files();
+    let mut rng = SmallRng::seed_from_u64(7);
+    let config = SimulatedAnnealingConfig {
+        seed: Some(7),
+        ..SimulatedAnnealingConfig::headless(50_000.0, 10.0, 10_000, 0.99)
+    };
+    let mut annealing = SimulatedAnnealing::new(&mut rng, config);
+    let mut solution = Solution::new();
+    // stop early, so there are both filled and unfilled orders
+    annealing.run_steps(&mut rng, &mut solution, 20_000);
+    let run = RunInfo {
+        seed: Some(annealing.seed()),
+        parameters: Some(annealing.parameters()),
+    };
+    (solution, run)
+}
+
+#[test]
+fn round_trip_is_exact() {
+    let (solution, run) = annealed_solution();
+    assert!(solution.unfilled_orders.len() > 0);
+    assert!(solution.fulfilled_order_count() > 0);
+
+    let saved = SolutionFile::new(&solution, run.clone());
+    let json = serde_json::to_string(&saved).unwrap();
+    let loaded_file: SolutionFile = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded_file, saved);
+
+    let loaded = loaded_file.to_solution().unwrap();
+    assert_eq!(loaded.score, solution.score);
+    assert_eq!(SolutionFile::new(&loaded, run), saved);
+    assert_eq!(
+        saved.score.travel_time + saved.score.objective_cost + saved.score.balance_cost + saved.score.penalty,
+        saved.score.total
+    );
+}
+
+#[test]
+fn other_versions_are_rejected() {
+    let (solution, run) = annealed_solution();
+    let mut saved = SolutionFile::new(&solution, run);
+    saved.version = SOLUTION_FORMAT_VERSION + 1;
+    assert!(saved.to_solution().is_err());
+}
+
+#[test]
+fn edited_times_are_rejected() {
+    let (solution, run) = annealed_solution();
+    let mut saved = SolutionFile::new(&solution, run);
+    let route = saved.trucks[0]
+        .days
+        .iter_mut()
+        .flat_map(|day| day.routes.iter_mut())
+        .find(|route| !route.orders.is_empty())
+        .unwrap();
+    route.time += 1;
+    assert!(saved.to_solution().is_err());
+}
+
+#[test]
+fn score_breakdown_matches_the_file() {
+    let (solution, run) = annealed_solution();
+    let saved = SolutionFile::new(&solution, run);
+    let breakdown = ScoreBreakdown::new(&solution);
+    assert_eq!(breakdown.total, saved.score.total);
+    assert_eq!(breakdown.penalty, saved.score.penalty);
+    assert_eq!(
+        breakdown.driving_time + breakdown.emptying_time + breakdown.dropoff_time + breakdown.break_time,
+        saved.score.travel_time
+    );
+}
+
+#[test]
+fn files_from_before_the_last_moves_still_load() {
+    let (solution, run) = annealed_solution();
+    let mut json = serde_json::to_value(SolutionFile::new(&solution, run)).unwrap();
+    // back when there were 9 moves, before changing dump sites and unload stops
+    for weights in ["search_weights", "perturbation_weights"] {
+        json["run"]["parameters"][weights].as_array_mut().unwrap().truncate(9);
+    }
+
+    let loaded: SolutionFile = serde_json::from_value(json).unwrap();
+    let parameters = loaded.run.parameters.as_ref().unwrap();
+    assert_eq!(parameters.search_weights[..9], SEARCH_WEIGHTS[..9]);
+    assert_eq!(parameters.search_weights[9..], [0; MOVE_COUNT - 9]);
+    assert_eq!(parameters.perturbation_weights[9..], [0; MOVE_COUNT - 9]);
+    assert_eq!(loaded.to_solution().unwrap().score, solution.score);
+}